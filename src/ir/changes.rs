@@ -0,0 +1,135 @@
+use crate::ir::resources::{resource_dependencies, ResourceInstruction};
+use crate::ir::CloudformationProgramIr;
+use crate::TransmuteError;
+use std::collections::{BTreeMap, BTreeSet};
+
+// ChangeStep is a single resource change needed to go from one template to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeStep {
+    Create(String),
+    Update(String),
+    Delete(String),
+}
+
+// change_plan compares two templates, and orders the changes between them so that each can be
+// applied safely on its own: a resource is created or updated only after everything it depends
+// on in the new template, and deleted only after everything that depended on it in the old one.
+//
+// Creates and updates come first, so nothing is deleted while a resource still refers to it.
+pub fn change_plan(
+    old: &CloudformationProgramIr,
+    new: &CloudformationProgramIr,
+) -> Vec<ChangeStep> {
+    let old_resources: BTreeMap<&str, &ResourceInstruction> =
+        old.resources.iter().map(|r| (r.name.as_str(), r)).collect();
+    let new_resources: BTreeMap<&str, &ResourceInstruction> =
+        new.resources.iter().map(|r| (r.name.as_str(), r)).collect();
+
+    let mut plan = Vec::new();
+    for name in dependency_order_lenient(&new_resources) {
+        match old_resources.get(name) {
+            None => plan.push(ChangeStep::Create(name.to_string())),
+            Some(old_resource) if has_changed(old_resource, new_resources[name]) => {
+                plan.push(ChangeStep::Update(name.to_string()))
+            }
+            Some(_) => {}
+        }
+    }
+    // Dependents have to go before what they depend on, the reverse of creating them.
+    for name in dependency_order_lenient(&old_resources).into_iter().rev() {
+        if !new_resources.contains_key(name) {
+            plan.push(ChangeStep::Delete(name.to_string()));
+        }
+    }
+    plan
+}
+
+fn has_changed(old: &ResourceInstruction, new: &ResourceInstruction) -> bool {
+    old.resource_type != new.resource_type
+        || old.condition != new.condition
+        || old.properties != new.properties
+        || old.depends_on != new.depends_on
+}
+
+// topological_order orders resources so each comes after every resource it refers to, by Ref,
+// Fn::GetAtt or DependsOn, and otherwise by name, which is the order cdk code has to declare
+// them in. Resources that depend on each other in a cycle can't be ordered, and are an error.
+pub fn topological_order(
+    resources: &[ResourceInstruction],
+) -> Result<Vec<&ResourceInstruction>, TransmuteError> {
+    let by_name: BTreeMap<&str, &ResourceInstruction> =
+        resources.iter().map(|r| (r.name.as_str(), r)).collect();
+    let (order, remaining) = dependency_order(&by_name);
+    if !remaining.is_empty() {
+        return Err(TransmuteError::new(
+            format!(
+                "Resources {} depend on each other in a cycle",
+                cycle_members(remaining).join(", ")
+            )
+            .as_str(),
+        ));
+    }
+    Ok(order.into_iter().map(|name| by_name[name]).collect())
+}
+
+// dependency_order_lenient is dependency_order, but resources in a cycle, which CloudFormation
+// rejects, are left for last rather than left out.
+fn dependency_order_lenient<'a>(
+    resources: &BTreeMap<&'a str, &ResourceInstruction>,
+) -> Vec<&'a str> {
+    let (mut order, remaining) = dependency_order(resources);
+    order.extend(remaining.keys());
+    order
+}
+
+// cycle_members narrows the resources that couldn't be ordered down to those in a cycle, by
+// dropping those that only depend on a cycle, which nothing else that remains depends on.
+fn cycle_members(mut remaining: BTreeMap<&str, BTreeSet<String>>) -> Vec<&str> {
+    loop {
+        let depended_on: BTreeSet<String> = remaining.values().flatten().cloned().collect();
+        let before = remaining.len();
+        remaining.retain(|name, _| depended_on.contains(*name));
+        if remaining.len() == before {
+            return remaining.keys().copied().collect();
+        }
+    }
+}
+
+// dependency_order orders resources so each comes after the resources it depends on, and
+// otherwise by name. Resources that can't be ordered, as they are in or depend on a cycle, are
+// returned separately, along with what each still depends on.
+#[allow(clippy::type_complexity)]
+fn dependency_order<'a>(
+    resources: &BTreeMap<&'a str, &ResourceInstruction>,
+) -> (Vec<&'a str>, BTreeMap<&'a str, BTreeSet<String>>) {
+    let mut remaining: BTreeMap<&'a str, BTreeSet<String>> = resources
+        .iter()
+        .map(|(name, resource)| {
+            let dependencies = resource_dependencies(resource)
+                .into_iter()
+                .filter(|x| resources.contains_key(x.as_str()) && x != name)
+                .collect();
+            (*name, dependencies)
+        })
+        .collect();
+
+    let mut order = Vec::new();
+    while !remaining.is_empty() {
+        let ready: Vec<&'a str> = remaining
+            .iter()
+            .filter(|(_, dependencies)| dependencies.is_empty())
+            .map(|(name, _)| *name)
+            .collect();
+        if ready.is_empty() {
+            break;
+        }
+        for name in ready {
+            remaining.remove(name);
+            for dependencies in remaining.values_mut() {
+                dependencies.remove(name);
+            }
+            order.push(name);
+        }
+    }
+    (order, remaining)
+}
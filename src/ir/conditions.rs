@@ -1,6 +1,7 @@
+use crate::ir::mappings;
 use crate::ir::reference::{Origin, Reference};
 use crate::parser::condition::{ConditionParseTree, ConditionValue, ConditionsParseTree};
-use crate::CloudformationParseTree;
+use crate::{CloudformationParseTree, TransmuteError};
 use std::collections::HashMap;
 
 // ConditionInstructions are simple assignment + boolean
@@ -37,19 +38,58 @@ impl ConditionIr {
     pub fn is_simple(&self) -> bool {
         matches!(self, ConditionIr::Ref(_) | ConditionIr::Str(_))
     }
+
+    // as_str is the value of a string literal, or None if it can only be known at deploy time.
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            ConditionIr::Str(x) => Option::Some(x),
+            _ => Option::None,
+        }
+    }
 }
 
-pub fn translate_conditions(parse_tree: &CloudformationParseTree) -> Vec<ConditionInstruction> {
+pub fn translate_conditions(
+    parse_tree: &CloudformationParseTree,
+) -> Result<Vec<ConditionInstruction>, TransmuteError> {
     let mut list = Vec::new();
     for cond in determine_order(&parse_tree.conditions) {
         let ir = translate_ir(&cond.val);
+        validate_lookups(&ir, parse_tree)?;
         list.push(ConditionInstruction {
             name: cond.name,
             value: ir,
         });
     }
 
-    list
+    Ok(list)
+}
+
+// validate_lookups runs every Fn::FindInMap in a condition through the same
+// validation that resources use.
+fn validate_lookups(
+    value: &ConditionIr,
+    parse_tree: &CloudformationParseTree,
+) -> Result<(), TransmuteError> {
+    match value {
+        ConditionIr::And(x) | ConditionIr::Or(x) => {
+            for cond in x.iter() {
+                validate_lookups(cond, parse_tree)?;
+            }
+            Ok(())
+        }
+        ConditionIr::Equals(a, b) => {
+            validate_lookups(a, parse_tree)?;
+            validate_lookups(b, parse_tree)
+        }
+        ConditionIr::Not(x) => validate_lookups(x, parse_tree),
+        ConditionIr::Map(name, l1, l2) => match name.as_ref() {
+            ConditionIr::Str(name) => {
+                mappings::validate_lookup(parse_tree, name, l1.as_str(), l2.as_str())
+            }
+            _ => Ok(()),
+        },
+        ConditionIr::Str(_) | ConditionIr::Ref(_) => Ok(()),
+    }
 }
 
 fn translate_ir(value: &ConditionValue) -> ConditionIr {
@@ -87,15 +127,30 @@ fn translate_ir(value: &ConditionValue) -> ConditionIr {
             if let Option::Some(s) = Reference::match_pseudo_parameter(x) {
                 origin = Origin::PseudoParameter(s);
             }
-            ConditionIr::Ref(Reference {
-                origin,
-                name: x.clone(),
-            })
+            ConditionIr::Ref(Reference::new(x, origin))
         }
-        ConditionValue::Condition(x) => ConditionIr::Ref(Reference {
-            origin: Origin::Condition,
-            name: x.clone(),
-        }),
+        ConditionValue::Condition(x) => ConditionIr::Ref(Reference::new(x, Origin::Condition)),
+    }
+}
+
+// implies is true when condition a being true guarantees condition b is also true. Only
+// Fn::And, Fn::Or and Condition references are followed, so false means "not known to imply".
+pub fn implies(conditions: &[ConditionInstruction], a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    match conditions.iter().find(|cond| cond.name == a) {
+        Some(cond) => ir_implies(conditions, &cond.value, b),
+        None => false,
+    }
+}
+
+fn ir_implies(conditions: &[ConditionInstruction], value: &ConditionIr, b: &str) -> bool {
+    match value {
+        ConditionIr::Ref(x) if x.origin == Origin::Condition => implies(conditions, &x.name, b),
+        ConditionIr::And(x) => x.iter().any(|v| ir_implies(conditions, v, b)),
+        ConditionIr::Or(x) => !x.is_empty() && x.iter().all(|v| ir_implies(conditions, v, b)),
+        _ => false,
     }
 }
 
@@ -226,7 +281,7 @@ impl ConditionNode {
 
 #[cfg(test)]
 mod tests {
-    use crate::ir::conditions::{translate_ir, ConditionIr};
+    use crate::ir::conditions::{implies, translate_ir, ConditionInstruction, ConditionIr};
     use crate::ir::reference::{Origin, PseudoParameter, Reference};
     use crate::parser::condition::ConditionValue;
 
@@ -266,4 +321,31 @@ mod tests {
             translate_ir(&ConditionValue::Str("hi".into()))
         );
     }
+
+    #[test]
+    fn test_implies() {
+        let condition = |name: &str| ConditionIr::Ref(Reference::new(name, Origin::Condition));
+        let conditions = vec![
+            ConditionInstruction {
+                name: "IsProd".into(),
+                value: ConditionIr::Equals(
+                    Box::new(ConditionIr::Str("prod".into())),
+                    Box::new(ConditionIr::Ref(Reference::new("Stage", Origin::Parameter))),
+                ),
+            },
+            ConditionInstruction {
+                name: "IsProdInUsEast1".into(),
+                value: ConditionIr::And(vec![condition("IsProd"), condition("IsUsEast1")]),
+            },
+            ConditionInstruction {
+                name: "IsProdOrStaging".into(),
+                value: ConditionIr::Or(vec![condition("IsProd"), condition("IsStaging")]),
+            },
+        ];
+
+        assert!(implies(&conditions, "IsProd", "IsProd"));
+        assert!(implies(&conditions, "IsProdInUsEast1", "IsProd"));
+        assert!(!implies(&conditions, "IsProd", "IsProdInUsEast1"));
+        assert!(!implies(&conditions, "IsProdOrStaging", "IsProd"));
+    }
 }
@@ -1,3 +1,4 @@
+use crate::parser::parameters::Parameter;
 use crate::CloudformationParseTree;
 use voca_rs::case::camel_case;
 
@@ -15,7 +16,12 @@ impl Constructor {
         for (name, param) in parse_tree.parameters.params.iter() {
             inputs.push(ConstructorParameter {
                 name: camel_case(name),
-                constructor_type: param.parameter_type.to_string(),
+                constructor_type: typescript_type(param).to_string(),
+                logical_name: name.to_string(),
+                parameter_type: param.parameter_type.to_string(),
+                default: param.default.clone(),
+                allowed_values: param.allowed_values.clone(),
+                description: param.description.clone(),
             })
         }
         Constructor { inputs }
@@ -30,4 +36,24 @@ impl Default for Constructor {
 pub struct ConstructorParameter {
     pub name: String,
     pub constructor_type: String,
+
+    // The parameter as it was declared in the template.
+    pub logical_name: String,
+    pub parameter_type: String,
+    pub default: Option<String>,
+    pub allowed_values: Option<Vec<String>>,
+    pub description: Option<String>,
+}
+
+// typescript_type maps a parameter's CloudFormation type onto the type its prop will have.
+// List parameters must become arrays, so their references can be passed to list properties.
+fn typescript_type(param: &Parameter) -> &'static str {
+    if param.is_list() {
+        return "string[]";
+    }
+
+    match param.parameter_type.as_str() {
+        "Number" => "number",
+        _ => "string",
+    }
 }
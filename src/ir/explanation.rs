@@ -0,0 +1,17 @@
+// Explanation records why a translation decision was made, so a user can check the
+// output against their intent instead of trusting it blindly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    // The logical id of the resource the decision was made in.
+    pub resource: String,
+    pub message: String,
+}
+
+impl Explanation {
+    pub fn new(resource: &str, message: String) -> Explanation {
+        Explanation {
+            resource: resource.to_string(),
+            message,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::ir::resources::ResourceIr;
+
+// ImportResolver supplies the values of exports that are already known, e.g. read from a
+// deployed environment, so a Fn::ImportValue of them can be inlined.
+pub trait ImportResolver {
+    // resolve returns the value exported under name, or None if it isn't known.
+    fn resolve(&self, name: &str) -> Option<ResourceIr>;
+}
+
+// resolve replaces every resolvable Fn::ImportValue within value. Only imports of a literal
+// export name can be resolved; a name built by an intrinsic is only known at deploy time.
+pub fn resolve(value: &mut ResourceIr, resolver: &dyn ImportResolver) {
+    if let ResourceIr::ImportValue(name) = value {
+        if let ResourceIr::String(name) = name.as_ref() {
+            if let Some(resolved) = resolver.resolve(name) {
+                *value = resolved;
+            }
+        }
+        return;
+    }
+
+    match value {
+        ResourceIr::Null
+        | ResourceIr::NoValue
+        | ResourceIr::Bool(_)
+        | ResourceIr::Number(_)
+        | ResourceIr::Double(_)
+        | ResourceIr::String(_)
+        | ResourceIr::Ref(_)
+        | ResourceIr::GetAtt(_, _)
+        | ResourceIr::ImportValue(_) => {}
+        ResourceIr::Array(_, arr) | ResourceIr::Join(_, arr) | ResourceIr::Sub(arr) => {
+            arr.iter_mut().for_each(|x| resolve(x, resolver))
+        }
+        ResourceIr::Object(_, o) => o.values_mut().for_each(|x| resolve(x, resolver)),
+        ResourceIr::If(_, true_expr, false_expr) => {
+            resolve(true_expr, resolver);
+            resolve(false_expr, resolver);
+        }
+        ResourceIr::Map(mapper, first, second) => {
+            resolve(mapper, resolver);
+            resolve(first, resolver);
+            resolve(second, resolver);
+        }
+        ResourceIr::Select(index, list) => {
+            resolve(index, resolver);
+            resolve(list, resolver);
+        }
+        ResourceIr::GetAZs(x)
+        | ResourceIr::ToJsonString(x)
+        | ResourceIr::Split(_, x)
+        | ResourceIr::Base64(x) => resolve(x, resolver),
+    }
+}
+
+// has_imports is true when value still has a Fn::ImportValue within it, i.e. one that was not
+// resolved, whose value is only known at deploy time.
+pub fn has_imports(value: &ResourceIr) -> bool {
+    match value {
+        ResourceIr::ImportValue(_) => true,
+        ResourceIr::Null
+        | ResourceIr::NoValue
+        | ResourceIr::Bool(_)
+        | ResourceIr::Number(_)
+        | ResourceIr::Double(_)
+        | ResourceIr::String(_)
+        | ResourceIr::Ref(_)
+        | ResourceIr::GetAtt(_, _) => false,
+        ResourceIr::Array(_, arr) | ResourceIr::Join(_, arr) | ResourceIr::Sub(arr) => {
+            arr.iter().any(has_imports)
+        }
+        ResourceIr::Object(_, o) => o.values().any(has_imports),
+        ResourceIr::If(_, true_expr, false_expr) => {
+            has_imports(true_expr) || has_imports(false_expr)
+        }
+        ResourceIr::Map(mapper, first, second) => {
+            has_imports(mapper) || has_imports(first) || has_imports(second)
+        }
+        ResourceIr::Select(index, list) => has_imports(index) || has_imports(list),
+        ResourceIr::GetAZs(x)
+        | ResourceIr::ToJsonString(x)
+        | ResourceIr::Split(_, x)
+        | ResourceIr::Base64(x) => has_imports(x),
+    }
+}
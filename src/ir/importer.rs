@@ -1,3 +1,4 @@
+use crate::ir::resources::is_custom_resource;
 use crate::CloudformationParseTree;
 use std::collections::HashSet;
 
@@ -10,6 +11,10 @@ impl Importer {
         let mut type_names = HashSet::new();
         for resource in parse_tree.resources.resources.iter() {
             let name = &resource.resource_type;
+            // Custom resources are synthesized with the cdk's own CfnResource.
+            if is_custom_resource(name) {
+                continue;
+            }
             let mut split_ref = name.split("::");
 
             // These must always exist.
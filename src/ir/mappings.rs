@@ -1,27 +1,123 @@
+use crate::ir::resources::{translate_mapping_value, ResourceIr};
 use crate::parser::lookup_table::MappingInnerValue;
-use crate::CloudformationParseTree;
+use crate::parser::resource::ResourceValue;
+use crate::specification::Specification;
+use crate::{CloudformationParseTree, TransmuteError};
 use std::collections::HashMap;
 
 pub struct MappingInstruction {
     pub name: String,
-    pub map: HashMap<String, HashMap<String, MappingInnerValue>>,
+    pub map: HashMap<String, HashMap<String, ResourceIr>>,
 }
 
 impl MappingInstruction {
-    pub fn find_first_type(&self) -> &MappingInnerValue {
-        let value = self.map.values().next().unwrap();
-        let inner_value = value.values().next().unwrap();
-        inner_value
+    // find_first_type returns a value of the mapping, or None when the mapping has none.
+    pub fn find_first_type(&self) -> Option<&ResourceIr> {
+        self.map.values().find_map(|inner| inner.values().next())
     }
 }
-pub fn translate(parse_tree: &CloudformationParseTree) -> Vec<MappingInstruction> {
+
+// translate_mappings translates the Mappings section. Mappings can't hold intrinsics, so each
+// value is a string, a number, or a list of strings.
+pub fn translate_mappings(
+    parse_tree: &CloudformationParseTree,
+    specification: &Specification,
+) -> Result<Vec<MappingInstruction>, TransmuteError> {
     let mapping_parse_tree = &parse_tree.mappings;
     let mut instructions = Vec::new();
     for (name, map) in mapping_parse_tree.mappings.iter() {
+        let mut outer = HashMap::new();
+        for (outer_key, inner_mapping) in map.mappings.iter() {
+            let mut inner = HashMap::new();
+            for (key, value) in inner_mapping.iter() {
+                let ir = translate_mapping_value(parse_tree, specification, name, &to_value(value))
+                    .map_err(|err| {
+                        err.within(key)
+                            .within(outer_key)
+                            .within(name)
+                            .within("Mappings")
+                    })?;
+                inner.insert(key.to_string(), ir);
+            }
+            outer.insert(outer_key.to_string(), inner);
+        }
         instructions.push(MappingInstruction {
             name: name.to_string(),
-            map: map.mappings.clone(),
+            map: outer,
         })
     }
-    instructions
+    Ok(instructions)
+}
+
+fn to_value(value: &MappingInnerValue) -> ResourceValue {
+    match value {
+        MappingInnerValue::String(x) => ResourceValue::String(x.to_string()),
+        MappingInnerValue::Number(x) => ResourceValue::Number(*x),
+        MappingInnerValue::Double(x) => ResourceValue::Double(*x),
+        MappingInnerValue::List(x) => ResourceValue::Array(
+            x.iter()
+                .map(|x| ResourceValue::String(x.to_string()))
+                .collect(),
+        ),
+    }
+}
+
+// validate_lookup checks a Fn::FindInMap against the Mappings section. Keys are only checked when
+// they are known before deploy time, so callers pass None for keys that are intrinsics.
+pub fn validate_lookup(
+    parse_tree: &CloudformationParseTree,
+    mapping_name: &str,
+    top_level_key: Option<&str>,
+    second_level_key: Option<&str>,
+) -> Result<(), TransmuteError> {
+    let mapping = match parse_tree.mappings.mappings.get(mapping_name) {
+        None => {
+            return Err(TransmuteError::new(
+                format!("Fn::FindInMap refers to missing mapping {}", mapping_name).as_str(),
+            ))
+        }
+        Some(x) => x,
+    };
+
+    let top_level_key = match top_level_key {
+        // Without the top level key, the second level key must at least exist somewhere.
+        None => {
+            return match second_level_key {
+                Some(x) if !mapping.mappings.values().any(|inner| inner.contains_key(x)) => {
+                    Err(TransmuteError::new(
+                        format!(
+                            "Fn::FindInMap refers to missing key {} in mapping {}",
+                            x, mapping_name
+                        )
+                        .as_str(),
+                    ))
+                }
+                _ => Ok(()),
+            }
+        }
+        Some(x) => x,
+    };
+    let inner_mapping = match mapping.mappings.get(top_level_key) {
+        None => {
+            return Err(TransmuteError::new(
+                format!(
+                    "Fn::FindInMap refers to missing key {} in mapping {}",
+                    top_level_key, mapping_name
+                )
+                .as_str(),
+            ))
+        }
+        Some(x) => x,
+    };
+
+    match second_level_key {
+        Some(x) if !inner_mapping.contains_key(x) => Err(TransmuteError::new(
+            format!(
+                "Fn::FindInMap refers to missing key {} in mapping {}.{}",
+                x, mapping_name, top_level_key
+            )
+            .as_str(),
+        )),
+        _ => Ok(()),
+    }
 }
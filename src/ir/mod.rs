@@ -1,23 +1,78 @@
 use crate::ir::conditions::ConditionInstruction;
 use crate::ir::constructor::Constructor;
+use crate::ir::explanation::Explanation;
+use crate::ir::import_values::ImportResolver;
 use crate::ir::importer::ImportInstruction;
 use crate::ir::mappings::MappingInstruction;
-use crate::ir::resources::ResourceInstruction;
+use crate::ir::outputs::OutputInstruction;
+use crate::ir::parameters::ParameterInstruction;
+use crate::ir::resources::{ResourceInstruction, ResourceIr};
+#[cfg(feature = "bundled-spec")]
+use crate::specification::spec;
+use crate::specification::Specification;
 use crate::{CloudformationParseTree, TransmuteError};
+use std::cell::RefCell;
+use std::collections::HashMap;
 
+pub mod changes;
 pub mod conditions;
 pub mod constructor;
+pub mod explanation;
+pub mod import_values;
 pub mod importer;
 pub mod mappings;
+pub mod names;
+pub mod outputs;
+pub mod parameters;
+pub mod permissions;
 pub mod reference;
 pub mod resources;
+pub mod suggestions;
+pub mod usage;
+
+// TranslationOptions changes how a parse tree is translated, for teams with their own conventions.
+#[derive(Debug, Default, Clone)]
+pub struct TranslationOptions {
+    // drop_empty_strings treats properties set to "" as unset, and leaves them out.
+    pub drop_empty_strings: bool,
+    // allowed_values lists the only values a property may be set to, keyed by the property's
+    // full name, e.g. "AWS::S3::Bucket.AccessControl". The specification doesn't carry these,
+    // so they are left to the user. Literal values outside of the list are warned about.
+    pub allowed_values: HashMap<String, Vec<String>>,
+    // type_allowlist, when not empty, limits translation to the resource types it matches.
+    // type_denylist skips the resource types it matches. Both take full types, or prefixes
+    // ending in *, e.g. "AWS::IAM::*", for migrating a template a few types at a time.
+    pub type_allowlist: Vec<String>,
+    pub type_denylist: Vec<String>,
+    // fix_property_casing matches property names against the specification regardless of
+    // case, and corrects miscased ones, e.g. bucketName, with a warning.
+    pub fix_property_casing: bool,
+    // is_nested marks the template as one deployed as a nested stack, whose outputs are read
+    // by the parent stack, rather than exported. Whether it is can't be told from the
+    // template alone.
+    pub is_nested: bool,
+    // check_references fails translation when a Ref or Fn::GetAtt names a resource that isn't
+    // in the template, which would otherwise only be found at deploy time.
+    pub check_references: bool,
+    // translate_unknown_types translates resources whose type isn't in the specification, e.g.
+    // one newer than the bundled specification, as plain json with a TODO, rather than failing.
+    pub translate_unknown_types: bool,
+    // list_ref_types are the resource types, most often custom resources, a Ref to which
+    // returns a list. The specification doesn't say what a Ref returns, so a Ref to a resource
+    // is otherwise taken to return a single value, even where a list is expected.
+    pub list_ref_types: Vec<String>,
+}
 
 pub struct CloudformationProgramIr {
     pub imports: Vec<ImportInstruction>,
     pub constructor: Constructor,
+    pub parameters: Vec<ParameterInstruction>,
     pub conditions: Vec<ConditionInstruction>,
     pub mappings: Vec<MappingInstruction>,
     pub resources: Vec<ResourceInstruction>,
+    pub outputs: Vec<OutputInstruction>,
+    // warnings are parts of the template that could be translated, but not faithfully.
+    pub warnings: Vec<String>,
 }
 
 impl CloudformationProgramIr {
@@ -25,29 +80,218 @@ impl CloudformationProgramIr {
         CloudformationProgramIr {
             imports: Vec::new(),
             constructor: Constructor::new(),
+            parameters: Vec::new(),
             conditions: Vec::new(),
             mappings: Vec::new(),
             resources: Vec::new(),
+            outputs: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
     // new_from_parse_tree takes a parse tree and translates it fully into Intermediate representation.
     // because there could be incorrect semantics, Result::Error can only happen on semantic error,
     // not parsing errors.
+    #[cfg(feature = "bundled-spec")]
     pub fn new_from_parse_tree(
         parse_tree: &CloudformationParseTree,
     ) -> Result<CloudformationProgramIr, TransmuteError> {
-        let conditions = conditions::translate_conditions(parse_tree);
+        Self::translate(
+            parse_tree,
+            &TranslationOptions::default(),
+            spec(),
+            Option::None,
+        )
+    }
+
+    #[cfg(feature = "bundled-spec")]
+    pub fn new_from_parse_tree_with_options(
+        parse_tree: &CloudformationParseTree,
+        options: &TranslationOptions,
+    ) -> Result<CloudformationProgramIr, TransmuteError> {
+        Self::translate(parse_tree, options, spec(), Option::None)
+    }
+
+    // new_from_parse_tree_with_specification translates against a specification supplied by
+    // the caller, rather than the bundled one.
+    pub fn new_from_parse_tree_with_specification(
+        parse_tree: &CloudformationParseTree,
+        options: &TranslationOptions,
+        specification: &Specification,
+    ) -> Result<CloudformationProgramIr, TransmuteError> {
+        Self::translate(parse_tree, options, specification, Option::None)
+    }
+
+    // explain_from_parse_tree is new_from_parse_tree, but also returns the reason behind
+    // each translation decision, for debugging the output.
+    #[cfg(feature = "bundled-spec")]
+    pub fn explain_from_parse_tree(
+        parse_tree: &CloudformationParseTree,
+    ) -> Result<(CloudformationProgramIr, Vec<Explanation>), TransmuteError> {
+        let explanations = RefCell::new(Vec::new());
+        let ir = Self::translate(
+            parse_tree,
+            &TranslationOptions::default(),
+            spec(),
+            Option::Some(&explanations),
+        )?;
+        Ok((ir, explanations.into_inner()))
+    }
+
+    // attach_comments re-attaches comments from the source template to the resources they
+    // were written on, so they can be re-emitted in the output.
+    pub fn attach_comments(&mut self, comments: &HashMap<String, Vec<String>>) {
+        for resource in self.resources.iter_mut() {
+            if let Some(resource_comments) = comments.get(&resource.name) {
+                resource.comments = resource_comments.clone();
+            }
+        }
+    }
+
+    // resolve_imports replaces each Fn::ImportValue the resolver knows the value of with that
+    // value, wherever it is in the resources or outputs. Imports it doesn't know are left as
+    // they are.
+    pub fn resolve_imports(&mut self, resolver: &dyn ImportResolver) {
+        for value in self.values_mut() {
+            import_values::resolve(value, resolver);
+        }
+    }
+
+    // inline_single_use_parameters replaces the Ref to each parameter that is only used once,
+    // has a default and isn't NoEcho with its default, and removes the parameter. Parameters
+    // used by a condition are kept, as conditions compare against the value as it is passed in.
+    pub fn inline_single_use_parameters(&mut self) {
+        let mut condition_refs = HashMap::new();
+        for cond in self.conditions.iter() {
+            parameters::count_condition_parameter_refs(&cond.value, &mut condition_refs);
+        }
+        let mut counts = HashMap::new();
+        for value in self.values() {
+            parameters::count_parameter_refs(value, &mut counts);
+        }
+
+        let inlined: Vec<(String, ResourceIr)> = self
+            .parameters
+            .iter()
+            .filter(|x| !x.no_echo && counts.get(&x.name) == Some(&1))
+            .filter(|x| !condition_refs.contains_key(&x.name))
+            .filter_map(|x| x.default.as_ref().map(|d| (x.name.to_string(), d.clone())))
+            .collect();
+        for (name, default) in inlined.iter() {
+            for value in self.values_mut() {
+                parameters::inline_parameter(value, name, default);
+            }
+            self.parameters.retain(|x| &x.name != name);
+            self.constructor.inputs.retain(|x| &x.logical_name != name);
+            self.warnings.push(format!(
+                "Parameter {} was only used once, so it was replaced with its default",
+                name
+            ));
+        }
+    }
+
+    // values are every value in the program a parameter can be referenced from, or an export
+    // imported into, other than conditions.
+    fn values(&self) -> impl Iterator<Item = &ResourceIr> {
+        let resources = self.resources.iter().flat_map(|x| {
+            x.properties
+                .values()
+                .chain(x.metadata.iter())
+                .chain(x.creation_policy.iter())
+        });
+        let outputs = self
+            .outputs
+            .iter()
+            .flat_map(|x| std::iter::once(&x.value).chain(x.export_name.iter()));
+        resources.chain(outputs)
+    }
+
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut ResourceIr> {
+        let resources = self.resources.iter_mut().flat_map(|x| {
+            x.properties
+                .values_mut()
+                .chain(x.metadata.iter_mut())
+                .chain(x.creation_policy.iter_mut())
+        });
+        let outputs = self
+            .outputs
+            .iter_mut()
+            .flat_map(|x| std::iter::once(&mut x.value).chain(x.export_name.iter_mut()));
+        resources.chain(outputs)
+    }
+
+    fn translate(
+        parse_tree: &CloudformationParseTree,
+        options: &TranslationOptions,
+        specification: &Specification,
+        explanations: Option<&RefCell<Vec<Explanation>>>,
+    ) -> Result<CloudformationProgramIr, TransmuteError> {
+        let conditions = conditions::translate_conditions(parse_tree)?;
         let imports = importer::Importer::translate(parse_tree);
         let constructor = constructor::Constructor::translate(parse_tree);
-        let mappings = mappings::translate(parse_tree);
-        let resources = resources::translates_resources(parse_tree);
+        let parameters = parameters::translate_parameters(parse_tree)?;
+        let mappings = mappings::translate_mappings(parse_tree, specification)?;
+        let casing_fixes = RefCell::new(Vec::new());
+        let coercions = RefCell::new(Vec::new());
+        let skipped = resources::skip_resource_types(
+            parse_tree,
+            &options.type_allowlist,
+            &options.type_denylist,
+        );
+        let context = resources::ResourcesContext {
+            skipped: &skipped,
+            explanations,
+            casing_fixes: match options.fix_property_casing {
+                true => Option::Some(&casing_fixes),
+                false => Option::None,
+            },
+            coercions: Option::Some(&coercions),
+            translate_unknown_types: options.translate_unknown_types,
+            list_ref_types: &options.list_ref_types,
+        };
+        let mut resources = resources::translates_resources(parse_tree, specification, &context)?;
+        if options.drop_empty_strings {
+            for resource in resources.iter_mut() {
+                resources::drop_empty_strings(&mut resource.properties);
+            }
+        }
+        let outputs = outputs::translate_outputs(parse_tree, specification, explanations)?;
+        let unresolved = resources::find_unresolved_sub_variables(parse_tree, &resources);
+        if options.check_references && !unresolved.is_empty() {
+            return Err(TransmuteError::new(&unresolved.join("; ")));
+        }
+        if options.check_references {
+            let dangling = resources::find_dangling_references(parse_tree, &resources, &outputs);
+            if !dangling.is_empty() {
+                return Err(TransmuteError::new(&dangling.join("; ")));
+            }
+        }
+        let mut warnings = parse_tree.warnings.clone();
+        warnings.extend(unresolved);
+        warnings.extend(casing_fixes.into_inner());
+        warnings.extend(coercions.into_inner());
+        if options.is_nested {
+            warnings.extend(outputs::find_nested_exports(&outputs));
+        }
+        warnings.extend(resources::find_skipped_references(
+            parse_tree, &resources, &skipped,
+        ));
+        warnings.extend(resources::find_warnings(&resources, &conditions));
+        warnings.extend(resources::find_ordering_hazards(&resources));
+        warnings.extend(resources::find_missing_required(&resources, specification));
+        warnings.extend(resources::find_disallowed_values(
+            &resources,
+            &options.allowed_values,
+        ));
         Ok(CloudformationProgramIr {
             imports,
             constructor,
+            parameters,
             conditions,
             mappings,
             resources,
+            outputs,
+            warnings,
         })
     }
 }
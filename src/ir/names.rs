@@ -0,0 +1,48 @@
+use crate::ir::resources::{ResourceInstruction, ResourceIr};
+
+// PhysicalName is a name-like property (BucketName, FunctionName, ...) of a resource.
+// Hardcoded physical names prevent a stack from being deployed twice in one account, so
+// governance tooling wants to know where they are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhysicalName {
+    pub resource: String,
+    pub property: String,
+    pub value: NameValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NameValue {
+    // The name is hardcoded in the template.
+    Literal(String),
+    // The name is generated by an intrinsic function, e.g. a Fn::Sub of the stack name.
+    Intrinsic,
+}
+
+// find_physical_names collects every top level property whose name ends in "Name".
+pub fn find_physical_names(resource_instructions: &[ResourceInstruction]) -> Vec<PhysicalName> {
+    let mut names = Vec::new();
+    for resource in resource_instructions.iter() {
+        let mut properties: Vec<(&String, &ResourceIr)> = resource
+            .properties
+            .iter()
+            .filter(|(property, _)| property.ends_with("Name"))
+            .collect();
+        properties.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (property, value) in properties {
+            let value = match value {
+                ResourceIr::Null | ResourceIr::NoValue => continue,
+                ResourceIr::String(x) => NameValue::Literal(x.to_string()),
+                ResourceIr::Number(x) => NameValue::Literal(x.to_string()),
+                ResourceIr::Double(x) => NameValue::Literal(x.to_string()),
+                _ => NameValue::Intrinsic,
+            };
+            names.push(PhysicalName {
+                resource: resource.name.to_string(),
+                property: property.to_string(),
+                value,
+            });
+        }
+    }
+    names
+}
@@ -0,0 +1,66 @@
+use crate::ir::explanation::Explanation;
+use crate::ir::resources::{translate_json, ResourceIr};
+use crate::specification::Specification;
+use crate::{CloudformationParseTree, TransmuteError};
+use std::cell::RefCell;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputInstruction {
+    pub name: String,
+    pub condition: Option<String>,
+    pub value: ResourceIr,
+    pub description: Option<String>,
+    // export_name is the name other stacks import the value by, when it is exported.
+    pub export_name: Option<ResourceIr>,
+}
+
+// translate_outputs translates the Outputs section. Values, and export names, which are often
+// a Fn::Sub of the stack name, can hold any intrinsic, so both are translated like properties.
+pub fn translate_outputs(
+    parse_tree: &CloudformationParseTree,
+    specification: &Specification,
+    explanations: Option<&RefCell<Vec<Explanation>>>,
+) -> Result<Vec<OutputInstruction>, TransmuteError> {
+    let mut outputs = Vec::new();
+    for output in parse_tree.outputs.outputs.iter() {
+        let translate = |value, segment| {
+            translate_json(
+                parse_tree,
+                specification,
+                &output.name,
+                "",
+                value,
+                explanations,
+            )
+            .map_err(|err| err.within(segment).within(&output.name).within("Outputs"))
+        };
+        let export_name = match output.export_name.as_ref() {
+            None => Option::None,
+            Some(x) => Option::Some(translate(x, "Export")?),
+        };
+        outputs.push(OutputInstruction {
+            name: output.name.to_string(),
+            condition: output.condition.clone(),
+            value: translate(&output.value, "Value")?,
+            description: output.description.clone(),
+            export_name,
+        });
+    }
+    Ok(outputs)
+}
+
+// find_nested_exports warns about each exported output of a nested stack template. Exports are
+// global to the account and region, so a nested stack deployed by more than one parent would
+// export the same name twice and fail to deploy.
+pub fn find_nested_exports(outputs: &[OutputInstruction]) -> Vec<String> {
+    outputs
+        .iter()
+        .filter(|x| x.export_name.is_some())
+        .map(|x| {
+            format!(
+                "Output {} has an Export, which nested stacks should not use, the parent stack can read the output instead",
+                x.name
+            )
+        })
+        .collect()
+}
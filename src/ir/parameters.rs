@@ -0,0 +1,209 @@
+use crate::ir::conditions::ConditionIr;
+use crate::ir::reference::Origin;
+use crate::ir::resources::{is_integer_text, ResourceIr};
+use crate::parser::parameters::{is_list_type, Parameter};
+use crate::specification::{Complexity, SimpleType};
+use crate::{CloudformationParseTree, TransmuteError};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterInstruction {
+    pub name: String,
+    pub parameter_type: String,
+    pub default: Option<ResourceIr>,
+    pub allowed_values: Option<Vec<String>>,
+    pub no_echo: bool,
+    pub description: Option<String>,
+    pub constraints: Map<String, Value>,
+}
+
+impl ParameterInstruction {
+    // is_list is true when a Ref to the parameter returns a list, e.g. a CommaDelimitedList.
+    pub fn is_list(&self) -> bool {
+        is_list_type(&self.parameter_type)
+    }
+}
+
+// translate_parameters translates the Parameters section, in order of name.
+pub fn translate_parameters(
+    parse_tree: &CloudformationParseTree,
+) -> Result<Vec<ParameterInstruction>, TransmuteError> {
+    let mut parameters = Vec::new();
+    for (name, param) in parse_tree.parameters.params.iter() {
+        let default = match param.default.as_ref() {
+            None => Option::None,
+            Some(x) => Option::Some(translate_default(param, x)?),
+        };
+        parameters.push(ParameterInstruction {
+            name: name.to_string(),
+            parameter_type: param.parameter_type.to_string(),
+            default,
+            allowed_values: param.allowed_values.clone(),
+            no_echo: param.no_echo,
+            description: param.description.clone(),
+            constraints: param.constraints.clone(),
+        });
+    }
+    parameters.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(parameters)
+}
+
+// translate_default gives a default the type a Ref to its parameter has. Defaults are always
+// written as strings or numbers, but a list parameter's default is a comma separated list.
+fn translate_default(param: &Parameter, default: &str) -> Result<ResourceIr, TransmuteError> {
+    // Defaults are stored as the json text they were declared with.
+    let text = match serde_json::from_str(default) {
+        Ok(Value::String(x)) => x,
+        Ok(Value::Number(x)) => x.to_string(),
+        _ => {
+            return Err(TransmuteError {
+                details: format!(
+                    "Default must be a string or a number {}",
+                    param.logical_name
+                ),
+                path: Vec::new(),
+            })
+        }
+    };
+
+    if param.is_list() {
+        let items = text
+            .split(',')
+            .map(|x| ResourceIr::String(x.trim().to_string()))
+            .collect();
+        return Ok(ResourceIr::Array(
+            Complexity::Simple(SimpleType::String),
+            items,
+        ));
+    }
+    let ir = match param.parameter_type.as_str() {
+        "Number" => match (text.parse::<i64>(), text.parse::<f64>()) {
+            (Ok(x), _) => ResourceIr::Number(x),
+            (Err(_), Ok(x)) if !is_integer_text(&text) => ResourceIr::Double(x),
+            _ => ResourceIr::String(text),
+        },
+        _ => ResourceIr::String(text),
+    };
+    Ok(ir)
+}
+
+// count_parameter_refs counts the Refs to each parameter within value, including the ${}
+// variables of a Fn::Sub.
+pub fn count_parameter_refs(value: &ResourceIr, counts: &mut HashMap<String, usize>) {
+    match value {
+        ResourceIr::Ref(x) if x.origin == Origin::Parameter => {
+            *counts.entry(x.name.to_string()).or_insert(0) += 1
+        }
+        ResourceIr::Null
+        | ResourceIr::NoValue
+        | ResourceIr::Bool(_)
+        | ResourceIr::Number(_)
+        | ResourceIr::Double(_)
+        | ResourceIr::String(_)
+        | ResourceIr::Ref(_)
+        | ResourceIr::GetAtt(_, _) => {}
+        ResourceIr::Array(_, arr) | ResourceIr::Join(_, arr) | ResourceIr::Sub(arr) => {
+            arr.iter().for_each(|x| count_parameter_refs(x, counts))
+        }
+        ResourceIr::Object(_, o) => o.values().for_each(|x| count_parameter_refs(x, counts)),
+        ResourceIr::If(_, true_expr, false_expr) => {
+            count_parameter_refs(true_expr, counts);
+            count_parameter_refs(false_expr, counts);
+        }
+        ResourceIr::Map(mapper, first, second) => {
+            count_parameter_refs(mapper, counts);
+            count_parameter_refs(first, counts);
+            count_parameter_refs(second, counts);
+        }
+        ResourceIr::Select(index, list) => {
+            count_parameter_refs(index, counts);
+            count_parameter_refs(list, counts);
+        }
+        ResourceIr::GetAZs(x)
+        | ResourceIr::ToJsonString(x)
+        | ResourceIr::ImportValue(x)
+        | ResourceIr::Split(_, x)
+        | ResourceIr::Base64(x) => count_parameter_refs(x, counts),
+    }
+}
+
+// count_condition_parameter_refs is count_parameter_refs for the values of conditions.
+pub fn count_condition_parameter_refs(value: &ConditionIr, counts: &mut HashMap<String, usize>) {
+    match value {
+        ConditionIr::Ref(x) if x.origin == Origin::Parameter => {
+            *counts.entry(x.name.to_string()).or_insert(0) += 1
+        }
+        ConditionIr::Ref(_) | ConditionIr::Str(_) => {}
+        ConditionIr::And(x) | ConditionIr::Or(x) => x
+            .iter()
+            .for_each(|v| count_condition_parameter_refs(v, counts)),
+        ConditionIr::Not(x) => count_condition_parameter_refs(x, counts),
+        ConditionIr::Equals(a, b) => {
+            count_condition_parameter_refs(a, counts);
+            count_condition_parameter_refs(b, counts);
+        }
+        ConditionIr::Map(mapper, first, second) => {
+            for x in [mapper, first, second] {
+                count_condition_parameter_refs(x, counts);
+            }
+        }
+    }
+}
+
+// inline_parameter replaces each Ref to the parameter name within value with default. Within
+// a Fn::Sub, scalar defaults are written as text, so they become part of the string.
+pub fn inline_parameter(value: &mut ResourceIr, name: &str, default: &ResourceIr) {
+    match value {
+        ResourceIr::Ref(x) if x.origin == Origin::Parameter && x.name == name => {
+            *value = default.clone()
+        }
+        ResourceIr::Sub(arr) => {
+            let text = match default {
+                ResourceIr::String(x) => Option::Some(x.to_string()),
+                ResourceIr::Number(x) => Option::Some(x.to_string()),
+                ResourceIr::Double(x) => Option::Some(x.to_string()),
+                _ => Option::None,
+            };
+            for part in arr.iter_mut() {
+                let is_ref = matches!(part, ResourceIr::Ref(x) if x.origin == Origin::Parameter && x.name == name);
+                match (is_ref, &text) {
+                    (true, Some(text)) => *part = ResourceIr::String(text.to_string()),
+                    _ => inline_parameter(part, name, default),
+                }
+            }
+        }
+        ResourceIr::Null
+        | ResourceIr::NoValue
+        | ResourceIr::Bool(_)
+        | ResourceIr::Number(_)
+        | ResourceIr::Double(_)
+        | ResourceIr::String(_)
+        | ResourceIr::Ref(_)
+        | ResourceIr::GetAtt(_, _) => {}
+        ResourceIr::Array(_, arr) | ResourceIr::Join(_, arr) => arr
+            .iter_mut()
+            .for_each(|x| inline_parameter(x, name, default)),
+        ResourceIr::Object(_, o) => o
+            .values_mut()
+            .for_each(|x| inline_parameter(x, name, default)),
+        ResourceIr::If(_, true_expr, false_expr) => {
+            inline_parameter(true_expr, name, default);
+            inline_parameter(false_expr, name, default);
+        }
+        ResourceIr::Map(mapper, first, second) => {
+            inline_parameter(mapper, name, default);
+            inline_parameter(first, name, default);
+            inline_parameter(second, name, default);
+        }
+        ResourceIr::Select(index, list) => {
+            inline_parameter(index, name, default);
+            inline_parameter(list, name, default);
+        }
+        ResourceIr::GetAZs(x)
+        | ResourceIr::ToJsonString(x)
+        | ResourceIr::ImportValue(x)
+        | ResourceIr::Split(_, x)
+        | ResourceIr::Base64(x) => inline_parameter(x, name, default),
+    }
+}
@@ -0,0 +1,133 @@
+use crate::ir::CloudformationProgramIr;
+use std::collections::BTreeSet;
+
+// required_deploy_actions lists the IAM actions needed to create, update and delete every
+// resource in the program, as a starting point for a least-privilege deployment role.
+//
+// The actions come from a hand-curated table, as neither the specification nor the template
+// carries them. Resource types missing from the table add nothing, so the result should be
+// checked against the types in the template before it is relied on.
+pub fn required_deploy_actions(ir: &CloudformationProgramIr) -> BTreeSet<String> {
+    let mut actions = BTreeSet::new();
+    for resource in ir.resources.iter() {
+        for action in deploy_actions(&resource.resource_type) {
+            actions.insert(action.to_string());
+        }
+    }
+    actions
+}
+
+// deploy_actions is the curated table of create/update/delete actions for each resource type.
+fn deploy_actions(resource_type: &str) -> &'static [&'static str] {
+    match resource_type {
+        "AWS::S3::Bucket" => &[
+            "s3:CreateBucket",
+            "s3:DeleteBucket",
+            "s3:GetBucketLocation",
+            "s3:PutBucketPolicy",
+            "s3:PutBucketTagging",
+            "s3:PutBucketVersioning",
+            "s3:PutEncryptionConfiguration",
+            "s3:PutLifecycleConfiguration",
+        ],
+        "AWS::S3::BucketPolicy" => &[
+            "s3:DeleteBucketPolicy",
+            "s3:GetBucketPolicy",
+            "s3:PutBucketPolicy",
+        ],
+        "AWS::SQS::Queue" => &[
+            "sqs:CreateQueue",
+            "sqs:DeleteQueue",
+            "sqs:GetQueueAttributes",
+            "sqs:SetQueueAttributes",
+            "sqs:TagQueue",
+        ],
+        "AWS::SNS::Topic" => &[
+            "sns:CreateTopic",
+            "sns:DeleteTopic",
+            "sns:GetTopicAttributes",
+            "sns:SetTopicAttributes",
+            "sns:TagResource",
+        ],
+        "AWS::SNS::Subscription" => &[
+            "sns:GetSubscriptionAttributes",
+            "sns:SetSubscriptionAttributes",
+            "sns:Subscribe",
+            "sns:Unsubscribe",
+        ],
+        "AWS::DynamoDB::Table" => &[
+            "dynamodb:CreateTable",
+            "dynamodb:DeleteTable",
+            "dynamodb:DescribeTable",
+            "dynamodb:TagResource",
+            "dynamodb:UpdateTable",
+        ],
+        "AWS::Lambda::Function" => &[
+            "iam:PassRole",
+            "lambda:CreateFunction",
+            "lambda:DeleteFunction",
+            "lambda:GetFunction",
+            "lambda:TagResource",
+            "lambda:UpdateFunctionCode",
+            "lambda:UpdateFunctionConfiguration",
+        ],
+        "AWS::Lambda::Permission" => &["lambda:AddPermission", "lambda:RemovePermission"],
+        "AWS::IAM::Role" => &[
+            "iam:AttachRolePolicy",
+            "iam:CreateRole",
+            "iam:DeleteRole",
+            "iam:DeleteRolePolicy",
+            "iam:DetachRolePolicy",
+            "iam:GetRole",
+            "iam:PutRolePolicy",
+            "iam:TagRole",
+            "iam:UpdateAssumeRolePolicy",
+        ],
+        "AWS::IAM::Policy" => &["iam:DeleteRolePolicy", "iam:PutRolePolicy"],
+        "AWS::IAM::ManagedPolicy" => &[
+            "iam:AttachRolePolicy",
+            "iam:CreatePolicy",
+            "iam:CreatePolicyVersion",
+            "iam:DeletePolicy",
+            "iam:DeletePolicyVersion",
+            "iam:DetachRolePolicy",
+            "iam:GetPolicy",
+        ],
+        "AWS::EC2::Instance" => &[
+            "ec2:CreateTags",
+            "ec2:DescribeInstances",
+            "ec2:ModifyInstanceAttribute",
+            "ec2:RunInstances",
+            "ec2:TerminateInstances",
+        ],
+        "AWS::EC2::SecurityGroup" => &[
+            "ec2:AuthorizeSecurityGroupEgress",
+            "ec2:AuthorizeSecurityGroupIngress",
+            "ec2:CreateSecurityGroup",
+            "ec2:DeleteSecurityGroup",
+            "ec2:DescribeSecurityGroups",
+            "ec2:RevokeSecurityGroupEgress",
+            "ec2:RevokeSecurityGroupIngress",
+        ],
+        "AWS::AutoScaling::AutoScalingGroup" => &[
+            "autoscaling:CreateAutoScalingGroup",
+            "autoscaling:DeleteAutoScalingGroup",
+            "autoscaling:DescribeAutoScalingGroups",
+            "autoscaling:UpdateAutoScalingGroup",
+        ],
+        "AWS::Logs::LogGroup" => &[
+            "logs:CreateLogGroup",
+            "logs:DeleteLogGroup",
+            "logs:DescribeLogGroups",
+            "logs:PutRetentionPolicy",
+        ],
+        "AWS::Events::Rule" => &[
+            "events:DeleteRule",
+            "events:DescribeRule",
+            "events:PutRule",
+            "events:PutTargets",
+            "events:RemoveTargets",
+        ],
+        _ => &[],
+    }
+}
@@ -4,6 +4,9 @@ use voca_rs::case::camel_case;
 pub struct Reference {
     pub origin: Origin,
     pub name: String,
+    // is_list is set on a Ref to a resource whose type returns a list, rather than the single
+    // value a Ref to a resource usually does, that is the whole value of a list property.
+    pub is_list: bool,
 }
 
 impl Reference {
@@ -11,6 +14,7 @@ impl Reference {
         Reference {
             name: name.to_string(),
             origin,
+            is_list: false,
         }
     }
     pub fn synthesize(&self) -> String {
@@ -18,6 +22,9 @@ impl Reference {
             Origin::Parameter => {
                 format!("props.{}", camel_case(&self.name))
             }
+            Origin::LogicalId if self.is_list => {
+                format!("cdk.Token.asList({})", camel_case(&self.name))
+            }
             Origin::LogicalId => camel_case(&self.name),
             Origin::Condition => camel_case(&self.name),
             Origin::PseudoParameter(x) => match x {
@@ -94,4 +101,5 @@ fn test_match_pseudo_parameters() {
         Reference::match_pseudo_parameter("hello_world"),
         Option::None
     );
+    assert_eq!(Reference::match_pseudo_parameter("AWS::Foo"), Option::None);
 }
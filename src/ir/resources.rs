@@ -1,19 +1,29 @@
+use crate::ir::conditions::{implies, ConditionInstruction};
+use crate::ir::explanation::Explanation;
+use crate::ir::mappings;
+use crate::ir::outputs::OutputInstruction;
 use crate::ir::reference::{Origin, Reference};
+use crate::ir::suggestions::closest_match;
 use crate::parser::resource::ResourceValue;
 use crate::parser::sub::{sub_parse_tree, SubValue};
-use crate::specification::{spec, Complexity, Specification};
+use crate::specification::ref_values::ref_value;
+use crate::specification::{Complexity, PropertyRule, SimpleType, Specification};
 use crate::{CloudformationParseTree, TransmuteError};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 // ResourceIr is the intermediate representation of a nested stack resource.
 // It is slightly more refined than the ResourceValue, in some cases always resolving
 // known types. It also decorates objects with the necessary information for a separate
 // system to output all the necessary internal structures appropriately.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ResourceIr {
     Null,
+    // NoValue is Ref AWS::NoValue, which leaves out the property it is the value of.
+    NoValue,
     Bool(bool),
     Number(i64),
+    Double(f64),
     String(String),
 
     // Higher level resolutions
@@ -28,6 +38,22 @@ pub enum ResourceIr {
     GetAtt(String, String),
     Sub(Vec<ResourceIr>),
     Map(Box<ResourceIr>, Box<ResourceIr>, Box<ResourceIr>),
+    ToJsonString(Box<ResourceIr>),
+    ImportValue(Box<ResourceIr>),
+    Split(String, Box<ResourceIr>),
+    Base64(Box<ResourceIr>),
+    Select(Box<ResourceIr>, Box<ResourceIr>),
+    GetAZs(Box<ResourceIr>),
+}
+
+impl ResourceIr {
+    // as_str is the value of a string literal, or None if it can only be known at deploy time.
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            ResourceIr::String(x) => Option::Some(x),
+            _ => Option::None,
+        }
+    }
 }
 
 /// ResourceTranslationInputs is a place to store all the intermediate recursion
@@ -37,70 +63,939 @@ pub struct ResourceTranslationInputs<'t> {
     parse_tree: &'t CloudformationParseTree,
     specification: &'t Specification,
     complexity: Complexity,
+    // is_map is set when the value is a map of user chosen keys to values of the complexity.
+    is_map: bool,
     property_type: Option<&'t str>,
+    resource_name: &'t str,
     resource_type: &'t str,
+    explanations: Option<&'t RefCell<Vec<Explanation>>>,
+    // casing_fixes collects a warning for each property name that only matched the
+    // specification case-insensitively, but only when miscased names should be fixed.
+    casing_fixes: Option<&'t RefCell<Vec<String>>>,
+    // coercions collects a warning for each string that was read as a Double.
+    coercions: Option<&'t RefCell<Vec<String>>>,
+    // list_ref_types are the resource types a Ref to which returns a list.
+    list_ref_types: &'t [String],
+}
+
+impl<'t> ResourceTranslationInputs<'t> {
+    // explain records a translation decision, but only when explanations were asked for.
+    fn explain(&self, message: String) {
+        if let Some(explanations) = self.explanations {
+            explanations
+                .borrow_mut()
+                .push(Explanation::new(self.resource_name, message));
+        }
+    }
 }
 
 // ResourceInstruction is all the information needed to output a resource assignment.
 pub struct ResourceInstruction {
     pub name: String,
     pub condition: Option<String>,
+    pub metadata: Option<ResourceIr>,
+    pub creation_policy: Option<ResourceIr>,
+    pub deletion_policy: Option<String>,
+    pub update_replace_policy: Option<String>,
+    pub depends_on: Vec<String>,
+    // comments from the original template, see parser::comments.
+    pub comments: Vec<String>,
+    // todos are the parts of the resource that could not be fully translated.
+    pub todos: Vec<Todo>,
     pub resource_type: String,
     pub properties: HashMap<String, ResourceIr>,
 }
 
-pub fn translates_resources(parse_tree: &CloudformationParseTree) -> Vec<ResourceInstruction> {
-    let spec = spec();
+// Todo is a part of a resource that has to be finished by hand, with a link to the
+// CloudFormation documentation on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Todo {
+    pub reason: String,
+    pub link: &'static str,
+}
+
+// is_custom_resource is true for types backed by a custom resource provider, which are either
+// AWS::CloudFormation::CustomResource or named Custom::<anything>.
+pub fn is_custom_resource(resource_type: &str) -> bool {
+    resource_type == "AWS::CloudFormation::CustomResource" || resource_type.starts_with("Custom::")
+}
+
+// ResourcesContext is everything translating the resources takes besides the template and the
+// specification. Each sink collects what translation noticed along the way, and is left as
+// None when the caller doesn't want it collected.
+#[derive(Default)]
+pub struct ResourcesContext<'a> {
+    // skipped are the names of the resources to leave out, see skip_resource_types.
+    pub skipped: &'a [String],
+    // explanations collects the reason behind each translation decision.
+    pub explanations: Option<&'a RefCell<Vec<Explanation>>>,
+    // casing_fixes collects a warning for each property name that only matched the
+    // specification case-insensitively. Miscased names are only fixed when it is set.
+    pub casing_fixes: Option<&'a RefCell<Vec<String>>>,
+    // coercions collects a warning for each string that was read as a Double.
+    pub coercions: Option<&'a RefCell<Vec<String>>>,
+    // translate_unknown_types translates a resource whose type isn't in the specification with
+    // its properties as plain json, and a TODO, rather than failing.
+    pub translate_unknown_types: bool,
+    // list_ref_types are the resource types a Ref to which returns a list, see list_ref.
+    pub list_ref_types: &'a [String],
+}
+
+// translates_resources translates every resource but the skipped ones, which are left out
+// before they are translated, so that even a resource that can't be translated can be skipped.
+pub fn translates_resources(
+    parse_tree: &CloudformationParseTree,
+    spec: &Specification,
+    context: &ResourcesContext,
+) -> Result<Vec<ResourceInstruction>, TransmuteError> {
+    translate_all_resources(parse_tree, spec, context).map_err(|mut errors| errors.remove(0))
+}
+
+// translates_resources_collecting is translates_resources, but rather than stopping at the
+// first error, it reports every one, each with the path to the property it was in. An error
+// stops the property it is in, but not the others.
+pub fn translates_resources_collecting(
+    parse_tree: &CloudformationParseTree,
+    spec: &Specification,
+    context: &ResourcesContext,
+) -> Result<Vec<ResourceInstruction>, Vec<TransmuteError>> {
+    translate_all_resources(parse_tree, spec, context)
+}
+
+// translate_all_resources translates every resource, returning the errors it ran into in the
+// order they were found.
+fn translate_all_resources(
+    parse_tree: &CloudformationParseTree,
+    spec: &Specification,
+    context: &ResourcesContext,
+) -> Result<Vec<ResourceInstruction>, Vec<TransmuteError>> {
+    let ResourcesContext {
+        skipped,
+        explanations,
+        casing_fixes,
+        coercions,
+        translate_unknown_types,
+        list_ref_types,
+    } = *context;
+    let mut errors = Vec::new();
     let mut resource_instructions = Vec::new();
     for resource in parse_tree.resources.resources.iter() {
-        let resource_spec = spec
-            .resource_types
-            .get(&resource.resource_type)
-            .unwrap()
-            .properties
-            .as_ref();
+        if skipped.contains(&resource.name) {
+            continue;
+        }
+        // Custom resources take whatever properties their provider accepts, so the
+        // specification's entry for them, which only knows ServiceToken, isn't used.
+        let resource_spec = match is_custom_resource(&resource.resource_type) {
+            true => Option::None,
+            false => spec.resource_types.get(&resource.resource_type),
+        };
+        let mut todos = Vec::new();
+        // SAM types are only expanded into resources by the transform, at deploy time.
+        if resource.resource_type.starts_with("AWS::Serverless::") && translate_unknown_types {
+            todos.push(Todo {
+                reason: format!(
+                    "{} is expanded by the AWS::Serverless-2016-10-31 transform, which isn't applied, so its properties were translated as plain json",
+                    resource.resource_type
+                ),
+                link: "https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/transform-aws-serverless.html",
+            });
+        } else if resource_spec.is_none() && !is_custom_resource(&resource.resource_type) {
+            if !translate_unknown_types {
+                errors.push(
+                    TransmuteError::new(&format!(
+                        "unknown resource type \"{}\" in {}",
+                        resource.resource_type, resource.name
+                    ))
+                    .within(&resource.name)
+                    .within("Resources"),
+                );
+                continue;
+            }
+            todos.push(Todo {
+                reason: format!(
+                    "{} is not in the specification, so its properties were translated as plain json",
+                    resource.resource_type
+                ),
+                link: "https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/cfn-resource-specification.html",
+            });
+        }
         let mut props = HashMap::new();
         for (name, prop) in resource.properties.iter() {
-            let property_rule = resource_spec.unwrap().get(name).unwrap();
-            let complexity = property_rule.get_complexity();
-            let property_type =
-                Specification::full_property_name(&complexity, &resource.resource_type);
-            let property_type = property_type.as_deref();
-            let rt = ResourceTranslationInputs {
-                parse_tree,
-                specification: &spec,
-                complexity: property_rule.get_complexity(),
-                property_type,
-                resource_type: &resource.resource_type,
+            let translate = || {
+                let (name, complexity, is_map, is_list) = match resource_spec {
+                    None => (
+                        name.as_str(),
+                        Complexity::Simple(SimpleType::Json),
+                        false,
+                        false,
+                    ),
+                    Some(rule) => {
+                        let (name, property_rule) = find_property(
+                            rule.properties.as_ref(),
+                            name,
+                            &resource.resource_type,
+                            &resource.name,
+                            casing_fixes,
+                        )?;
+                        (
+                            name,
+                            property_rule.get_complexity(),
+                            property_rule.is_map(),
+                            property_rule.is_list(),
+                        )
+                    }
+                };
+                let property_type = spec.full_property_name(&complexity, &resource.resource_type);
+                let property_type = property_type.as_deref();
+                let rt = ResourceTranslationInputs {
+                    parse_tree,
+                    specification: spec,
+                    complexity,
+                    is_map,
+                    property_type,
+                    resource_name: &resource.name,
+                    resource_type: &resource.resource_type,
+                    explanations,
+                    casing_fixes,
+                    coercions,
+                    list_ref_types,
+                };
+
+                translate_resource(prop, &rt).map(|ir| (name, list_ref(ir, is_list, &rt)))
             };
 
-            let ir = translate_resource(prop, &rt).unwrap();
-            props.insert(name.to_string(), ir);
+            match translate() {
+                // A property that is AWS::NoValue in every case is the same as one that isn't set.
+                Ok((_, ResourceIr::NoValue)) => {}
+                Ok((name, ir)) => {
+                    props.insert(name.to_string(), ir);
+                }
+                Err(err) => errors.push(
+                    err.within(name)
+                        .within("Properties")
+                        .within(&resource.name)
+                        .within("Resources"),
+                ),
+            }
         }
+        normalize_ssm_parameter(&resource.resource_type, &mut props);
+
+        // Resource attributes have no specification, so they are translated as json.
+        let translate_attribute = |value: &ResourceValue| {
+            translate_json(
+                parse_tree,
+                spec,
+                &resource.name,
+                &resource.resource_type,
+                value,
+                explanations,
+            )
+        };
+        // Metadata is free-form json, so every key in it is kept exactly as written.
+        let metadata = match resource.metadata.as_ref().map(translate_attribute) {
+            None => Option::None,
+            Some(Ok(m)) => Option::Some(m),
+            Some(Err(err)) => {
+                errors.push(
+                    err.within("Metadata")
+                        .within(&resource.name)
+                        .within("Resources"),
+                );
+                Option::None
+            }
+        };
+        // CreationPolicy has a fixed shape, which cdk models like any complex property.
+        let creation_policy = match resource.creation_policy.as_ref().map(translate_attribute) {
+            None => Option::None,
+            Some(Ok(mut ir)) => {
+                mark_complex(&mut ir, "CreationPolicy");
+                Option::Some(ir)
+            }
+            Some(Err(err)) => {
+                errors.push(
+                    err.within("CreationPolicy")
+                        .within(&resource.name)
+                        .within("Resources"),
+                );
+                Option::None
+            }
+        };
 
         resource_instructions.push(ResourceInstruction {
             name: resource.name.to_string(),
             resource_type: resource.resource_type.to_string(),
             condition: resource.condition.clone(),
+            metadata,
+            creation_policy,
+            deletion_policy: resource.deletion_policy.clone(),
+            update_replace_policy: resource.update_replace_policy.clone(),
+            depends_on: resource.depends_on.clone(),
+            comments: Vec::new(),
+            todos,
             properties: props,
         });
     }
+    match errors.is_empty() {
+        true => Ok(resource_instructions),
+        false => Err(errors),
+    }
+}
+
+// find_property looks up the rule for a property of owner, a resource or property type. When
+// casing fixes are on, a name that only matches case-insensitively, e.g. bucketName, is
+// corrected to the specification's, with a warning. It returns the name the property has in
+// the specification.
+fn find_property<'a>(
+    properties: Option<&'a HashMap<String, PropertyRule>>,
+    name: &str,
+    owner: &str,
+    resource_name: &str,
+    casing_fixes: Option<&RefCell<Vec<String>>>,
+) -> Result<(&'a str, &'a PropertyRule), TransmuteError> {
+    if let Some((key, rule)) = properties.and_then(|x| x.get_key_value(name)) {
+        return Ok((key, rule));
+    }
+    if let Some(casing_fixes) = casing_fixes {
+        let found =
+            properties.and_then(|x| x.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)));
+        if let Some((key, rule)) = found {
+            casing_fixes.borrow_mut().push(format!(
+                "{} in {} was corrected to {}",
+                name, resource_name, key
+            ));
+            return Ok((key, rule));
+        }
+    }
+
+    Err(TransmuteError {
+        details: format!(
+            "unknown property \"{}\" on {} in {}",
+            name, owner, resource_name
+        ),
+        path: Vec::new(),
+    })
+}
+
+// translate_json translates a value that has no specification, e.g. a resource attribute or
+// an output's Value, as json. name is what the value belongs to, for errors and explanations.
+pub fn translate_json(
+    parse_tree: &CloudformationParseTree,
+    specification: &Specification,
+    name: &str,
+    resource_type: &str,
+    value: &ResourceValue,
+    explanations: Option<&RefCell<Vec<Explanation>>>,
+) -> Result<ResourceIr, TransmuteError> {
+    let rt = ResourceTranslationInputs {
+        parse_tree,
+        specification,
+        complexity: Complexity::Simple(SimpleType::Json),
+        is_map: false,
+        property_type: Option::None,
+        resource_name: name,
+        resource_type,
+        explanations,
+        casing_fixes: Option::None,
+        coercions: Option::None,
+        list_ref_types: &[],
+    };
+    translate_resource(value, &rt)
+}
+
+// translate_mapping_value translates a value of the Mappings section, which is a string, a number
+// or a list of strings. name is the mapping the value is in, for errors and explanations.
+pub fn translate_mapping_value(
+    parse_tree: &CloudformationParseTree,
+    specification: &Specification,
+    name: &str,
+    value: &ResourceValue,
+) -> Result<ResourceIr, TransmuteError> {
+    let rt = ResourceTranslationInputs {
+        parse_tree,
+        specification,
+        complexity: Complexity::Simple(SimpleType::String),
+        is_map: false,
+        property_type: Option::None,
+        resource_name: name,
+        resource_type: "",
+        explanations: Option::None,
+        casing_fixes: Option::None,
+        coercions: Option::None,
+        list_ref_types: &[],
+    };
+    translate_resource(value, &rt)
+}
+
+// mark_complex sets the complexity of every object within value, so that their keys are
+// synthesized like those of a complex property.
+fn mark_complex(value: &mut ResourceIr, name: &str) {
+    match value {
+        ResourceIr::Object(complexity, o) => {
+            *complexity = Complexity::Complex(name.to_string());
+            o.values_mut().for_each(|x| mark_complex(x, name));
+        }
+        ResourceIr::Array(_, arr) => arr.iter_mut().for_each(|x| mark_complex(x, name)),
+        _ => {}
+    }
+}
+
+// A StringList parameter stores its value as a single comma-delimited string. If the value
+// was written as a list, it has to be joined to be stored.
+fn normalize_ssm_parameter(resource_type: &str, props: &mut HashMap<String, ResourceIr>) {
+    if resource_type != "AWS::SSM::Parameter" {
+        return;
+    }
+    if props.get("Type") != Option::Some(&ResourceIr::String("StringList".to_string())) {
+        return;
+    }
+    if let Some(value) = props.remove("Value") {
+        let value = match value {
+            ResourceIr::Array(_, _) => ResourceIr::Join(",".to_string(), vec![value]),
+            _ => value,
+        };
+        props.insert("Value".to_string(), value);
+    }
+}
+
+// drop_empty_strings removes every key set to an empty string, at any depth.
+pub fn drop_empty_strings(props: &mut HashMap<String, ResourceIr>) {
+    props.retain(|_, value| value != &ResourceIr::String(String::new()));
+    for value in props.values_mut() {
+        drop_nested_empty_strings(value);
+    }
+}
+
+fn drop_nested_empty_strings(value: &mut ResourceIr) {
+    match value {
+        ResourceIr::Object(_, o) => drop_empty_strings(o),
+        ResourceIr::Array(_, arr) => arr.iter_mut().for_each(drop_nested_empty_strings),
+        _ => {}
+    }
+}
+
+// has_transforms is true when value has a Fn::Transform within it, a macro that CloudFormation
+// only applies at deploy time.
+pub fn has_transforms(value: &ResourceIr) -> bool {
+    match value {
+        ResourceIr::Object(_, o) => {
+            o.contains_key("Fn::Transform") || o.values().any(has_transforms)
+        }
+        ResourceIr::Array(_, arr) => arr.iter().any(has_transforms),
+        _ => false,
+    }
+}
+
+// find_warnings reports anything in the translated resources that will behave differently
+// from the original template.
+pub fn find_warnings(
+    resource_instructions: &[ResourceInstruction],
+    condition_instructions: &[ConditionInstruction],
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let conditions: HashMap<&str, &str> = resource_instructions
+        .iter()
+        .filter_map(|r| r.condition.as_deref().map(|c| (r.name.as_str(), c)))
+        .collect();
+    for resource in resource_instructions.iter() {
+        // A dependency that only exists under a condition is missing whenever that condition is
+        // false, unless the dependent resource is gated by a condition that implies it.
+        let is_guarded = |guards: &[&str], condition: &str| {
+            resource
+                .condition
+                .iter()
+                .map(String::as_str)
+                .chain(guards.iter().copied())
+                .any(|guard| implies(condition_instructions, guard, condition))
+        };
+        for dependency in resource.depends_on.iter() {
+            if let Some(condition) = conditions.get(dependency.as_str()) {
+                if !is_guarded(&[], condition) {
+                    warnings.push(format!(
+                        "{} depends on {}, which only exists when condition {} is true",
+                        resource.name, dependency, condition
+                    ));
+                }
+            }
+        }
+
+        // The same goes for references, though a reference in the true branch of a Fn::If is
+        // also guarded by the If's condition.
+        let mut references = Vec::new();
+        for property in resource.properties.values() {
+            find_references(property, &mut Vec::new(), &mut references);
+        }
+        let mut reported = Vec::new();
+        for (target, guards) in references.iter() {
+            if let Some(condition) = conditions.get(target) {
+                if !is_guarded(guards, condition) && !reported.contains(target) {
+                    reported.push(target);
+                    warnings.push(format!(
+                        "{} references {}, which only exists when condition {} is true",
+                        resource.name, target, condition
+                    ));
+                }
+            }
+        }
+
+        if let Some(ResourceIr::Object(_, metadata)) = &resource.metadata {
+            // Fn::Transform in Metadata invokes a macro that rewrites the resource at deploy time.
+            if metadata.contains_key("Fn::Transform") {
+                warnings.push(format!(
+                    "{} has a Fn::Transform in its Metadata, which is only applied by CloudFormation at deploy time",
+                    resource.name
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+// skip_resource_types returns the names of the resources whose type is not in the allowlist
+// (when there is one), or is in the denylist, which are left untranslated. Patterns are either a
+// full type, or a prefix ending in *, e.g. "AWS::IAM::*".
+pub fn skip_resource_types(
+    parse_tree: &CloudformationParseTree,
+    allowlist: &[String],
+    denylist: &[String],
+) -> Vec<String> {
+    let matches = |patterns: &[String], resource_type: &str| {
+        patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => resource_type.starts_with(prefix),
+                None => pattern == resource_type,
+            })
+    };
+
+    parse_tree
+        .resources
+        .resources
+        .iter()
+        .filter(|resource| {
+            !(allowlist.is_empty() || matches(allowlist, &resource.resource_type))
+                || matches(denylist, &resource.resource_type)
+        })
+        .map(|resource| resource.name.clone())
+        .collect()
+}
+
+// find_skipped_references warns about each skipped resource, and each reference to one.
+pub fn find_skipped_references(
+    parse_tree: &CloudformationParseTree,
+    resource_instructions: &[ResourceInstruction],
+    skipped: &[String],
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for resource in parse_tree.resources.resources.iter() {
+        if skipped.contains(&resource.name) {
+            warnings.push(format!(
+                "{} was skipped, as resources of type {} are not translated",
+                resource.name, resource.resource_type
+            ));
+        }
+    }
+
+    for resource in resource_instructions.iter() {
+        let mut references = Vec::new();
+        for property in resource.properties.values() {
+            find_references(property, &mut Vec::new(), &mut references);
+        }
+        let mut reported = Vec::new();
+        for target in resource
+            .depends_on
+            .iter()
+            .map(|x| x.as_str())
+            .chain(references.iter().map(|(target, _)| *target))
+        {
+            if skipped.iter().any(|x| x == target) && !reported.contains(&target) {
+                reported.push(target);
+                warnings.push(format!(
+                    "{} references {}, which was skipped",
+                    resource.name, target
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+// find_missing_required reports required properties that are never set, or are only set under
+// some conditions, i.e. when one branch of a Fn::If is AWS::NoValue.
+pub fn find_missing_required(
+    resource_instructions: &[ResourceInstruction],
+    spec: &Specification,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for resource in resource_instructions.iter() {
+        let rules = match spec
+            .resource_types
+            .get(&resource.resource_type)
+            .and_then(|rule| rule.properties.as_ref())
+        {
+            None => continue,
+            Some(x) => x,
+        };
+        let mut required: Vec<&String> = rules
+            .iter()
+            .filter(|(_, rule)| rule.is_required())
+            .map(|(name, _)| name)
+            .collect();
+        required.sort();
+        for name in required {
+            match resource.properties.get(name) {
+                None | Some(ResourceIr::Null) | Some(ResourceIr::NoValue) => warnings.push(
+                    format!("{} is missing required property {}", resource.name, name),
+                ),
+                Some(ResourceIr::If(condition, true_expr, false_expr))
+                    if may_be_unset(true_expr) || may_be_unset(false_expr) =>
+                {
+                    warnings.push(format!(
+                        "{} only sets required property {} depending on condition {}",
+                        resource.name, name, condition
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    warnings
+}
+
+// may_be_unset is true when value is AWS::NoValue in at least one branch.
+fn may_be_unset(value: &ResourceIr) -> bool {
+    match value {
+        ResourceIr::NoValue => true,
+        ResourceIr::If(_, true_expr, false_expr) => {
+            may_be_unset(true_expr) || may_be_unset(false_expr)
+        }
+        _ => false,
+    }
+}
+
+// find_disallowed_values reports literal property values that aren't in the property's list of
+// allowed values. Values only known at deploy time can't be checked.
+pub fn find_disallowed_values(
+    resource_instructions: &[ResourceInstruction],
+    allowed_values: &HashMap<String, Vec<String>>,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for resource in resource_instructions.iter() {
+        for (name, value) in resource.properties.iter() {
+            let property_name = format!("{}.{}", resource.resource_type, name);
+            let allowed = match allowed_values.get(&property_name) {
+                None => continue,
+                Some(x) => x,
+            };
+            let literals = match value {
+                ResourceIr::Array(_, arr) => arr.iter().filter_map(ResourceIr::as_str).collect(),
+                _ => value.as_str().into_iter().collect::<Vec<_>>(),
+            };
+            for literal in literals {
+                if !allowed.iter().any(|x| x == literal) {
+                    warnings.push(format!(
+                        "{} sets {} to {}, which is not one of: {}",
+                        resource.name,
+                        name,
+                        literal,
+                        allowed.join(", ")
+                    ));
+                }
+            }
+        }
+    }
+    warnings
+}
+
+// find_ordering_hazards suggests a DependsOn where a resource relies on another without
+// referencing it. A function can be created as soon as its role is, and fail to start if a
+// policy it needs hasn't been attached to the role yet. This is only a heuristic.
+pub fn find_ordering_hazards(resource_instructions: &[ResourceInstruction]) -> Vec<String> {
+    let referenced = |resource: &ResourceInstruction, property: &str| {
+        let mut references = Vec::new();
+        if let Some(value) = resource.properties.get(property) {
+            find_references(value, &mut Vec::new(), &mut references);
+        }
+        references
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<String>>()
+    };
+
+    let mut warnings = Vec::new();
+    for function in resource_instructions
+        .iter()
+        .filter(|r| r.resource_type == "AWS::Lambda::Function")
+    {
+        let roles = referenced(function, "Role");
+        for policy in resource_instructions.iter().filter(|r| {
+            r.resource_type == "AWS::IAM::Policy" || r.resource_type == "AWS::IAM::ManagedPolicy"
+        }) {
+            if function.depends_on.contains(&policy.name) {
+                continue;
+            }
+            if let Some(role) = referenced(policy, "Roles")
+                .into_iter()
+                .find(|x| roles.contains(x))
+            {
+                warnings.push(format!(
+                    "{} uses role {}, which {} is attached to; consider DependsOn: {} so the policy is attached first",
+                    function.name, role, policy.name, policy.name
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+// resources_of_type returns the resources of the given type, in the order they were translated.
+pub fn resources_of_type<'a>(
+    resource_instructions: &'a [ResourceInstruction],
+    resource_type: &str,
+) -> Vec<&'a ResourceInstruction> {
     resource_instructions
+        .iter()
+        .filter(|r| r.resource_type == resource_type)
+        .collect()
 }
 
+// referenced_logical_ids lists every logical id that another resource refers to, by Ref,
+// Fn::GetAtt or DependsOn.
+pub fn referenced_logical_ids(resource_instructions: &[ResourceInstruction]) -> HashSet<String> {
+    resource_instructions
+        .iter()
+        .flat_map(resource_dependencies)
+        .collect()
+}
+
+// find_dangling_references lists each Ref or Fn::GetAtt of a logical id that isn't a resource
+// in the template. Any name that isn't a parameter is taken to be a logical id when it is
+// translated, so a misspelled one is only caught here, once every resource is known.
+pub fn find_dangling_references(
+    parse_tree: &CloudformationParseTree,
+    resource_instructions: &[ResourceInstruction],
+    outputs: &[OutputInstruction],
+) -> Vec<String> {
+    let names: Vec<&str> = parse_tree
+        .resources
+        .resources
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    let values = resource_instructions
+        .iter()
+        .flat_map(|r| {
+            r.properties
+                .values()
+                .chain(r.metadata.iter())
+                .map(move |x| (r.name.as_str(), x))
+        })
+        .chain(outputs.iter().map(|x| (x.name.as_str(), &x.value)));
+
+    let mut dangling = BTreeSet::new();
+    for (owner, value) in values {
+        let mut references = Vec::new();
+        find_references(value, &mut Vec::new(), &mut references);
+        for (name, _) in references {
+            if !names.contains(&name) {
+                dangling.insert((owner, name));
+            }
+        }
+    }
+    dangling
+        .into_iter()
+        .map(
+            |(owner, name)| match closest_match(name, names.iter().copied()) {
+                None => format!(
+                    "{} refers to {}, which is not a resource in the template",
+                    owner, name
+                ),
+                Some(suggestion) => format!(
+                    "{} refers to {}, which is not a resource in the template, did you mean {}?",
+                    owner, name, suggestion
+                ),
+            },
+        )
+        .collect()
+}
+
+// resource_dependencies lists the logical ids a resource refers to, by Ref, Fn::GetAtt or
+// DependsOn, each of which has to exist before the resource can be created.
+pub fn resource_dependencies(resource: &ResourceInstruction) -> BTreeSet<String> {
+    let mut references = Vec::new();
+    for value in resource.properties.values() {
+        find_references(value, &mut Vec::new(), &mut references);
+    }
+    let mut dependencies: BTreeSet<String> =
+        references.into_iter().map(|(x, _)| x.to_string()).collect();
+    dependencies.extend(resource.depends_on.iter().cloned());
+    dependencies
+}
+
+// find_references collects the logical ids referenced within a value, along with the Fn::If
+// conditions that must be true for each reference to be used.
+fn find_references<'a>(
+    value: &'a ResourceIr,
+    guards: &mut Vec<&'a str>,
+    references: &mut Vec<(&'a str, Vec<&'a str>)>,
+) {
+    match value {
+        ResourceIr::Null
+        | ResourceIr::NoValue
+        | ResourceIr::Bool(_)
+        | ResourceIr::Number(_)
+        | ResourceIr::Double(_)
+        | ResourceIr::String(_) => {}
+        ResourceIr::Ref(x) => {
+            if x.origin == Origin::LogicalId {
+                references.push((&x.name, guards.clone()));
+            }
+        }
+        ResourceIr::GetAtt(name, _) => references.push((name, guards.clone())),
+        ResourceIr::If(condition, true_expr, false_expr) => {
+            guards.push(condition);
+            find_references(true_expr, guards, references);
+            guards.pop();
+            find_references(false_expr, guards, references);
+        }
+        ResourceIr::Array(_, arr) | ResourceIr::Join(_, arr) | ResourceIr::Sub(arr) => {
+            for x in arr.iter() {
+                find_references(x, guards, references);
+            }
+        }
+        ResourceIr::Object(_, o) => {
+            for x in o.values() {
+                find_references(x, guards, references);
+            }
+        }
+        ResourceIr::Map(mapper, first, second) => {
+            for x in [mapper, first, second] {
+                find_references(x, guards, references);
+            }
+        }
+        ResourceIr::Select(index, list) => {
+            find_references(index, guards, references);
+            find_references(list, guards, references);
+        }
+        ResourceIr::ToJsonString(x)
+        | ResourceIr::GetAZs(x)
+        | ResourceIr::ImportValue(x)
+        | ResourceIr::Split(_, x)
+        | ResourceIr::Base64(x) => find_references(x, guards, references),
+    }
+}
+
+// coerce_primitive reads a string as the primitive type the specification declares for it.
+// CloudFormation accepts "true" for a Boolean and "8080" for an Integer, and YAML reads numbers
+// with leading zeros, e.g. 0755, as strings, as they could be octal. CloudFormation reads them
+// as decimal wherever a number is expected. Strings that can't be read as the type are None.
+fn coerce_primitive(s: &str, complexity: &Complexity) -> Option<ResourceIr> {
+    match complexity {
+        Complexity::Simple(SimpleType::Boolean) => match s.to_ascii_lowercase().as_str() {
+            "true" => Option::Some(ResourceIr::Bool(true)),
+            "false" => Option::Some(ResourceIr::Bool(false)),
+            _ => Option::None,
+        },
+        Complexity::Simple(SimpleType::Integer) | Complexity::Simple(SimpleType::Long) => {
+            s.parse::<i64>().ok().map(ResourceIr::Number)
+        }
+        Complexity::Simple(SimpleType::Double) => match (s.parse::<i64>(), s.parse::<f64>()) {
+            (Ok(x), _) => Option::Some(ResourceIr::Number(x)),
+            (Err(_), Ok(x)) if x.is_finite() && !is_integer_text(s) => {
+                Option::Some(ResourceIr::Double(x))
+            }
+            _ => Option::None,
+        },
+        _ => Option::None,
+    }
+}
+
+// is_integer_text is true for integer literals that did not fit an i64, which would lose
+// precision as a double.
+pub(crate) fn is_integer_text(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+// list_ref marks a Ref to a resource that is the whole value of a list property as returning a
+// list, so it is emitted as one. The specification doesn't say what a Ref returns, and a Ref to
+// any type in ref_values returns a single value, so only the types the caller lists as returning
+// a list, most often custom resources, are marked.
+fn list_ref(
+    ir: ResourceIr,
+    is_list: bool,
+    resource_translator: &ResourceTranslationInputs,
+) -> ResourceIr {
+    let mut reference = match ir {
+        ResourceIr::Ref(reference) if is_list && reference.origin == Origin::LogicalId => reference,
+        ir => return ir,
+    };
+    let target = match resource_translator
+        .parse_tree
+        .resources
+        .resources
+        .iter()
+        .find(|r| r.name == reference.name)
+    {
+        Some(target) => target,
+        None => return ResourceIr::Ref(reference),
+    };
+    if resource_translator
+        .list_ref_types
+        .contains(&target.resource_type)
+    {
+        resource_translator.explain(format!("Ref {} returns a list", reference.name));
+        reference.is_list = true;
+    } else if let Some((_, description)) = ref_value(&target.resource_type) {
+        resource_translator.explain(format!(
+            "Ref {} returns {}, a single value, where a list is expected",
+            reference.name, description
+        ));
+    }
+    ResourceIr::Ref(reference)
+}
+
+// translate_resource translates a value, placing any error within the intrinsic it was in.
 fn translate_resource(
     resource_value: &ResourceValue,
     resource_translator: &ResourceTranslationInputs,
+) -> Result<ResourceIr, TransmuteError> {
+    let ir = translate_value(resource_value, resource_translator);
+    match resource_value.intrinsic() {
+        Some(intrinsic) => ir.map_err(|err| err.within(intrinsic.key())),
+        None => ir,
+    }
+}
+
+fn translate_value(
+    resource_value: &ResourceValue,
+    resource_translator: &ResourceTranslationInputs,
 ) -> Result<ResourceIr, TransmuteError> {
     match resource_value {
         ResourceValue::Null => Ok(ResourceIr::Null),
         ResourceValue::Bool(b) => Ok(ResourceIr::Bool(*b)),
         ResourceValue::Number(n) => Ok(ResourceIr::Number(*n)),
-        ResourceValue::String(s) => Ok(ResourceIr::String(s.to_string())),
+        ResourceValue::Double(n) => Ok(ResourceIr::Double(*n)),
+        ResourceValue::String(s) => {
+            let ir = match resource_translator.is_map {
+                true => Option::None,
+                false => coerce_primitive(s, &resource_translator.complexity),
+            };
+            // A double's text isn't always kept, e.g. "0.10" is written back out as 0.1.
+            if let (Some(ResourceIr::Double(x)), Some(coercions)) =
+                (&ir, resource_translator.coercions)
+            {
+                coercions.borrow_mut().push(format!(
+                    "\"{}\" in {} is a string, but was read as the number {:?} as its property is a Double",
+                    s, resource_translator.resource_name, x
+                ));
+            }
+            Ok(ir.unwrap_or_else(|| ResourceIr::String(s.to_string())))
+        }
         ResourceValue::Array(parse_resource_vec) => {
             let mut array_ir = Vec::new();
-            for parse_resource in parse_resource_vec {
-                let x = translate_resource(parse_resource, resource_translator)?;
+            for (i, parse_resource) in parse_resource_vec.iter().enumerate() {
+                let x = translate_resource(parse_resource, resource_translator)
+                    .map_err(|err| err.within(&i.to_string()))?;
                 array_ir.push(x);
             }
 
@@ -109,32 +1004,92 @@ fn translate_resource(
                 array_ir,
             ))
         }
-        ResourceValue::Object(o) => {
+        ResourceValue::Object(o) if resource_translator.is_map => {
+            // The keys of a map are the user's own, so only the values follow the item rule.
+            let mut new_rt = resource_translator.clone();
+            new_rt.is_map = false;
             let mut new_hash = HashMap::new();
             for (s, rv) in o {
-                let property_ir = match resource_translator.complexity {
-                    Complexity::Simple(_) => translate_resource(rv, resource_translator)?,
-                    Complexity::Complex(_) => {
-                        // Update the rule with it's underlying property rule.
-                        let mut new_rt = resource_translator.clone();
-                        let rule = resource_translator
+                match translate_resource(rv, &new_rt).map_err(|err| err.within(s))? {
+                    ResourceIr::NoValue => {}
+                    ir => {
+                        new_hash.insert(s.to_string(), ir);
+                    }
+                }
+            }
+
+            Ok(ResourceIr::Object(
+                Complexity::Simple(SimpleType::Json),
+                new_hash,
+            ))
+        }
+        ResourceValue::Object(o) => {
+            let rule = match &resource_translator.complexity {
+                Complexity::Simple(_) => Option::None,
+                Complexity::Complex(x) => {
+                    let rule = resource_translator.property_type.and_then(|property_type| {
+                        resource_translator
                             .specification
                             .property_types
-                            .get(&resource_translator.property_type.unwrap().to_string())
-                            .unwrap();
-                        let properties = rule.properties.as_ref().unwrap();
-                        let property_rule = properties.get(s).unwrap();
-                        new_rt.complexity = property_rule.get_complexity();
-                        let opt = Specification::full_property_name(
-                            &property_rule.get_complexity(),
-                            resource_translator.resource_type,
-                        );
-                        new_rt.property_type = opt.as_deref();
-                        translate_resource(rv, &new_rt)?
+                            .get(property_type)
+                    });
+                    if rule.is_none() {
+                        // Without a rule, nothing is known of the object's shape, so it is
+                        // passed through as json.
+                        resource_translator.explain(format!(
+                            "translated {} as json because its type is not in the specification",
+                            x
+                        ));
                     }
-                };
+                    rule
+                }
+            };
 
-                new_hash.insert(s.to_string(), property_ir);
+            let mut new_hash = HashMap::new();
+            for (s, rv) in o {
+                let entry = || -> Result<(&str, ResourceIr), TransmuteError> {
+                    Ok(match rule {
+                        Some(rule) => {
+                            // Update the rule with it's underlying property rule.
+                            let mut new_rt = resource_translator.clone();
+                            let (key, property_rule) = find_property(
+                                rule.properties.as_ref(),
+                                s,
+                                resource_translator.property_type.unwrap_or_default(),
+                                resource_translator.resource_name,
+                                resource_translator.casing_fixes,
+                            )?;
+                            new_rt.complexity = property_rule.get_complexity();
+                            new_rt.is_map = property_rule.is_map();
+                            let opt = resource_translator.specification.full_property_name(
+                                &property_rule.get_complexity(),
+                                resource_translator.resource_type,
+                            );
+                            new_rt.property_type = opt.as_deref();
+                            let ir = translate_resource(rv, &new_rt)?;
+                            (key, list_ref(ir, property_rule.is_list(), &new_rt))
+                        }
+                        None => match resource_translator.complexity {
+                            Complexity::Simple(_) => {
+                                (s.as_str(), translate_resource(rv, resource_translator)?)
+                            }
+                            Complexity::Complex(_) => {
+                                let mut new_rt = resource_translator.clone();
+                                new_rt.complexity = Complexity::Simple(SimpleType::Json);
+                                new_rt.is_map = false;
+                                new_rt.property_type = Option::None;
+                                (s.as_str(), translate_resource(rv, &new_rt)?)
+                            }
+                        },
+                    })
+                };
+                // Like a resource's properties, a key that is always AWS::NoValue is left out.
+                match entry().map_err(|err| err.within(s))? {
+                    (_, ResourceIr::NoValue) => {}
+                    (key, property_ir) => {
+                        new_hash.insert(key.to_string(), property_ir);
+                    }
+                }
             }
 
             Ok(ResourceIr::Object(
@@ -173,22 +1128,47 @@ fn translate_resource(
                 }
             }
             let vars = sub_parse_tree(val.as_str())?;
-            let r = vars
-                .iter()
-                .map(|x| match x {
+            let mut r = Vec::new();
+            for x in vars.iter() {
+                let ir = match x {
                     SubValue::String(x) => ResourceIr::String(x.to_string()),
+                    SubValue::Literal(x) => ResourceIr::String(format!("${{{}}}", x)),
                     SubValue::Variable(x) => match excess_map.get(x) {
-                        None => ResourceIr::Ref(find_ref(x, resource_translator.parse_tree)),
-                        Some(x) => x.clone(),
+                        None => translate_sub_variable(x, resource_translator)?,
+                        // Like ${AWS::NoValue}, a variable mapped to AWS::NoValue contributes
+                        // nothing to the string.
+                        Some(ResourceIr::NoValue) => {
+                            resource_translator.explain(format!(
+                                "resolved ${{{}}} from the Fn::Sub variable map as an empty string",
+                                x
+                            ));
+                            ResourceIr::String(String::new())
+                        }
+                        Some(val) => {
+                            resource_translator.explain(format!(
+                                "resolved ${{{}}} from the Fn::Sub variable map",
+                                x
+                            ));
+                            val.clone()
+                        }
                     },
-                })
-                .collect();
+                };
+                r.push(ir);
+            }
             Ok(ResourceIr::Sub(r))
         }
         ResourceValue::FindInMap(mapper, first, second) => {
             let mapper_str = translate_resource(mapper, resource_translator)?;
             let first_str = translate_resource(first, resource_translator)?;
             let second_str = translate_resource(second, resource_translator)?;
+            if let ResourceIr::String(mapping_name) = &mapper_str {
+                mappings::validate_lookup(
+                    resource_translator.parse_tree,
+                    mapping_name,
+                    first_str.as_str(),
+                    second_str.as_str(),
+                )?;
+            }
             Ok(ResourceIr::Map(
                 Box::new(mapper_str),
                 Box::new(first_str),
@@ -238,11 +1218,31 @@ fn translate_resource(
             ))
         }
         ResourceValue::Join(x) => {
-            let sep = x.get(0).unwrap();
-
-            let sep = match sep {
-                ResourceValue::String(x) => x,
-                _ => return Err(TransmuteError::new("Separator for join must be a string")),
+            // The separator is read before any intrinsic is resolved, so it can only be a literal.
+            let sep = match x.get(0) {
+                Some(ResourceValue::String(x)) => x,
+                Some(sep) => {
+                    let found = match sep.intrinsic() {
+                        Some(intrinsic) => intrinsic.key(),
+                        None => "a non-string value",
+                    };
+                    return Err(TransmuteError {
+                        details: format!(
+                            "Fn::Join separator must be a literal string, found {} in {}",
+                            found, resource_translator.resource_name
+                        ),
+                        path: Vec::new(),
+                    });
+                }
+                None => {
+                    return Err(TransmuteError {
+                        details: format!(
+                            "Fn::Join must have a separator in {}",
+                            resource_translator.resource_name
+                        ),
+                        path: Vec::new(),
+                    })
+                }
             };
 
             let iterator = x.iter().skip(1);
@@ -255,22 +1255,304 @@ fn translate_resource(
 
             Ok(ResourceIr::Join(sep.to_string(), irs))
         }
-        ResourceValue::Ref(x) => Ok(ResourceIr::Ref(find_ref(x, resource_translator.parse_tree))),
+        ResourceValue::Base64(x) => {
+            let ir = translate_resource(x, resource_translator)?;
+            Ok(ResourceIr::Base64(Box::new(ir)))
+        }
+        ResourceValue::ImportValue(x) => {
+            let name = translate_resource(x, resource_translator)?;
+            Ok(ResourceIr::ImportValue(Box::new(name)))
+        }
+        ResourceValue::ToJsonString(x) => {
+            // Whatever is being serialized is free-form json, not the property's own type.
+            let mut new_rt = resource_translator.clone();
+            new_rt.complexity = Complexity::Simple(SimpleType::Json);
+            new_rt.is_map = false;
+            new_rt.property_type = Option::None;
+            let ir = translate_resource(x, &new_rt)?;
+            Ok(ResourceIr::ToJsonString(Box::new(ir)))
+        }
+        ResourceValue::Select(index, list) => {
+            let index = match translate_resource(index, resource_translator)? {
+                // Indexes are often written as strings, but are always numbers.
+                ResourceIr::String(x) => match x.parse::<i64>() {
+                    Ok(n) => ResourceIr::Number(n),
+                    Err(_) => {
+                        return Err(TransmuteError::new(
+                            format!("Fn::Select index must be a number, found {}", x).as_str(),
+                        ))
+                    }
+                },
+                x => x,
+            };
+            let list = translate_resource(list, resource_translator)?;
+            // Only literal lists have a known length, dynamic ones can't be checked until deploy.
+            if let ResourceIr::Number(n) = index {
+                let out_of_range = match &list {
+                    ResourceIr::Array(_, arr) => n < 0 || n as usize >= arr.len(),
+                    _ => n < 0,
+                };
+                if out_of_range {
+                    return Err(TransmuteError {
+                        details: format!(
+                            "Fn::Select index {} is out of range in {}",
+                            n, resource_translator.resource_name
+                        ),
+                        path: Vec::new(),
+                    });
+                }
+            }
+            Ok(ResourceIr::Select(Box::new(index), Box::new(list)))
+        }
+        ResourceValue::Split(delimiter, source) => {
+            let delimiter = match delimiter.as_ref() {
+                ResourceValue::String(x) => x,
+                _ => return Err(TransmuteError::new("Delimiter for split must be a string")),
+            };
+            let source = translate_resource(source, resource_translator)?;
+            Ok(ResourceIr::Split(delimiter.to_string(), Box::new(source)))
+        }
+        ResourceValue::GetAZs(region) => {
+            let region = translate_resource(region, resource_translator)?;
+            Ok(ResourceIr::GetAZs(Box::new(region)))
+        }
+        ResourceValue::Ref(x) => {
+            let reference = match find_ref(x, resource_translator.parse_tree) {
+                ResourceIr::Ref(reference) => reference,
+                ir => {
+                    resource_translator.explain(format!("resolved Ref {} as no value", x));
+                    return Ok(ir);
+                }
+            };
+            resource_translator.explain(format!("resolved Ref {} {}", x, explain_ref(&reference)));
+            if reference.origin == Origin::LogicalId {
+                // A Ref may return a name where an id is wanted, or the other way around.
+                let target = resource_translator
+                    .parse_tree
+                    .resources
+                    .resources
+                    .iter()
+                    .find(|r| &r.name == x)
+                    .and_then(|r| ref_value(&r.resource_type));
+                if let Some((_, description)) = target {
+                    resource_translator.explain(format!("Ref {} returns {}", x, description));
+                }
+            }
+            Ok(ResourceIr::Ref(reference))
+        }
+    }
+}
+
+// translate_sub_variable resolves a ${} variable that isn't in the Fn::Sub variable map.
+// Pseudo parameters are matched before anything else, so that no part of their name is
+// mistaken for a logical id or an attribute.
+fn translate_sub_variable(
+    x: &str,
+    resource_translator: &ResourceTranslationInputs,
+) -> Result<ResourceIr, TransmuteError> {
+    if let Some(pseudo) = Reference::match_pseudo_parameter(x) {
+        resource_translator.explain(format!("resolved ${{{}}} as a pseudo parameter", x));
+        return Ok(ResourceIr::Ref(Reference::new(
+            x,
+            Origin::PseudoParameter(pseudo),
+        )));
+    }
+    // AWS::NoValue removes a property, but inside a string there is nothing to remove, so it
+    // contributes nothing.
+    if x == "AWS::NoValue" {
+        resource_translator.explain(format!("resolved ${{{}}} as an empty string", x));
+        return Ok(ResourceIr::String(String::new()));
+    }
+    // Logical ids are alphanumeric, so anything else in the AWS:: namespace was meant to be a
+    // pseudo parameter, just not one that exists, and can't be translated as anything else.
+    if x.starts_with("AWS::") {
+        return Err(TransmuteError::new(&format!(
+            "Fn::Sub variable ${{{}}} could not be resolved, {} is not a pseudo parameter in {}",
+            x, x, resource_translator.resource_name
+        )));
+    }
+
+    let ir = match x.split_once('.') {
+        // ${Resource.Attribute} is shorthand for a GetAtt. Attributes may themselves be
+        // dotted (e.g. a nested stack's Outputs.VpcId), so only the first dot separates the
+        // logical id from the attribute.
+        Some((name, attribute)) => {
+            resource_translator.explain(format!(
+                "resolved ${{{}}} as GetAtt of {} because it contains a '.'",
+                x, name
+            ));
+            ResourceIr::GetAtt(name.to_string(), attribute.to_string())
+        }
+        None => {
+            let ir = find_ref(x, resource_translator.parse_tree);
+            if let ResourceIr::Ref(reference) = &ir {
+                resource_translator.explain(format!(
+                    "resolved ${{{}}} {}",
+                    x,
+                    explain_ref(reference)
+                ));
+            }
+            ir
+        }
+    };
+
+    Ok(ir)
+}
+
+// find_unresolved_sub_variables reports each Fn::Sub variable that names neither a parameter, a
+// pseudo parameter nor a resource, in the resources that were translated and the outputs. These
+// are translated as references all the same, as they are most often a misspelling, or a shell
+// variable that wasn't escaped, which can be fixed by hand.
+pub fn find_unresolved_sub_variables(
+    parse_tree: &CloudformationParseTree,
+    resource_instructions: &[ResourceInstruction],
+) -> Vec<String> {
+    let values = parse_tree
+        .resources
+        .resources
+        .iter()
+        .filter(|r| resource_instructions.iter().any(|x| x.name == r.name))
+        .flat_map(|r| {
+            r.properties
+                .values()
+                .chain(r.metadata.iter())
+                .map(move |x| (r.name.as_str(), x))
+        })
+        .chain(
+            parse_tree
+                .outputs
+                .outputs
+                .iter()
+                .map(|x| (x.name.as_str(), &x.value)),
+        );
+
+    let mut warnings = Vec::new();
+    for (owner, value) in values {
+        let mut variables = Vec::new();
+        find_sub_variables(value, &mut variables);
+        for variable in variables {
+            // ${Resource.Attribute} is a GetAtt, of which only the logical id can be checked.
+            let name = variable.split_once('.').map_or(variable.as_str(), |x| x.0);
+            if Reference::match_pseudo_parameter(name).is_some()
+                || name == "AWS::NoValue"
+                || parse_tree.parameters.params.contains_key(name)
+            {
+                continue;
+            }
+            if let Some(problem) = check_sub_variable(name, parse_tree) {
+                warnings.push(format!("{}: {}", owner, problem));
+            }
+        }
     }
+    warnings
 }
 
-fn find_ref(x: &str, parse_tree: &CloudformationParseTree) -> Reference {
+// find_sub_variables collects the ${} variables of each Fn::Sub within value that aren't in the
+// Fn::Sub's own variable map.
+fn find_sub_variables(value: &ResourceValue, variables: &mut Vec<String>) {
+    match value {
+        ResourceValue::Null
+        | ResourceValue::Bool(_)
+        | ResourceValue::Number(_)
+        | ResourceValue::Double(_)
+        | ResourceValue::String(_)
+        | ResourceValue::Ref(_) => {}
+        ResourceValue::Sub(arr) => {
+            let mut mapped = HashSet::new();
+            for x in arr.iter().skip(1) {
+                if let ResourceValue::Object(obj) = x {
+                    mapped.extend(obj.keys());
+                    obj.values().for_each(|x| find_sub_variables(x, variables));
+                }
+            }
+            // A malformed Fn::Sub fails translation, so it has nothing to report.
+            let vars = match arr.first() {
+                Some(ResourceValue::String(x)) => sub_parse_tree(x).unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            for x in vars {
+                if let SubValue::Variable(x) = x {
+                    if !mapped.contains(&x) && !variables.contains(&x) {
+                        variables.push(x);
+                    }
+                }
+            }
+        }
+        ResourceValue::Array(arr) | ResourceValue::Join(arr) => {
+            arr.iter().for_each(|x| find_sub_variables(x, variables))
+        }
+        ResourceValue::Object(o) => o.values().for_each(|x| find_sub_variables(x, variables)),
+        ResourceValue::FindInMap(x, y, z) | ResourceValue::If(x, y, z) => {
+            find_sub_variables(x, variables);
+            find_sub_variables(y, variables);
+            find_sub_variables(z, variables);
+        }
+        ResourceValue::GetAtt(x, y) | ResourceValue::Select(x, y) | ResourceValue::Split(x, y) => {
+            find_sub_variables(x, variables);
+            find_sub_variables(y, variables);
+        }
+        ResourceValue::ToJsonString(x)
+        | ResourceValue::GetAZs(x)
+        | ResourceValue::ImportValue(x)
+        | ResourceValue::Base64(x) => find_sub_variables(x, variables),
+    }
+}
+
+// check_sub_variable returns why a Fn::Sub variable that isn't a parameter or pseudo parameter
+// doesn't name a resource either, or None when it does. Misspellings are common, so it suggests
+// the closest name it could have meant.
+fn check_sub_variable(name: &str, parse_tree: &CloudformationParseTree) -> Option<String> {
+    let resources = &parse_tree.resources.resources;
+    if resources.iter().any(|r| r.name == name) {
+        return Option::None;
+    }
+
+    let candidates = resources
+        .iter()
+        .map(|r| r.name.as_str())
+        .chain(parse_tree.parameters.params.keys().map(|p| p.as_str()));
+    let problem = match closest_match(name, candidates) {
+        // Nothing is close, which is most often a shell variable in a script, e.g. UserData,
+        // that was meant to be left for the shell.
+        None => format!(
+            "Fn::Sub variable ${{{}}} could not be resolved, if it is a shell variable it has to be escaped as ${{!{}}}",
+            name, name
+        ),
+        Some(suggestion) => format!(
+            "Fn::Sub variable ${{{}}} could not be resolved, did you mean ${{{}}}?",
+            name, suggestion
+        ),
+    };
+    Option::Some(problem)
+}
+
+fn explain_ref(reference: &Reference) -> &'static str {
+    match reference.origin {
+        Origin::PseudoParameter(_) => "as a pseudo parameter",
+        Origin::Parameter => "as a parameter",
+        Origin::Condition => "as a condition",
+        Origin::LogicalId => "as LogicalId ref because it is not a parameter or pseudo parameter",
+    }
+}
+
+// find_ref resolves what a Ref names. AWS::NoValue names nothing, so it is NoValue rather than
+// a reference.
+fn find_ref(x: &str, parse_tree: &CloudformationParseTree) -> ResourceIr {
+    if x == "AWS::NoValue" {
+        return ResourceIr::NoValue;
+    }
+
     let opt_pseudo = Reference::match_pseudo_parameter(x);
 
     if let Some(pseudo) = opt_pseudo {
-        return Reference::new(x, Origin::PseudoParameter(pseudo));
+        return ResourceIr::Ref(Reference::new(x, Origin::PseudoParameter(pseudo)));
     }
 
     for (name, _) in parse_tree.parameters.params.iter() {
         if name == x {
-            return Reference::new(x, Origin::Parameter);
+            return ResourceIr::Ref(Reference::new(x, Origin::Parameter));
         }
     }
 
-    Reference::new(x, Origin::LogicalId)
+    ResourceIr::Ref(Reference::new(x, Origin::LogicalId))
 }
@@ -0,0 +1,51 @@
+// closest_match finds the candidate a misspelled name was most likely meant to be.
+// Candidates further than a third of the name's length away aren't suggested, as at that
+// point it is more likely the name is simply missing than misspelled.
+pub fn closest_match<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = std::cmp::max(1, name.chars().count() / 3);
+    candidates
+        .into_iter()
+        .map(|candidate| (levenshtein(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+// levenshtein is the number of single character insertions, deletions or substitutions
+// to turn a into b.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        assert_eq!(levenshtein("Bucket", "Bucket"), 0);
+        assert_eq!(levenshtein("Bcuket", "Bucket"), 2);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn closest() {
+        let candidates = vec!["Bucket", "Queue", "BucketPolicy"];
+        assert_eq!(closest_match("Bcuket", candidates.clone()), Some("Bucket"));
+        assert_eq!(closest_match("Topic", candidates), None);
+    }
+}
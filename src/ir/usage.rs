@@ -0,0 +1,131 @@
+use crate::ir::conditions::ConditionIr;
+use crate::ir::reference::Origin;
+use crate::ir::resources::ResourceIr;
+use crate::ir::CloudformationProgramIr;
+use crate::parser::intrinsics::IntrinsicFunction;
+use std::collections::HashMap;
+
+// intrinsic_usage counts how many times each intrinsic function is used in the program, as a
+// rough measure of how much work it is to migrate by hand.
+//
+// The ${} variables of a Fn::Sub are part of the Sub, so they aren't counted as Refs or GetAtts.
+// AWS::NoValue is translated to NoValue rather than a Ref, so it isn't counted either.
+pub fn intrinsic_usage(ir: &CloudformationProgramIr) -> HashMap<IntrinsicFunction, usize> {
+    let mut usage = HashMap::new();
+    for cond in ir.conditions.iter() {
+        count_condition(&cond.value, &mut usage);
+    }
+    for resource in ir.resources.iter() {
+        for value in resource
+            .properties
+            .values()
+            .chain(resource.metadata.iter())
+            .chain(resource.creation_policy.iter())
+        {
+            count_resource(value, &mut usage);
+        }
+    }
+    usage
+}
+
+fn count_condition(value: &ConditionIr, usage: &mut HashMap<IntrinsicFunction, usize>) {
+    let intrinsic = match value {
+        ConditionIr::And(x) => {
+            x.iter().for_each(|v| count_condition(v, usage));
+            IntrinsicFunction::And
+        }
+        ConditionIr::Or(x) => {
+            x.iter().for_each(|v| count_condition(v, usage));
+            IntrinsicFunction::Or
+        }
+        ConditionIr::Not(x) => {
+            count_condition(x, usage);
+            IntrinsicFunction::Not
+        }
+        ConditionIr::Equals(a, b) => {
+            count_condition(a, usage);
+            count_condition(b, usage);
+            IntrinsicFunction::Equals
+        }
+        ConditionIr::Map(mapper, first, second) => {
+            for x in [mapper, first, second] {
+                count_condition(x, usage);
+            }
+            IntrinsicFunction::FindInMap
+        }
+        ConditionIr::Ref(x) if x.origin == Origin::Condition => IntrinsicFunction::Condition,
+        ConditionIr::Ref(_) => IntrinsicFunction::Ref,
+        ConditionIr::Str(_) => return,
+    };
+    *usage.entry(intrinsic).or_insert(0) += 1;
+}
+
+fn count_resource(value: &ResourceIr, usage: &mut HashMap<IntrinsicFunction, usize>) {
+    let intrinsic = match value {
+        ResourceIr::Null
+        | ResourceIr::NoValue
+        | ResourceIr::Bool(_)
+        | ResourceIr::Number(_)
+        | ResourceIr::Double(_)
+        | ResourceIr::String(_) => return,
+        ResourceIr::Array(_, arr) => {
+            arr.iter().for_each(|v| count_resource(v, usage));
+            return;
+        }
+        ResourceIr::Object(_, o) => {
+            o.values().for_each(|v| count_resource(v, usage));
+            return;
+        }
+        ResourceIr::Ref(_) => IntrinsicFunction::Ref,
+        ResourceIr::GetAtt(_, _) => IntrinsicFunction::GetAtt,
+        ResourceIr::Sub(arr) => {
+            for part in arr.iter() {
+                if !matches!(part, ResourceIr::Ref(_) | ResourceIr::GetAtt(_, _)) {
+                    count_resource(part, usage);
+                }
+            }
+            IntrinsicFunction::Sub
+        }
+        ResourceIr::If(_, true_expr, false_expr) => {
+            count_resource(true_expr, usage);
+            count_resource(false_expr, usage);
+            IntrinsicFunction::If
+        }
+        ResourceIr::Join(_, arr) => {
+            arr.iter().for_each(|v| count_resource(v, usage));
+            IntrinsicFunction::Join
+        }
+        ResourceIr::Map(mapper, first, second) => {
+            for x in [mapper, first, second] {
+                count_resource(x, usage);
+            }
+            IntrinsicFunction::FindInMap
+        }
+        ResourceIr::Select(index, list) => {
+            count_resource(index, usage);
+            count_resource(list, usage);
+            IntrinsicFunction::Select
+        }
+        ResourceIr::GetAZs(x) => {
+            count_resource(x, usage);
+            IntrinsicFunction::GetAZs
+        }
+        ResourceIr::Base64(x) => {
+            count_resource(x, usage);
+            IntrinsicFunction::Base64
+        }
+        ResourceIr::Split(_, x) => {
+            count_resource(x, usage);
+            IntrinsicFunction::Split
+        }
+        ResourceIr::ImportValue(x) => {
+            count_resource(x, usage);
+            IntrinsicFunction::ImportValue
+        }
+        ResourceIr::ToJsonString(x) => {
+            count_resource(x, usage);
+            IntrinsicFunction::ToJsonString
+        }
+    };
+    *usage.entry(intrinsic).or_insert(0) += 1;
+}
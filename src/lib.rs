@@ -1,16 +1,27 @@
 #![allow(dead_code)]
 
+#[cfg(feature = "bundled-spec")]
+use crate::ir::CloudformationProgramIr;
 use crate::parser::condition::{build_conditions, ConditionsParseTree};
+use crate::parser::globals::{apply_globals, has_serverless_transform};
+use crate::parser::language_extensions::find_undeclared_language_extensions;
+use crate::parser::logical_ids::{check_logical_ids, check_yaml_logical_ids};
 use crate::parser::lookup_table::{build_mappings, MappingsParseTree};
+use crate::parser::outputs::{build_outputs, OutputsParseTree};
 use crate::parser::parameters::{build_parameters, Parameters};
-use crate::parser::resource::{build_resources, ResourceValue, ResourcesParseTree};
-use serde_json::Value;
+use crate::parser::resource::{
+    build_resources, strip_designer_metadata, ResourceValue, ResourcesParseTree,
+};
+use crate::parser::yaml::yaml_to_json;
+use serde_json::{Map, Value};
 
 pub mod integrations;
 pub mod ir;
 pub mod parser;
 pub mod specification;
 pub mod synthesizer;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub trait CustomIntegration {
     fn is_type(resource_type: &str) -> bool;
@@ -20,14 +31,47 @@ pub trait CustomIntegration {
 #[derive(Debug)]
 pub struct TransmuteError {
     details: String,
+    // path is where in the template the error was found, e.g.
+    // ["Resources", "MyInstance", "Properties", "UserData"]. It is empty when that isn't known.
+    path: Vec<String>,
+}
+
+impl std::fmt::Display for TransmuteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.details),
+            false => write!(f, "{}: {}", self.path.join("."), self.details),
+        }
+    }
 }
 
 impl TransmuteError {
     fn new(msg: &str) -> TransmuteError {
         TransmuteError {
             details: msg.to_string(),
+            path: Vec::new(),
         }
     }
+
+    // within places the error within segment of the template, as the error is passed back up
+    // from where it was found, so the path is built outermost segment first.
+    fn within(mut self, segment: &str) -> TransmuteError {
+        self.path.insert(0, segment.to_string());
+        self
+    }
+
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+}
+
+// transmute reads a template, json or YAML, and translates every section of it, returning the
+// instructions for its parameters, conditions, mappings, resources and outputs in one call.
+#[cfg(feature = "bundled-spec")]
+pub fn transmute(template: &str) -> Result<CloudformationProgramIr, TransmuteError> {
+    let parse_tree =
+        CloudformationParseTree::build_from_template_str(template, &ParseOptions::default())?;
+    CloudformationProgramIr::new_from_parse_tree(&parse_tree)
 }
 
 pub struct Import {
@@ -40,30 +84,135 @@ pub struct CdkBuilder {
     imports: Vec<Import>,
 }
 
+// ParseOptions changes how a template is read, for templates that need more than CloudFormation.
+#[derive(Debug, Default, Clone)]
+pub struct ParseOptions {
+    // merge_sam_globals applies the SAM Globals section to the resources of SAM templates.
+    pub merge_sam_globals: bool,
+    // keep_designer_metadata keeps the layout the CloudFormation Designer stores in each
+    // resource's Metadata, which templates exported from the console carry. It means nothing
+    // to the resource, so it is dropped by default.
+    pub keep_designer_metadata: bool,
+}
+
 #[derive(Debug)]
 pub struct CloudformationParseTree {
     pub parameters: Parameters,
     pub mappings: MappingsParseTree,
     pub conditions: ConditionsParseTree,
     pub resources: ResourcesParseTree,
+    pub outputs: OutputsParseTree,
+    // warnings are parts of the template that could be read, but not faithfully.
+    pub warnings: Vec<String>,
 }
 
 impl CloudformationParseTree {
+    // build_from_str reads a template straight from the contents of a template file.
+    pub fn build_from_str(
+        contents: &str,
+        options: &ParseOptions,
+    ) -> Result<CloudformationParseTree, TransmuteError> {
+        // Some editors start files with a byte order mark, which isn't valid json.
+        let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+        check_logical_ids(contents)?;
+        let json_obj: Value = match serde_json::from_str(contents) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(TransmuteError {
+                    details: format!("Template could not be read: {}", e),
+                    path: Vec::new(),
+                })
+            }
+        };
+
+        Self::build_with_options(&json_obj, options)
+    }
+
+    // build_from_yaml_str reads a template from the contents of a YAML template file, including
+    // its short form intrinsics.
+    pub fn build_from_yaml_str(
+        contents: &str,
+        options: &ParseOptions,
+    ) -> Result<CloudformationParseTree, TransmuteError> {
+        let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+        check_yaml_logical_ids(contents)?;
+        let json_obj = yaml_to_json(contents)?;
+
+        Self::build_with_options(&json_obj, options)
+    }
+
+    // build_from_template_str reads a template that may be either json or YAML. Json templates
+    // are always objects, so anything else is read as YAML.
+    pub fn build_from_template_str(
+        contents: &str,
+        options: &ParseOptions,
+    ) -> Result<CloudformationParseTree, TransmuteError> {
+        // Some editors start files with a byte order mark, which both readers strip themselves.
+        match contents.trim_start_matches('\u{feff}').trim_start() {
+            x if x.starts_with('{') => Self::build_from_str(contents, options),
+            _ => Self::build_from_yaml_str(contents, options),
+        }
+    }
+
     pub fn build(json_obj: &Value) -> Result<CloudformationParseTree, TransmuteError> {
-        let parameters = match json_obj["Parameters"].as_object() {
-            None => Parameters::new(),
-            Some(params) => build_parameters(params)?,
+        Self::build_with_options(json_obj, &ParseOptions::default())
+    }
+
+    pub fn build_with_options(
+        json_obj: &Value,
+        options: &ParseOptions,
+    ) -> Result<CloudformationParseTree, TransmuteError> {
+        // An empty Parameters section is as often written as `Parameters:` (null) as it is `{}`.
+        let parameters = match &json_obj["Parameters"] {
+            Value::Null => Parameters::new(),
+            Value::Object(params) => build_parameters(params)?,
+            _ => return Err(TransmuteError::new("Parameters must be an object")),
+        };
+        let conditions = match &json_obj["Conditions"] {
+            Value::Null => build_conditions(&Map::new())?,
+            Value::Object(conditions) => build_conditions(conditions)?,
+            _ => return Err(TransmuteError::new("Conditions must be an object")),
+        };
+        let mut resources = match &json_obj["Resources"] {
+            Value::Null => return Err(TransmuteError::new("Template has no Resources")),
+            Value::Object(resources) => build_resources(resources)?,
+            _ => return Err(TransmuteError::new("Resources must be an object")),
+        };
+        if !options.keep_designer_metadata {
+            strip_designer_metadata(&mut resources);
+        }
+        let mut warnings = find_undeclared_language_extensions(json_obj);
+        if let Some(globals) = json_obj["Globals"].as_object() {
+            match options.merge_sam_globals && has_serverless_transform(json_obj) {
+                true => {
+                    apply_globals(globals, &mut resources)?;
+                    warnings.push(String::from(
+                        "Globals were merged into resources, which only partially emulates SAM",
+                    ));
+                }
+                false => warnings.push(String::from(
+                    "Globals were not merged into resources, so the resources they apply to are missing them",
+                )),
+            }
+        }
+        let mappings = match &json_obj["Mappings"] {
+            Value::Null => MappingsParseTree::new(),
+            Value::Object(mappings) => build_mappings(mappings)?,
+            _ => return Err(TransmuteError::new("Mappings must be an object")),
+        };
+        let outputs = match &json_obj["Outputs"] {
+            Value::Null => OutputsParseTree::default(),
+            Value::Object(outputs) => build_outputs(outputs)?,
+            _ => return Err(TransmuteError::new("Outputs must be an object")),
         };
-        let conditions = build_conditions(json_obj["Conditions"].as_object().unwrap())?;
-        let resources = build_resources(json_obj["Resources"].as_object().unwrap())?;
-        let mappings: MappingsParseTree =
-            build_mappings(json_obj["Mappings"].as_object().unwrap())?;
 
         Ok(CloudformationParseTree {
             parameters,
             conditions,
             resources,
             mappings,
+            outputs,
+            warnings,
         })
     }
 }
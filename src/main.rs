@@ -1,8 +1,8 @@
 use clap::{App, Arg};
 use noctilucent::ir::CloudformationProgramIr;
+use noctilucent::synthesizer::json_synthesizer::JsonSynthesizer;
 use noctilucent::synthesizer::typescript_synthesizer::TypescriptSynthesizer;
-use noctilucent::CloudformationParseTree;
-use serde_json::Value;
+use noctilucent::{CloudformationParseTree, ParseOptions};
 use std::fs;
 
 fn main() {
@@ -16,13 +16,50 @@ fn main() {
                 .required(true)
                 .index(1),
         )
+        .arg(
+            Arg::new("explain")
+                .long("explain")
+                .about("Prints the reason behind each translation decision to stderr"),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .about("Outputs the normalized CloudFormation json instead of typescript"),
+        )
+        .arg(
+            Arg::new("pretty")
+                .long("pretty")
+                .requires("json")
+                .about("Indents the json output"),
+        )
         .get_matches();
 
     let txt_location: &str = matches.value_of("INPUT").unwrap();
     let contents = fs::read_to_string(txt_location).unwrap();
-    let value: Value = serde_json::from_str(contents.as_str()).unwrap();
 
-    let cfn_tree = CloudformationParseTree::build(&value).unwrap();
-    let ir = CloudformationProgramIr::new_from_parse_tree(&cfn_tree).unwrap();
-    TypescriptSynthesizer::output(ir);
+    let options = ParseOptions::default();
+    let cfn_tree = if txt_location.ends_with(".yaml") || txt_location.ends_with(".yml") {
+        CloudformationParseTree::build_from_yaml_str(contents.as_str(), &options).unwrap()
+    } else {
+        CloudformationParseTree::build_from_str(contents.as_str(), &options).unwrap()
+    };
+    let ir = if matches.is_present("explain") {
+        let (ir, explanations) =
+            CloudformationProgramIr::explain_from_parse_tree(&cfn_tree).unwrap();
+        for explanation in explanations.iter() {
+            eprintln!("{}: {}", explanation.resource, explanation.message);
+        }
+        ir
+    } else {
+        CloudformationProgramIr::new_from_parse_tree(&cfn_tree).unwrap()
+    };
+    for warning in ir.warnings.iter() {
+        eprintln!("warning: {}", warning);
+    }
+    if matches.is_present("json") {
+        let synthesizer = JsonSynthesizer::new(matches.is_present("pretty"));
+        println!("{}", synthesizer.output(&ir));
+    } else {
+        TypescriptSynthesizer::output(ir);
+    }
 }
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+// Templates are read as json, which has no comments. YAML templates often carry comments
+// that matter for review, so extract_resource_comments reads them from the YAML source
+// directly, to be attached to the translated resources.
+//
+// This is a line scanner, not a YAML parser: it keeps the comment lines directly above a
+// resource's logical id, and any comment on the same line, under the top level Resources key.
+pub fn extract_resource_comments(yaml: &str) -> HashMap<String, Vec<String>> {
+    let mut comments = HashMap::new();
+    let mut in_resources = false;
+    let mut resource_indent: Option<usize> = Option::None;
+    let mut pending: Vec<String> = Vec::new();
+
+    for line in yaml.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if trimmed.is_empty() {
+            pending.clear();
+            continue;
+        }
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            pending.push(comment.trim().to_string());
+            continue;
+        }
+
+        // A new top level key starts or ends the Resources section.
+        if indent == 0 {
+            in_resources = trimmed.starts_with("Resources:");
+            resource_indent = Option::None;
+            pending.clear();
+            continue;
+        }
+        if !in_resources {
+            pending.clear();
+            continue;
+        }
+
+        // Logical ids are the first keys found in the section, every deeper line is a property.
+        let indent_of_ids = *resource_indent.get_or_insert(indent);
+        if indent == indent_of_ids {
+            let (key, inline_comment) = match trimmed.split_once('#') {
+                None => (trimmed, Option::None),
+                Some((key, comment)) => (key, Option::Some(comment.trim().to_string())),
+            };
+            if let Some(logical_id) = key.trim_end().strip_suffix(':') {
+                let mut resource_comments = std::mem::take(&mut pending);
+                resource_comments.extend(inline_comment);
+                if !resource_comments.is_empty() {
+                    comments.insert(logical_id.trim().to_string(), resource_comments);
+                }
+            }
+        }
+        pending.clear();
+    }
+
+    comments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_above_and_inline() {
+        let yaml = "\
+# The template comment
+Resources:
+  # Stores the access logs.
+  # Do not rename, other stacks import it.
+  LogBucket:
+    Type: AWS::S3::Bucket
+    Properties:
+      # Not a resource comment
+      BucketName: logs
+
+  Queue: # Work queue
+    Type: AWS::SQS::Queue
+Outputs:
+  # Not a resource comment
+  QueueArn:
+    Value: !GetAtt Queue.Arn
+";
+        let comments = extract_resource_comments(yaml);
+        assert_eq!(comments.len(), 2);
+        assert_eq!(
+            comments.get("LogBucket").unwrap(),
+            &vec![
+                "Stores the access logs.".to_string(),
+                "Do not rename, other stacks import it.".to_string()
+            ]
+        );
+        assert_eq!(
+            comments.get("Queue").unwrap(),
+            &vec!["Work queue".to_string()]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::parser::intrinsics::IntrinsicFunction;
 use crate::TransmuteError;
 use serde_json::{Map, Value};
 use std::collections::HashMap;
@@ -67,15 +68,28 @@ fn build_condition_recursively(name: &str, obj: &Value) -> Result<ConditionValue
         _ => {
             return Err(TransmuteError {
                 details: format!("Condition must be an object or string {}, {:?}", name, obj),
+                path: Vec::new(),
             })
         }
     };
 
+    if val.len() > 1 {
+        let keys: Vec<&str> = val.keys().map(|x| x.as_str()).collect();
+        return Err(TransmuteError {
+            details: format!(
+                "Condition functions must be the only key in their object, found {} {}",
+                keys.join(", "),
+                name
+            ),
+            path: Vec::new(),
+        });
+    }
+
     // there should only be one key, but for now iterate over all keys
     #[allow(clippy::never_loop)]
     for (condition_name, condition_object) in val {
-        let cond: ConditionValue = match condition_name.as_str() {
-            "Fn::And" => {
+        let cond: ConditionValue = match IntrinsicFunction::from_key(condition_name) {
+            Some(IntrinsicFunction::And) => {
                 let mut v: Vec<ConditionValue> = Vec::new();
                 let arr = match condition_object.as_array() {
                     None => {
@@ -92,11 +106,12 @@ fn build_condition_recursively(name: &str, obj: &Value) -> Result<ConditionValue
 
                 ConditionValue::And(v)
             }
-            "Fn::Equals" => {
+            Some(IntrinsicFunction::Equals) => {
                 let arr = match condition_object.as_array() {
                     None => {
                         return Err(TransmuteError {
                             details: format!("Condition must be an array {}", name),
+                            path: Vec::new(),
                         })
                     }
                     Some(x) => x,
@@ -106,6 +121,7 @@ fn build_condition_recursively(name: &str, obj: &Value) -> Result<ConditionValue
                     None => {
                         return Err(TransmuteError {
                             details: format!("Equal condition must have 2 array values {}", name),
+                            path: Vec::new(),
                         })
                     }
                     Some(x) => build_condition_recursively(name, x),
@@ -114,17 +130,19 @@ fn build_condition_recursively(name: &str, obj: &Value) -> Result<ConditionValue
                     None => {
                         return Err(TransmuteError {
                             details: format!("Equal condition must have 2 array values {}", name),
+                            path: Vec::new(),
                         })
                     }
                     Some(x) => build_condition_recursively(name, x),
                 }?;
                 ConditionValue::Equals(Box::new(obj1), Box::new(obj2))
             }
-            "Fn::Not" => {
+            Some(IntrinsicFunction::Not) => {
                 let arr = match condition_object.as_array() {
                     None => {
                         return Err(TransmuteError {
                             details: format!("Condition must be an array {}", name),
+                            path: Vec::new(),
                         })
                     }
                     Some(x) => x,
@@ -134,17 +152,19 @@ fn build_condition_recursively(name: &str, obj: &Value) -> Result<ConditionValue
                     None => {
                         return Err(TransmuteError {
                             details: format!("Equal condition must have 2 array values {}", name),
+                            path: Vec::new(),
                         })
                     }
                     Some(x) => build_condition_recursively(name, x),
                 }?;
                 ConditionValue::Not(Box::new(obj1))
             }
-            "Fn::Or" => {
+            Some(IntrinsicFunction::Or) => {
                 let arr = match condition_object.as_array() {
                     None => {
                         return Err(TransmuteError {
                             details: format!("Condition must be an array {}", name),
+                            path: Vec::new(),
                         })
                     }
                     Some(x) => x,
@@ -158,33 +178,36 @@ fn build_condition_recursively(name: &str, obj: &Value) -> Result<ConditionValue
 
                 ConditionValue::Or(v)
             }
-            "Condition" => {
+            Some(IntrinsicFunction::Condition) => {
                 let condition_name = match condition_object.as_str() {
                     None => {
                         return Err(TransmuteError {
                             details: format!("Condition must a string {}", name),
+                            path: Vec::new(),
                         })
                     }
                     Some(x) => x,
                 };
                 ConditionValue::Condition(condition_name.to_string())
             }
-            "Ref" => {
+            Some(IntrinsicFunction::Ref) => {
                 let ref_name = match condition_object.as_str() {
                     None => {
                         return Err(TransmuteError {
                             details: format!("Condition must a string {}", name),
+                            path: Vec::new(),
                         })
                     }
                     Some(x) => x,
                 };
                 ConditionValue::Ref(ref_name.to_string())
             }
-            "Fn::FindInMap" => {
+            Some(IntrinsicFunction::FindInMap) => {
                 let arr = match condition_object.as_array() {
                     None => {
                         return Err(TransmuteError {
                             details: format!("Condition must be an array {}", name),
+                            path: Vec::new(),
                         })
                     }
                     Some(x) => x,
@@ -195,7 +218,7 @@ fn build_condition_recursively(name: &str, obj: &Value) -> Result<ConditionValue
                 let m3 = build_condition_recursively(name, arr.get(2).unwrap())?;
                 ConditionValue::FindInMap(Box::new(m1), Box::new(m2), Box::new(m3))
             }
-            v => ConditionValue::Str(v.to_string()),
+            _ => ConditionValue::Str(condition_name.to_string()),
         };
 
         return Ok(cond);
@@ -203,5 +226,6 @@ fn build_condition_recursively(name: &str, obj: &Value) -> Result<ConditionValue
 
     Err(TransmuteError {
         details: String::from("Nothing found?"),
+        path: Vec::new(),
     })
 }
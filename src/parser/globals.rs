@@ -0,0 +1,93 @@
+use crate::parser::resource::{build_resources_recursively, ResourceValue, ResourcesParseTree};
+use crate::TransmuteError;
+use serde_json::{Map, Value};
+
+const SERVERLESS_TRANSFORM: &str = "AWS::Serverless-2016-10-31";
+
+// has_serverless_transform is true when the template is a SAM template.
+pub fn has_serverless_transform(json_obj: &Value) -> bool {
+    has_transform(json_obj, SERVERLESS_TRANSFORM)
+}
+
+// has_transform is true when the template declares the transform.
+// Transform can either be a single transform, or a list of them.
+pub fn has_transform(json_obj: &Value, transform: &str) -> bool {
+    match &json_obj["Transform"] {
+        Value::String(x) => x == transform,
+        Value::Array(x) => x.iter().any(|t| t.as_str() == Some(transform)),
+        _ => false,
+    }
+}
+
+// apply_globals emulates how SAM applies the Globals section: every property in a section of
+// Globals is a default for every resource of the matching serverless type. This is only a
+// partial emulation, as SAM has per-property rules that aren't reproduced here.
+pub fn apply_globals(
+    globals: &Map<String, Value>,
+    resources: &mut ResourcesParseTree,
+) -> Result<(), TransmuteError> {
+    for (section, properties) in globals {
+        let resource_type = match section.as_str() {
+            "Function" => "AWS::Serverless::Function",
+            "Api" => "AWS::Serverless::Api",
+            "HttpApi" => "AWS::Serverless::HttpApi",
+            "SimpleTable" => "AWS::Serverless::SimpleTable",
+            "StateMachine" => "AWS::Serverless::StateMachine",
+            "LayerVersion" => "AWS::Serverless::LayerVersion",
+            _ => {
+                return Err(TransmuteError {
+                    details: format!("Globals section {} is not supported by SAM", section),
+                    path: Vec::new(),
+                })
+            }
+        };
+        let properties = match properties.as_object() {
+            None => {
+                return Err(TransmuteError {
+                    details: format!("Globals section {} must be an object", section),
+                    path: Vec::new(),
+                })
+            }
+            Some(x) => x,
+        };
+
+        for resource in resources
+            .resources
+            .iter_mut()
+            .filter(|r| r.resource_type == resource_type)
+        {
+            for (name, value) in properties {
+                let global = build_resources_recursively(name, value)?;
+                let merged = match resource.properties.remove(name) {
+                    None => global,
+                    Some(local) => merge(global, local),
+                };
+                resource.properties.insert(name.to_string(), merged);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// merge follows SAM's rules: maps are merged with the resource's keys winning, lists are
+// appended to the global list, and anything else is overridden by the resource.
+fn merge(global: ResourceValue, local: ResourceValue) -> ResourceValue {
+    match (global, local) {
+        (ResourceValue::Object(mut global), ResourceValue::Object(local)) => {
+            for (key, value) in local {
+                let value = match global.remove(&key) {
+                    None => value,
+                    Some(global_value) => merge(global_value, value),
+                };
+                global.insert(key, value);
+            }
+            ResourceValue::Object(global)
+        }
+        (ResourceValue::Array(mut global), ResourceValue::Array(local)) => {
+            global.extend(local);
+            ResourceValue::Array(global)
+        }
+        (_, local) => local,
+    }
+}
@@ -0,0 +1,110 @@
+// IntrinsicFunction is every intrinsic function the parser understands. Anything else is read
+// as a plain property, or a plain string within a condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntrinsicFunction {
+    And,
+    Base64,
+    Condition,
+    Equals,
+    FindInMap,
+    GetAZs,
+    GetAtt,
+    If,
+    ImportValue,
+    Join,
+    Not,
+    Or,
+    Ref,
+    Select,
+    Split,
+    Sub,
+    ToJsonString,
+}
+
+impl IntrinsicFunction {
+    pub const ALL: [IntrinsicFunction; 17] = [
+        IntrinsicFunction::And,
+        IntrinsicFunction::Base64,
+        IntrinsicFunction::Condition,
+        IntrinsicFunction::Equals,
+        IntrinsicFunction::FindInMap,
+        IntrinsicFunction::GetAZs,
+        IntrinsicFunction::GetAtt,
+        IntrinsicFunction::If,
+        IntrinsicFunction::ImportValue,
+        IntrinsicFunction::Join,
+        IntrinsicFunction::Not,
+        IntrinsicFunction::Or,
+        IntrinsicFunction::Ref,
+        IntrinsicFunction::Select,
+        IntrinsicFunction::Split,
+        IntrinsicFunction::Sub,
+        IntrinsicFunction::ToJsonString,
+    ];
+
+    // from_key reads the key of an intrinsic function's object, e.g. "Fn::Sub".
+    pub fn from_key(key: &str) -> Option<IntrinsicFunction> {
+        IntrinsicFunction::ALL
+            .iter()
+            .find(|intrinsic| intrinsic.key() == key)
+            .copied()
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            IntrinsicFunction::And => "Fn::And",
+            IntrinsicFunction::Base64 => "Fn::Base64",
+            IntrinsicFunction::Condition => "Condition",
+            IntrinsicFunction::Equals => "Fn::Equals",
+            IntrinsicFunction::FindInMap => "Fn::FindInMap",
+            IntrinsicFunction::GetAZs => "Fn::GetAZs",
+            IntrinsicFunction::GetAtt => "Fn::GetAtt",
+            IntrinsicFunction::If => "Fn::If",
+            IntrinsicFunction::ImportValue => "Fn::ImportValue",
+            IntrinsicFunction::Join => "Fn::Join",
+            IntrinsicFunction::Not => "Fn::Not",
+            IntrinsicFunction::Or => "Fn::Or",
+            IntrinsicFunction::Ref => "Ref",
+            IntrinsicFunction::Select => "Fn::Select",
+            IntrinsicFunction::Split => "Fn::Split",
+            IntrinsicFunction::Sub => "Fn::Sub",
+            IntrinsicFunction::ToJsonString => "Fn::ToJsonString",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::intrinsics::IntrinsicFunction;
+
+    #[test]
+    fn test_from_key() {
+        let keys = [
+            ("Fn::And", IntrinsicFunction::And),
+            ("Fn::Base64", IntrinsicFunction::Base64),
+            ("Condition", IntrinsicFunction::Condition),
+            ("Fn::Equals", IntrinsicFunction::Equals),
+            ("Fn::FindInMap", IntrinsicFunction::FindInMap),
+            ("Fn::GetAZs", IntrinsicFunction::GetAZs),
+            ("Fn::GetAtt", IntrinsicFunction::GetAtt),
+            ("Fn::If", IntrinsicFunction::If),
+            ("Fn::ImportValue", IntrinsicFunction::ImportValue),
+            ("Fn::Join", IntrinsicFunction::Join),
+            ("Fn::Not", IntrinsicFunction::Not),
+            ("Fn::Or", IntrinsicFunction::Or),
+            ("Ref", IntrinsicFunction::Ref),
+            ("Fn::Select", IntrinsicFunction::Select),
+            ("Fn::Split", IntrinsicFunction::Split),
+            ("Fn::Sub", IntrinsicFunction::Sub),
+            ("Fn::ToJsonString", IntrinsicFunction::ToJsonString),
+        ];
+        assert_eq!(keys.len(), IntrinsicFunction::ALL.len());
+        for (key, intrinsic) in keys {
+            assert_eq!(IntrinsicFunction::from_key(key), Some(intrinsic));
+            assert_eq!(intrinsic.key(), key);
+        }
+
+        assert_eq!(IntrinsicFunction::from_key("Fn::Transform"), None);
+        assert_eq!(IntrinsicFunction::from_key("BucketName"), None);
+    }
+}
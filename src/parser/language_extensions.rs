@@ -0,0 +1,50 @@
+use crate::parser::globals::has_transform;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+const LANGUAGE_EXTENSIONS_TRANSFORM: &str = "AWS::LanguageExtensions";
+
+// find_undeclared_language_extensions warns about each intrinsic of the language extensions
+// transform the template uses without declaring the transform, which CloudFormation rejects
+// at deploy time. Fn::ForEach is written as a key of the form Fn::ForEach::<name>.
+pub fn find_undeclared_language_extensions(json_obj: &Value) -> Vec<String> {
+    if has_transform(json_obj, LANGUAGE_EXTENSIONS_TRANSFORM) {
+        return Vec::new();
+    }
+
+    let mut found = BTreeSet::new();
+    find_extensions(json_obj, &mut found);
+    found
+        .iter()
+        .map(|x| {
+            format!(
+                "{} is only available with the {} transform, which the template doesn't declare",
+                x, LANGUAGE_EXTENSIONS_TRANSFORM
+            )
+        })
+        .collect()
+}
+
+fn find_extensions(value: &Value, found: &mut BTreeSet<&'static str>) {
+    match value {
+        Value::Array(arr) => arr.iter().for_each(|x| find_extensions(x, found)),
+        Value::Object(obj) => {
+            for (key, value) in obj {
+                match key.as_str() {
+                    "Fn::Length" => {
+                        found.insert("Fn::Length");
+                    }
+                    "Fn::ToJsonString" => {
+                        found.insert("Fn::ToJsonString");
+                    }
+                    x if x.starts_with("Fn::ForEach::") => {
+                        found.insert("Fn::ForEach");
+                    }
+                    _ => {}
+                }
+                find_extensions(value, found);
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+    }
+}
@@ -0,0 +1,202 @@
+use crate::TransmuteError;
+use serde::de::{Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+
+// Logical ids are the keys of json objects, and serde_json::Value silently keeps only the last
+// duplicate key. check_logical_ids has to work from the raw template text, before any Value
+// has been built, so that a merged or malformed template isn't translated with a resource missing.
+pub fn check_logical_ids(template: &str) -> Result<(), TransmuteError> {
+    match serde_json::from_str(template) {
+        Ok(logical_ids) => check_unique(logical_ids),
+        Err(e) => Err(TransmuteError {
+            details: format!("Template could not be read: {}", e),
+            path: Vec::new(),
+        }),
+    }
+}
+
+// check_yaml_logical_ids is check_logical_ids for YAML templates, which are read from their raw
+// text for the same reason.
+pub fn check_yaml_logical_ids(template: &str) -> Result<(), TransmuteError> {
+    match serde_yaml::from_str(template) {
+        Ok(logical_ids) => check_unique(logical_ids),
+        Err(e) => Err(TransmuteError {
+            details: format!("Template could not be read: {}", e),
+            path: Vec::new(),
+        }),
+    }
+}
+
+fn check_unique(logical_ids: TemplateLogicalIds) -> Result<(), TransmuteError> {
+    // Parameters and resources share a single namespace, so one may not shadow the other.
+    let mut seen = HashSet::new();
+    for logical_id in logical_ids
+        .parameters
+        .iter()
+        .chain(logical_ids.resources.iter())
+    {
+        if !seen.insert(logical_id) {
+            return Err(TransmuteError {
+                details: format!("Logical id {} is declared more than once", logical_id),
+                path: Vec::new(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct TemplateLogicalIds {
+    parameters: Vec<String>,
+    resources: Vec<String>,
+}
+
+impl<'de> Deserialize<'de> for TemplateLogicalIds {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(TemplateVisitor)
+    }
+}
+
+struct TemplateVisitor;
+
+impl<'de> Visitor<'de> for TemplateVisitor {
+    type Value = TemplateLogicalIds;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a cloudformation template object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut logical_ids = TemplateLogicalIds::default();
+        while let Some(section) = map.next_key::<String>()? {
+            match section.as_str() {
+                "Parameters" => logical_ids
+                    .parameters
+                    .extend(map.next_value::<SectionKeys>()?.0),
+                "Resources" => logical_ids
+                    .resources
+                    .extend(map.next_value::<SectionKeys>()?.0),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(logical_ids)
+    }
+}
+
+// SectionKeys is every key of a section, in order, including duplicates.
+struct SectionKeys(Vec<String>);
+
+impl<'de> Deserialize<'de> for SectionKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SectionVisitor)
+    }
+}
+
+struct SectionVisitor;
+
+impl<'de> Visitor<'de> for SectionVisitor {
+    type Value = SectionKeys;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a template section")
+    }
+
+    // An empty section may be written as null.
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(SectionKeys(Vec::new()))
+    }
+
+    // Any other section that isn't an object declares no logical ids, and is reported with a
+    // clearer error once the template is built.
+    fn visit_bool<E>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(SectionKeys(Vec::new()))
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(SectionKeys(Vec::new()))
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(SectionKeys(Vec::new()))
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Self::Value, E> {
+        Ok(SectionKeys(Vec::new()))
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Self::Value, E> {
+        Ok(SectionKeys(Vec::new()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(SectionKeys(Vec::new()))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut keys = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value::<IgnoredAny>()?;
+            keys.push(key);
+        }
+        Ok(SectionKeys(keys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_logical_ids() {
+        let template = r#"{
+            "Parameters": {"Name": {"Type": "String"}},
+            "Resources": {
+                "Bucket": {"Type": "AWS::S3::Bucket"},
+                "Queue": {"Type": "AWS::SQS::Queue"}
+            }
+        }"#;
+        assert!(check_logical_ids(template).is_ok());
+    }
+
+    #[test]
+    fn duplicate_resource_logical_id() {
+        let template = r#"{
+            "Resources": {
+                "Bucket": {"Type": "AWS::S3::Bucket"},
+                "Bucket": {"Type": "AWS::SQS::Queue"}
+            }
+        }"#;
+        let err = check_logical_ids(template).unwrap_err();
+        assert_eq!(err.details, "Logical id Bucket is declared more than once");
+    }
+
+    #[test]
+    fn parameter_shadowing_resource() {
+        let template = r#"{
+            "Parameters": {"Bucket": {"Type": "String"}},
+            "Resources": {"Bucket": {"Type": "AWS::S3::Bucket"}}
+        }"#;
+        assert!(check_logical_ids(template).is_err());
+    }
+
+    #[test]
+    fn duplicate_yaml_resource_logical_id() {
+        let template = "
+Resources:
+  Bucket:
+    Type: AWS::S3::Bucket
+    Properties:
+      BucketName: !Sub ${AWS::StackName}-logs
+  Bucket:
+    Type: AWS::SQS::Queue
+";
+        let err = check_yaml_logical_ids(template).unwrap_err();
+        assert_eq!(err.details, "Logical id Bucket is declared more than once");
+    }
+}
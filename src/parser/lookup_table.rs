@@ -49,13 +49,15 @@ impl MappingParseTree {
 
 /**
  * MappingInnerValue tracks the allowed value types in a Mapping as defined by CloudFormation in the
- * link below. Right now that is either a String or List.
+ * link below. Right now that is either a String, a Number or a List.
  *
  * https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/mappings-section-structure.html#mappings-section-structure-syntax
  */
 #[derive(Debug, Clone)]
 pub enum MappingInnerValue {
     String(String),
+    Number(i64),
+    Double(f64),
     List(Vec<String>),
 }
 
@@ -63,6 +65,8 @@ impl Display for MappingInnerValue {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         return match self {
             MappingInnerValue::String(string_val) => write!(f, "\"{}\"", string_val),
+            MappingInnerValue::Number(number_val) => write!(f, "{}", number_val),
+            MappingInnerValue::Double(double_val) => write!(f, "{:?}", double_val),
             MappingInnerValue::List(list_val) => {
                 let quoted_list_values: Vec<String> =
                     list_val.iter().map(|val| format!("\"{}\"", val)).collect();
@@ -123,6 +127,7 @@ fn convert_to_string_vector(
                         "List values for mappings must be a string. Found {:?}, for key {}",
                         inner_key, vector_val
                     ),
+                    path: Vec::new(),
                 })
             }
         };
@@ -136,6 +141,7 @@ fn ensure_object<'a>(name: &str, obj: &'a Value) -> Result<&'a Map<String, Value
         Value::Object(x) => Ok(x),
         _ => Err(TransmuteError {
             details: format!("Mapping must be an object {}, {:?}", name, obj),
+            path: Vec::new(),
         }),
     };
 }
@@ -143,13 +149,19 @@ fn ensure_object<'a>(name: &str, obj: &'a Value) -> Result<&'a Map<String, Value
 fn ensure_mapping_value_type(name: &str, obj: &Value) -> Result<MappingInnerValue, TransmuteError> {
     return match obj {
         Value::String(x) => Ok(MappingInnerValue::String(x.to_string())),
-        Value::Number(x) => Ok(MappingInnerValue::String(x.to_string())),
+        Value::Number(x) => match (x.as_i64(), x.as_u64()) {
+            (Some(n), _) => Ok(MappingInnerValue::Number(n)),
+            // Integers beyond i64 keep their text, an f64 would round them.
+            (None, Some(_)) => Ok(MappingInnerValue::String(x.to_string())),
+            (None, None) => Ok(MappingInnerValue::Double(x.as_f64().unwrap())),
+        },
         Value::Array(x) => Ok(MappingInnerValue::List(convert_to_string_vector(x, name)?)),
         _ => Err(TransmuteError {
             details: format!(
                 "Inner mapping value must be a string or array. Found {:?}, for {}",
                 name, obj
             ),
+            path: Vec::new(),
         }),
     };
 }
@@ -0,0 +1,96 @@
+use crate::parser::condition::ConditionsParseTree;
+use crate::parser::lookup_table::MappingsParseTree;
+use crate::parser::outputs::{OutputParseTree, OutputsParseTree};
+use crate::parser::parameters::Parameters;
+use crate::parser::resource::ResourcesParseTree;
+use crate::{CloudformationParseTree, TransmuteError};
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+// merge combines several templates into one, for consolidating small stacks into a single one.
+//
+// Every section is unioned. Parameters and resources share one namespace, as a Ref can't tell
+// them apart, and two outputs may not export the same name. Any collision is an error, since
+// there is no telling which of the two definitions was meant.
+pub fn merge(
+    templates: Vec<CloudformationParseTree>,
+) -> Result<CloudformationParseTree, TransmuteError> {
+    let mut parameters = Parameters::new();
+    let mut mappings = MappingsParseTree::new();
+    let mut conditions = ConditionsParseTree {
+        conditions: HashMap::new(),
+    };
+    let mut resources = ResourcesParseTree {
+        resources: Vec::new(),
+    };
+    let mut outputs: Vec<OutputParseTree> = Vec::new();
+    let mut warnings = Vec::new();
+
+    let mut logical_ids = HashSet::new();
+    for template in templates {
+        for (name, param) in template.parameters.params {
+            check_unique(&mut logical_ids, &name, "Logical id")?;
+            parameters.add(param);
+        }
+        for resource in template.resources.resources {
+            check_unique(&mut logical_ids, &resource.name, "Logical id")?;
+            resources.resources.push(resource);
+        }
+        for (name, mapping) in template.mappings.mappings {
+            if mappings.mappings.contains_key(&name) {
+                return Err(collision("Mapping", &name));
+            }
+            mappings.insert(name, mapping);
+        }
+        for (name, condition) in template.conditions.conditions {
+            if conditions.conditions.contains_key(&name) {
+                return Err(collision("Condition", &name));
+            }
+            conditions.conditions.insert(name, condition);
+        }
+        for output in template.outputs.outputs {
+            if outputs.iter().any(|x| x.name == output.name) {
+                return Err(collision("Output", &output.name));
+            }
+            if let Some(export_name) = &output.export_name {
+                let taken = outputs
+                    .iter()
+                    .find(|x| x.export_name.as_ref() == Some(export_name));
+                if let Some(other) = taken {
+                    return Err(TransmuteError {
+                        details: format!(
+                            "Outputs {} and {} have the same export name",
+                            other.name, output.name
+                        ),
+                        path: Vec::new(),
+                    });
+                }
+            }
+            outputs.push(output);
+        }
+        warnings.extend(template.warnings);
+    }
+
+    Ok(CloudformationParseTree {
+        parameters,
+        mappings,
+        conditions,
+        resources,
+        outputs: OutputsParseTree { outputs },
+        warnings,
+    })
+}
+
+fn check_unique(seen: &mut HashSet<String>, name: &str, kind: &str) -> Result<(), TransmuteError> {
+    if !seen.insert(name.to_string()) {
+        return Err(collision(kind, name));
+    }
+    Ok(())
+}
+
+fn collision(kind: &str, name: &str) -> TransmuteError {
+    TransmuteError {
+        details: format!("{} {} is defined by more than one template", kind, name),
+        path: Vec::new(),
+    }
+}
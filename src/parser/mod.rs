@@ -1,5 +1,13 @@
+pub mod comments;
 pub mod condition;
+pub mod globals;
+pub mod intrinsics;
+pub mod language_extensions;
+pub mod logical_ids;
 pub mod lookup_table;
+pub mod merge;
+pub mod outputs;
 pub mod parameters;
 pub mod resource;
 pub mod sub;
+pub mod yaml;
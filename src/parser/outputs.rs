@@ -0,0 +1,63 @@
+use crate::parser::resource::{build_resources_recursively, ResourceValue};
+use crate::TransmuteError;
+use serde_json::{Map, Value};
+
+#[derive(Debug, PartialEq)]
+pub struct OutputParseTree {
+    pub name: String,
+    pub value: ResourceValue,
+    pub condition: Option<String>,
+    pub description: Option<String>,
+    // export_name is the Name of the output's Export, when it is exported.
+    pub export_name: Option<ResourceValue>,
+}
+
+#[derive(Debug, Default)]
+pub struct OutputsParseTree {
+    pub outputs: Vec<OutputParseTree>,
+}
+
+pub fn build_outputs(vals: &Map<String, Value>) -> Result<OutputsParseTree, TransmuteError> {
+    let mut outputs = Vec::new();
+    for (name, obj) in vals {
+        let value = match obj.get("Value") {
+            Some(x) => build_resources_recursively(name, x)?,
+            None => {
+                return Err(TransmuteError {
+                    details: format!("Output must have a Value {}", name),
+                    path: Vec::new(),
+                })
+            }
+        };
+        let condition = obj
+            .get("Condition")
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_string());
+        let description = obj
+            .get("Description")
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_string());
+        let export_name = match obj.get("Export") {
+            None => Option::None,
+            Some(export) => match export.get("Name") {
+                Some(x) => Option::Some(build_resources_recursively(name, x)?),
+                None => {
+                    return Err(TransmuteError {
+                        details: format!("Export must have a Name {}", name),
+                        path: Vec::new(),
+                    })
+                }
+            },
+        };
+
+        outputs.push(OutputParseTree {
+            name: name.to_string(),
+            value,
+            condition,
+            description,
+            export_name,
+        });
+    }
+
+    Ok(OutputsParseTree { outputs })
+}
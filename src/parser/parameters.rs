@@ -26,18 +26,41 @@ pub struct Parameter {
     pub logical_name: String,
     pub parameter_type: String, // TODO - I think this is limited, may want to make it an enum.
     pub default: Option<String>,
+    pub allowed_values: Option<Vec<String>>,
+    pub description: Option<String>,
+    // no_echo masks the parameter's value wherever CloudFormation would show it.
+    pub no_echo: bool,
+    // constraints are the checks CloudFormation makes of a value, e.g. MinLength or
+    // AllowedPattern, along with the ConstraintDescription shown when one fails, as declared.
+    pub constraints: Map<String, Value>,
 }
 
+// CONSTRAINTS are the parameter properties that constrain its value.
+const CONSTRAINTS: &[&str] = &[
+    "AllowedPattern",
+    "ConstraintDescription",
+    "MaxLength",
+    "MaxValue",
+    "MinLength",
+    "MinValue",
+];
+
 impl Parameter {
-    fn new(logical_name: String, parameter_type: String, default: Option<String>) -> Parameter {
-        Parameter {
-            logical_name,
-            parameter_type,
-            default,
-        }
+    // is_list is true when a Ref to this parameter returns a list of values rather than a
+    // single string. CloudFormation passes these in comma-delimited, but resolves them to lists.
+    pub fn is_list(&self) -> bool {
+        is_list_type(&self.parameter_type)
     }
 }
 
+// is_list_type is true for the parameter types that a Ref resolves to a list of values.
+pub fn is_list_type(t: &str) -> bool {
+    t == "CommaDelimitedList"
+        || t.starts_with("List<")
+        || t == "AWS::SSM::Parameter::Value<CommaDelimitedList>"
+        || t.starts_with("AWS::SSM::Parameter::Value<List<")
+}
+
 impl Default for Parameters {
     fn default() -> Self {
         Self::new()
@@ -47,18 +70,80 @@ impl Default for Parameters {
 pub fn build_parameters(vals: &Map<String, Value>) -> Result<Parameters, TransmuteError> {
     let mut params = Parameters::new();
     for (name, obj) in vals {
-        let t = match obj.get("Type") {
+        let t = match obj.get("Type").and_then(|v| v.as_str()) {
             Some(v) => v.to_string(),
             None => {
                 return Err(TransmuteError {
                     details: format!("Type was not specified correctly {}", name),
+                    path: Vec::new(),
                 })
             }
         };
 
         let def: Option<String> = obj.get("Default").map(|d| d.to_string());
+        let allowed_values = match obj.get("AllowedValues") {
+            None => Option::None,
+            Some(Value::Array(arr)) => {
+                let mut v = Vec::new();
+                for allowed in arr {
+                    match allowed {
+                        Value::String(x) => v.push(x.to_string()),
+                        Value::Number(x) => v.push(x.to_string()),
+                        _ => {
+                            return Err(TransmuteError {
+                                details: format!(
+                                    "AllowedValues must only contain strings or numbers {}",
+                                    name
+                                ),
+                                path: Vec::new(),
+                            })
+                        }
+                    }
+                }
+                Option::Some(v)
+            }
+            Some(_) => {
+                return Err(TransmuteError {
+                    details: format!("AllowedValues must be an array {}", name),
+                    path: Vec::new(),
+                })
+            }
+        };
+
+        let description = obj
+            .get("Description")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string());
+        // NoEcho is as often written as the string "true" as it is a boolean.
+        let no_echo = match obj.get("NoEcho") {
+            None => false,
+            Some(Value::Bool(x)) => *x,
+            Some(Value::String(x)) => x.eq_ignore_ascii_case("true"),
+            Some(_) => {
+                return Err(TransmuteError {
+                    details: format!("NoEcho must be a boolean {}", name),
+                    path: Vec::new(),
+                })
+            }
+        };
+
+        let constraints = obj
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(key, _)| CONSTRAINTS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
 
-        params.add(Parameter::new(name.clone(), t, def));
+        params.add(Parameter {
+            logical_name: name.clone(),
+            parameter_type: t,
+            default: def,
+            allowed_values,
+            description,
+            no_echo,
+            constraints,
+        });
     }
 
     Ok(params)
@@ -1,13 +1,17 @@
+use crate::parser::intrinsics::IntrinsicFunction;
 use crate::TransmuteError;
 use serde_json::{Map, Value};
 use std::collections::HashMap;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub enum ResourceValue {
     // Literally just json bits here
     Null,
     Bool(bool),
     Number(i64),
+    // Double holds numbers that aren't integers, or are too large for an i64. 1.0 is kept
+    // a Double, so it is written back out the way the template had it.
+    Double(f64),
     String(String),
     Array(Vec<ResourceValue>),
     Object(HashMap<String, ResourceValue>),
@@ -20,18 +24,53 @@ pub enum ResourceValue {
     If(Box<ResourceValue>, Box<ResourceValue>, Box<ResourceValue>),
     Join(Vec<ResourceValue>),
     Ref(String),
-    // Select
-    // GetAZs
-    // Base64
+    ToJsonString(Box<ResourceValue>),
+    Select(Box<ResourceValue>, Box<ResourceValue>),
+    GetAZs(Box<ResourceValue>),
+    ImportValue(Box<ResourceValue>),
+    Split(Box<ResourceValue>, Box<ResourceValue>),
+    Base64(Box<ResourceValue>),
 }
 
-impl ResourceValue {}
+impl ResourceValue {
+    // intrinsic is the intrinsic function the value calls, or None for literal json.
+    pub fn intrinsic(&self) -> Option<IntrinsicFunction> {
+        let intrinsic = match self {
+            ResourceValue::Null
+            | ResourceValue::Bool(_)
+            | ResourceValue::Number(_)
+            | ResourceValue::Double(_)
+            | ResourceValue::String(_)
+            | ResourceValue::Array(_)
+            | ResourceValue::Object(_) => return Option::None,
+            ResourceValue::Sub(_) => IntrinsicFunction::Sub,
+            ResourceValue::FindInMap(_, _, _) => IntrinsicFunction::FindInMap,
+            ResourceValue::GetAtt(_, _) => IntrinsicFunction::GetAtt,
+            ResourceValue::If(_, _, _) => IntrinsicFunction::If,
+            ResourceValue::Join(_) => IntrinsicFunction::Join,
+            ResourceValue::Ref(_) => IntrinsicFunction::Ref,
+            ResourceValue::ToJsonString(_) => IntrinsicFunction::ToJsonString,
+            ResourceValue::Select(_, _) => IntrinsicFunction::Select,
+            ResourceValue::GetAZs(_) => IntrinsicFunction::GetAZs,
+            ResourceValue::ImportValue(_) => IntrinsicFunction::ImportValue,
+            ResourceValue::Split(_, _) => IntrinsicFunction::Split,
+            ResourceValue::Base64(_) => IntrinsicFunction::Base64,
+        };
 
-#[derive(Debug, Eq, PartialEq)]
+        Option::Some(intrinsic)
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub struct ResourceParseTree {
     pub name: String,
     pub resource_type: String,
     pub condition: Option<String>,
+    pub metadata: Option<ResourceValue>,
+    pub creation_policy: Option<ResourceValue>,
+    pub deletion_policy: Option<String>,
+    pub update_replace_policy: Option<String>,
+    pub depends_on: Vec<String>,
     pub properties: HashMap<String, ResourceValue>,
 }
 
@@ -58,14 +97,59 @@ pub fn build_resources(
             .map(|t| t.as_str())
             .flatten()
             .map(|t| t.to_string());
+        // DependsOn is either a single logical id, or a list of them.
+        let depends_on = match resource_object.get("DependsOn") {
+            None => Vec::new(),
+            Some(Value::String(x)) => vec![x.to_string()],
+            Some(Value::Array(arr)) => {
+                let mut v = Vec::new();
+                for dependency in arr {
+                    match dependency.as_str() {
+                        None => {
+                            return Err(TransmuteError {
+                                details: format!("DependsOn must only contain strings {}", name),
+                                path: Vec::new(),
+                            })
+                        }
+                        Some(x) => v.push(x.to_string()),
+                    }
+                }
+                v
+            }
+            Some(_) => {
+                return Err(TransmuteError {
+                    details: format!("DependsOn must be a string or an array {}", name),
+                    path: Vec::new(),
+                })
+            }
+        };
+        let metadata = match resource_object.get("Metadata") {
+            None => Option::None,
+            Some(m) => Option::Some(build_resources_recursively(name, m)?),
+        };
+        let creation_policy = match resource_object.get("CreationPolicy") {
+            None => Option::None,
+            Some(p) => Option::Some(build_resources_recursively(name, p)?),
+        };
 
+        let deletion_policy = build_policy(name, resource_object, "DeletionPolicy")?;
+        let update_replace_policy = build_policy(name, resource_object, "UpdateReplacePolicy")?;
+
+        // Resources with nothing to configure, e.g. a bucket kept with DeletionPolicy: Retain,
+        // often leave Properties out altogether.
+        let empty = Map::new();
+        let property_map = match resource_object.get("Properties") {
+            None | Some(Value::Null) => &empty,
+            Some(Value::Object(x)) => x,
+            Some(_) => {
+                return Err(TransmuteError {
+                    details: format!("Properties must be an object {}", name),
+                    path: Vec::new(),
+                })
+            }
+        };
         let mut properties = HashMap::new();
-        for (prop_name, prop_value) in resource_object
-            .get("Properties")
-            .map(|x| x.as_object())
-            .flatten()
-            .unwrap()
-        {
+        for (prop_name, prop_value) in property_map {
             let result = build_resources_recursively(name, prop_value)?;
             properties.insert(prop_name.to_owned(), result);
         }
@@ -73,6 +157,11 @@ pub fn build_resources(
             name: name.to_owned(),
             resource_type,
             condition,
+            metadata,
+            creation_policy,
+            deletion_policy,
+            update_replace_policy,
+            depends_on,
             properties,
         })
     }
@@ -80,13 +169,57 @@ pub fn build_resources(
     Ok(ResourcesParseTree { resources })
 }
 
-fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue, TransmuteError> {
+// build_policy reads a policy attribute, e.g. DeletionPolicy, which is one of a few names such
+// as Retain, Snapshot or Delete.
+fn build_policy(
+    name: &str,
+    resource_object: &Map<String, Value>,
+    key: &str,
+) -> Result<Option<String>, TransmuteError> {
+    match resource_object.get(key) {
+        None => Ok(Option::None),
+        Some(Value::String(x)) => Ok(Option::Some(x.to_string())),
+        Some(_) => Err(TransmuteError {
+            details: format!("{} must be a string {}", key, name),
+            path: Vec::new(),
+        }),
+    }
+}
+
+// strip_designer_metadata removes the AWS::CloudFormation::Designer key from each resource's
+// Metadata, along with the Metadata itself when nothing else is left in it.
+pub fn strip_designer_metadata(resources: &mut ResourcesParseTree) {
+    for resource in resources.resources.iter_mut() {
+        if let Some(ResourceValue::Object(metadata)) = resource.metadata.as_mut() {
+            metadata.remove("AWS::CloudFormation::Designer");
+            if metadata.is_empty() {
+                resource.metadata = Option::None;
+            }
+        }
+    }
+}
+
+pub(crate) fn build_resources_recursively(
+    name: &str,
+    obj: &Value,
+) -> Result<ResourceValue, TransmuteError> {
     let val = match obj {
         Value::String(x) => return Ok(ResourceValue::String(x.to_string())),
         Value::Object(x) => x,
         Value::Null => return Ok(ResourceValue::Null),
         Value::Bool(b) => return Ok(ResourceValue::Bool(b.to_owned())),
-        Value::Number(n) => return Ok(ResourceValue::Number(n.as_i64().unwrap())),
+        Value::Number(n) => {
+            // Integers beyond i64 keep their text, an f64 would round them.
+            return match (n.as_i64(), n.as_u64(), n.as_f64()) {
+                (Some(x), _, _) => Ok(ResourceValue::Number(x)),
+                (None, Some(_), _) => Ok(ResourceValue::String(n.to_string())),
+                (None, None, Some(x)) => Ok(ResourceValue::Double(x)),
+                (None, None, None) => Err(TransmuteError {
+                    details: format!("Number {} could not be read in {}", n, name),
+                    path: Vec::new(),
+                }),
+            };
+        }
         Value::Array(arr) => {
             let mut v = Vec::new();
             for item in arr.iter() {
@@ -99,6 +232,20 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
     };
 
     if val.len() > 1 {
+        // An intrinsic must be the only key in its object. "Condition" is left out, as it is
+        // also an ordinary key, e.g. in IAM policy statements.
+        if let Some(key) = val.keys().find(|key| {
+            matches!(IntrinsicFunction::from_key(key), Some(x) if x != IntrinsicFunction::Condition)
+        }) {
+            let keys: Vec<&str> = val.keys().map(|x| x.as_str()).collect();
+            return Err(TransmuteError::new(&format!(
+                "{} must be the only key in its object, found {} {}",
+                key,
+                keys.join(", "),
+                name
+            )));
+        }
+
         let mut hm = HashMap::new();
         for (name, obj) in val {
             hm.insert(name.to_owned(), build_resources_recursively(name, obj)?);
@@ -108,8 +255,8 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
     } else {
         #[allow(clippy::never_loop)]
         for (resource_name, resource_object) in val {
-            let cond: ResourceValue = match resource_name.as_str() {
-                "Fn::Sub" => {
+            let cond: ResourceValue = match IntrinsicFunction::from_key(resource_name) {
+                Some(IntrinsicFunction::Sub) => {
                     let mut v = Vec::new();
                     match resource_object {
                         Value::String(str) => {
@@ -127,12 +274,13 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::Sub can only be eitehr an array or a string {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             });
                         }
                     }
                     ResourceValue::Sub(v)
                 }
-                "Fn::FindInMap" => {
+                Some(IntrinsicFunction::FindInMap) => {
                     let v = match resource_object.as_array() {
                         None => {
                             return Err(TransmuteError {
@@ -140,6 +288,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::Map is supposed to be an array entry {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => x,
@@ -152,6 +301,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::Map is supposed to have 3 values in array, has 0 {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => build_resources_recursively(name, x),
@@ -163,6 +313,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::Map is supposed to have 3 values in array, has 1 {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => build_resources_recursively(name, x),
@@ -174,6 +325,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::Map is supposed to have 3 values in array, has 2 {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => build_resources_recursively(name, x),
@@ -184,7 +336,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                         Box::new(third_obj),
                     )
                 }
-                "Fn::GetAtt" => {
+                Some(IntrinsicFunction::GetAtt) => {
                     let v = match resource_object.as_array() {
                         None => {
                             return Err(TransmuteError {
@@ -192,6 +344,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::Map is supposed to be an array entry {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => x,
@@ -204,6 +357,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::Map is supposed to have 3 values in array, has 0 {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => build_resources_recursively(name, x),
@@ -215,6 +369,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::Map is supposed to have 3 values in array, has 1 {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => build_resources_recursively(name, x),
@@ -222,7 +377,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
 
                     ResourceValue::GetAtt(Box::new(first_obj), Box::new(second_obj))
                 }
-                "Fn::If" => {
+                Some(IntrinsicFunction::If) => {
                     let v = match resource_object.as_array() {
                         None => {
                             return Err(TransmuteError {
@@ -230,6 +385,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::If is supposed to be an array entry {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => x,
@@ -242,6 +398,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::If is supposed to have 3 values in array, has 0 {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => build_resources_recursively(name, x),
@@ -253,6 +410,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::If is supposed to have 3 values in array, has 1 {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => build_resources_recursively(name, x),
@@ -264,6 +422,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::If is supposed to have 3 values in array, has 2 {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => build_resources_recursively(name, x),
@@ -274,7 +433,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                         Box::new(third_obj),
                     )
                 }
-                "Fn::Join" => {
+                Some(IntrinsicFunction::Join) => {
                     let arr = match resource_object.as_array() {
                         None => {
                             return Err(TransmuteError {
@@ -282,6 +441,7 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
                                     "Fn::Map is supposed to be an array entry {}",
                                     name
                                 ),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => x,
@@ -296,28 +456,94 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
 
                     ResourceValue::Join(v)
                 }
-                "Ref" => {
+                Some(IntrinsicFunction::Select) => {
+                    let v = match resource_object.as_array() {
+                        None => {
+                            return Err(TransmuteError {
+                                details: format!(
+                                    "Fn::Select is supposed to be an array entry {}",
+                                    name
+                                ),
+                                path: Vec::new(),
+                            })
+                        }
+                        Some(x) => x,
+                    };
+                    if v.len() != 2 {
+                        return Err(TransmuteError {
+                            details: format!(
+                                "Fn::Select is supposed to have 2 values in array, has {} {}",
+                                v.len(),
+                                name
+                            ),
+                            path: Vec::new(),
+                        });
+                    }
+
+                    let index = build_resources_recursively(name, &v[0])?;
+                    let list = build_resources_recursively(name, &v[1])?;
+                    ResourceValue::Select(Box::new(index), Box::new(list))
+                }
+                Some(IntrinsicFunction::Split) => {
+                    let v = match resource_object.as_array() {
+                        None => {
+                            return Err(TransmuteError {
+                                details: format!(
+                                    "Fn::Split is supposed to be an array entry {}",
+                                    name
+                                ),
+                                path: Vec::new(),
+                            })
+                        }
+                        Some(x) => x,
+                    };
+                    if v.len() != 2 {
+                        return Err(TransmuteError {
+                            details: format!(
+                                "Fn::Split is supposed to have 2 values in array, has {} {}",
+                                v.len(),
+                                name
+                            ),
+                            path: Vec::new(),
+                        });
+                    }
+
+                    let delimiter = build_resources_recursively(name, &v[0])?;
+                    let source = build_resources_recursively(name, &v[1])?;
+                    ResourceValue::Split(Box::new(delimiter), Box::new(source))
+                }
+                Some(IntrinsicFunction::GetAZs) => ResourceValue::GetAZs(Box::new(
+                    build_resources_recursively(name, resource_object)?,
+                )),
+                Some(IntrinsicFunction::Base64) => ResourceValue::Base64(Box::new(
+                    build_resources_recursively(name, resource_object)?,
+                )),
+                Some(IntrinsicFunction::ImportValue) => ResourceValue::ImportValue(Box::new(
+                    build_resources_recursively(name, resource_object)?,
+                )),
+                Some(IntrinsicFunction::ToJsonString) => ResourceValue::ToJsonString(Box::new(
+                    build_resources_recursively(name, resource_object)?,
+                )),
+                Some(IntrinsicFunction::Ref) => {
                     let ref_name = match resource_object.as_str() {
                         None => {
                             return Err(TransmuteError {
                                 details: format!("Condition must a string {}", name),
+                                path: Vec::new(),
                             })
                         }
                         Some(x) => x,
                     };
 
-                    match ref_name {
-                        "AWS::NoValue" => ResourceValue::Null,
-                        &_ => ResourceValue::Ref(ref_name.to_string()),
-                    }
+                    ResourceValue::Ref(ref_name.to_string())
                 }
 
                 // If it is none of the above, it must be part of the resource properties, continue
                 // parsing as if this was an object with a single property.
-                v => {
+                _ => {
                     let mut hm = HashMap::new();
                     hm.insert(
-                        v.to_owned(),
+                        resource_name.to_owned(),
                         build_resources_recursively(name, resource_object)?,
                     );
                     ResourceValue::Object(hm)
@@ -330,5 +556,6 @@ fn build_resources_recursively(name: &str, obj: &Value) -> Result<ResourceValue,
 
     Err(TransmuteError {
         details: String::from("Nothing found?"),
+        path: Vec::new(),
     })
 }
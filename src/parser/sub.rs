@@ -11,6 +11,8 @@ use nom::IResult;
 pub enum SubValue {
     String(String),
     Variable(String),
+    // Literal is written ${!Literal}, and stands for the text ${Literal} rather than a variable.
+    Literal(String),
 }
 
 pub fn sub_parse_tree(str: &str) -> Result<Vec<SubValue>, TransmuteError> {
@@ -42,9 +44,14 @@ pub fn sub_parse_tree(str: &str) -> Result<Vec<SubValue>, TransmuteError> {
 
 /// inner_resolver will do one of the following:
 /// * take until you see a ${ which is the start of the variable bits.
+/// * take something like ${! ... }, which is escaped
 /// * take something like ${ ... }
 fn inner_resolver(str: &str) -> IResult<&str, SubValue> {
     let ir = alt((
+        map(
+            delimited(tag("${!"), take_until("}"), take(1usize)),
+            |literal: &str| SubValue::Literal(literal.to_string()),
+        ),
         map(
             delimited(tag("${"), take_until("}"), take(1usize)),
             |var: &str| SubValue::Variable(var.to_string()),
@@ -115,4 +122,20 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn escaped_literal() -> Result<(), TransmuteError> {
+        let v = sub_parse_tree("echo ${!HOME} in ${AWS::Region}")?;
+        assert_eq!(
+            v,
+            vec![
+                SubValue::String("echo ".to_string()),
+                SubValue::Literal("HOME".to_string()),
+                SubValue::String(" in ".to_string()),
+                SubValue::Variable("AWS::Region".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
 }
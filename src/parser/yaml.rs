@@ -0,0 +1,97 @@
+use crate::TransmuteError;
+use serde_json::{Map, Value};
+use serde_yaml::value::TaggedValue;
+
+// yaml_to_json reads a YAML template into the same json Value a json template is read into,
+// so the rest of the parser doesn't have to know which one it was given.
+//
+// Short form intrinsics (!Ref, !Sub, ...) are YAML tags, which json has no notion of, so they
+// are rewritten into their long form here.
+pub fn yaml_to_json(template: &str) -> Result<Value, TransmuteError> {
+    let yaml: serde_yaml::Value = match serde_yaml::from_str(template) {
+        Ok(x) => x,
+        Err(e) => {
+            return Err(TransmuteError {
+                details: format!("Template could not be read: {}", e),
+                path: Vec::new(),
+            })
+        }
+    };
+
+    convert(yaml)
+}
+
+fn convert(yaml: serde_yaml::Value) -> Result<Value, TransmuteError> {
+    let value = match yaml {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => match serde_json::to_value(&n) {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(TransmuteError {
+                    details: format!("Number {} could not be read: {}", n, e),
+                    path: Vec::new(),
+                })
+            }
+        },
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(seq) => {
+            Value::Array(seq.into_iter().map(convert).collect::<Result<_, _>>()?)
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut obj = Map::new();
+            for (key, value) in mapping {
+                let key = match key {
+                    serde_yaml::Value::String(s) => s,
+                    serde_yaml::Value::Number(n) => n.to_string(),
+                    serde_yaml::Value::Bool(b) => b.to_string(),
+                    _ => {
+                        return Err(TransmuteError::new(
+                            "Template keys must be strings, numbers or booleans",
+                        ))
+                    }
+                };
+                obj.insert(key, convert(value)?);
+            }
+            Value::Object(obj)
+        }
+        serde_yaml::Value::Tagged(tagged) => convert_tagged(*tagged)?,
+    };
+
+    Ok(value)
+}
+
+// convert_tagged rewrites a short form intrinsic, e.g. `!Ref X`, into its long form.
+fn convert_tagged(tagged: TaggedValue) -> Result<Value, TransmuteError> {
+    let TaggedValue { tag, value } = tagged;
+    let name = tag.to_string();
+    let name = name.trim_start_matches('!');
+    let value = convert(value)?;
+    let (key, value) = match name {
+        "Ref" | "Condition" => (name.to_string(), value),
+        // !GetAtt takes "Resource.Attribute" as well as the long form's [Resource, Attribute].
+        "GetAtt" => match value {
+            Value::String(s) => match s.split_once('.') {
+                Some((resource, attribute)) => (
+                    String::from("Fn::GetAtt"),
+                    Value::Array(vec![
+                        Value::String(resource.to_string()),
+                        Value::String(attribute.to_string()),
+                    ]),
+                ),
+                None => {
+                    return Err(TransmuteError {
+                        details: format!("!GetAtt must be Resource.Attribute, found {}", s),
+                        path: Vec::new(),
+                    })
+                }
+            },
+            x => (String::from("Fn::GetAtt"), x),
+        },
+        _ => (format!("Fn::{}", name), value),
+    };
+
+    let mut obj = Map::new();
+    obj.insert(key, value);
+    Ok(Value::Object(obj))
+}
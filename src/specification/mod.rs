@@ -1,6 +1,12 @@
 use crate::specification::Complexity::{Complex, Simple};
+use crate::TransmuteError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+#[cfg(feature = "bundled-spec")]
+use std::sync::OnceLock;
+
+pub mod ref_values;
+pub mod stateful;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Rule {
@@ -24,7 +30,7 @@ pub struct Rule {
 // Complex means there are deeper structures, and CDK
 // has enough information to actually camel case, so
 // you have to camelcase as well.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Complexity {
     Simple(SimpleType),
     Complex(String),
@@ -89,6 +95,20 @@ impl PropertyRule {
 
         Simple(self.primitive_type.unwrap())
     }
+
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    // is_map is true for properties that map arbitrary keys to values of the item type.
+    pub fn is_map(&self) -> bool {
+        self.property_type.as_deref() == Some("Map")
+    }
+
+    // is_list is true for properties that hold a list of values of the item type.
+    pub fn is_list(&self) -> bool {
+        self.property_type.as_deref() == Some("List")
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -105,16 +125,22 @@ impl Specification {
     // `AWS::Iam::Role.Policy` yet are represented in the specification
     // as "Policy". full_property_name transforms that property name
     // and complexity property into the correct type.
-    pub fn full_property_name(complexity: &Complexity, resource_type: &str) -> Option<String> {
+    pub fn full_property_name(
+        &self,
+        complexity: &Complexity,
+        resource_type: &str,
+    ) -> Option<String> {
         match complexity {
             Complexity::Simple(_) => Option::None,
             Complexity::Complex(x) => {
-                let mut full_rule_name = format!("{}.{}", resource_type, x);
                 // Every type in CloudFormation has the form: {resource}.{resource_type}
-                // e.g. AWS::Iam::Role.Policy . Tag's lookup name in the specification is "Tag".
-                // no one can explain why. Thanks CFN.
-                if x == "Tag" {
-                    full_rule_name = "Tag".to_string();
+                // e.g. AWS::Iam::Role.Policy . Types shared by every resource, like Tag, are
+                // the exception: their lookup name in the specification is just "Tag".
+                let full_rule_name = format!("{}.{}", resource_type, x);
+                if !self.property_types.contains_key(&full_rule_name)
+                    && self.property_types.contains_key(x)
+                {
+                    return Option::Some(x.to_string());
                 }
 
                 Option::Some(full_rule_name)
@@ -123,19 +149,43 @@ impl Specification {
     }
 }
 
+impl Specification {
+    // from_json reads a specification from the contents of a CloudFormation resource
+    // specification file.
+    pub fn from_json(contents: &str) -> Result<Specification, TransmuteError> {
+        match serde_json::from_str::<Specification>(contents) {
+            Ok(x) => Ok(x),
+            Err(e) => Err(TransmuteError::new(
+                format!("Specification could not be read: {}", e).as_str(),
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "bundled-spec")]
 fn read_specification() -> String {
     let str = include_str!("spec.json");
     str.to_string()
 }
 
-// Fully reads the specification from the stored json file
-pub fn spec() -> Specification {
-    let str = read_specification();
-    let res = serde_json::from_str::<Specification>(str.as_str()).unwrap();
+// Fully reads the specification from the stored json file. It is only read the first time it
+// is asked for, as it holds thousands of types, and every translation after shares it.
+#[cfg(feature = "bundled-spec")]
+pub fn spec() -> &'static Specification {
+    static SPECIFICATION: OnceLock<Specification> = OnceLock::new();
+    SPECIFICATION.get_or_init(|| {
+        let str = read_specification();
+        serde_json::from_str::<Specification>(str.as_str()).unwrap()
+    })
+}
 
-    res
+#[cfg(feature = "bundled-spec")]
+#[test]
+fn test_spec_is_read_once() {
+    assert!(std::ptr::eq(spec(), spec()));
 }
 
+#[cfg(feature = "bundled-spec")]
 #[test]
 fn test_pull_json_spec() {
     let specification = spec();
@@ -162,3 +212,47 @@ fn test_pull_json_spec() {
             .unwrap()
     );
 }
+
+#[cfg(all(test, not(feature = "bundled-spec")))]
+mod tests {
+    use crate::ir::{CloudformationProgramIr, TranslationOptions};
+    use crate::specification::Specification;
+    use crate::CloudformationParseTree;
+
+    #[test]
+    fn test_translate_without_bundled_spec() {
+        let specification = Specification::from_json(
+            r#"{
+                "PropertyTypes": {},
+                "ResourceTypes": {
+                    "AWS::SQS::Queue": {
+                        "Properties": {
+                            "QueueName": {"PrimitiveType": "String", "Required": false}
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let template = serde_json::json!({
+            "Conditions": {},
+            "Mappings": {},
+            "Resources": {
+                "Queue": {
+                    "Type": "AWS::SQS::Queue",
+                    "Properties": {"QueueName": "orders"}
+                }
+            }
+        });
+
+        let parse_tree = CloudformationParseTree::build(&template).unwrap();
+        let ir = CloudformationProgramIr::new_from_parse_tree_with_specification(
+            &parse_tree,
+            &TranslationOptions::default(),
+            &specification,
+        )
+        .unwrap();
+        assert_eq!(ir.resources[0].name, "Queue");
+        assert!(Specification::from_json("{}").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+// The specification lists the attributes of each resource type, but not what a Ref to it
+// returns, which differs from type to type: a name for some, an id or an ARN for others.
+const REF_VALUES: &[(&str, RefValue, &str)] = &[
+    ("AWS::CloudFormation::Stack", RefValue::Id, "the stack id"),
+    ("AWS::DynamoDB::Table", RefValue::Name, "the table name"),
+    ("AWS::EC2::Instance", RefValue::Id, "the instance id"),
+    (
+        "AWS::EC2::SecurityGroup",
+        RefValue::Id,
+        "the security group id",
+    ),
+    ("AWS::EC2::Subnet", RefValue::Id, "the subnet id"),
+    ("AWS::EC2::VPC", RefValue::Id, "the VPC id"),
+    ("AWS::ECS::Cluster", RefValue::Name, "the cluster name"),
+    ("AWS::Events::Rule", RefValue::Name, "the rule name"),
+    (
+        "AWS::IAM::InstanceProfile",
+        RefValue::Name,
+        "the instance profile name",
+    ),
+    ("AWS::IAM::ManagedPolicy", RefValue::Arn, "the policy ARN"),
+    ("AWS::IAM::Policy", RefValue::Id, "the policy id"),
+    ("AWS::IAM::Role", RefValue::Name, "the role name"),
+    ("AWS::IAM::User", RefValue::Name, "the user name"),
+    ("AWS::KMS::Key", RefValue::Id, "the key id"),
+    ("AWS::Kinesis::Stream", RefValue::Name, "the stream name"),
+    ("AWS::Lambda::Function", RefValue::Name, "the function name"),
+    ("AWS::Logs::LogGroup", RefValue::Name, "the log group name"),
+    (
+        "AWS::RDS::DBInstance",
+        RefValue::Name,
+        "the DB instance identifier",
+    ),
+    ("AWS::S3::Bucket", RefValue::Name, "the bucket name"),
+    ("AWS::SNS::Topic", RefValue::Arn, "the topic ARN"),
+    ("AWS::SQS::Queue", RefValue::Url, "the queue URL"),
+    (
+        "AWS::SecretsManager::Secret",
+        RefValue::Arn,
+        "the secret ARN",
+    ),
+    (
+        "AWS::StepFunctions::StateMachine",
+        RefValue::Arn,
+        "the state machine ARN",
+    ),
+];
+
+// RefValue is the kind of value a Ref to a resource returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefValue {
+    Name,
+    Id,
+    Arn,
+    Url,
+}
+
+// ref_value returns what a Ref to a resource of the given type returns, along with a
+// description of it, e.g. "the queue URL". Types that aren't known return None.
+pub fn ref_value(resource_type: &str) -> Option<(RefValue, &'static str)> {
+    REF_VALUES
+        .iter()
+        .find(|(x, _, _)| *x == resource_type)
+        .map(|(_, value, description)| (*value, *description))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ref_values() {
+        assert_eq!(
+            ref_value("AWS::SQS::Queue"),
+            Some((RefValue::Url, "the queue URL"))
+        );
+        assert_eq!(
+            ref_value("AWS::IAM::Role"),
+            Some((RefValue::Name, "the role name"))
+        );
+        assert_eq!(ref_value("AWS::Foo::Bar"), None);
+    }
+}
@@ -0,0 +1,53 @@
+// Resources that CloudFormation can snapshot on deletion (DeletionPolicy: Snapshot). Their
+// data outlives any single deployment, which is exactly what makes them stateful.
+const SNAPSHOT_TYPES: &[&str] = &[
+    "AWS::DocDB::DBCluster",
+    "AWS::EC2::Volume",
+    "AWS::ElastiCache::CacheCluster",
+    "AWS::ElastiCache::ReplicationGroup",
+    "AWS::Neptune::DBCluster",
+    "AWS::RDS::DBCluster",
+    "AWS::RDS::DBInstance",
+    "AWS::Redshift::Cluster",
+];
+
+// Other resources that hold data which is lost when the resource is replaced or deleted.
+const STATEFUL_TYPES: &[&str] = &[
+    "AWS::Backup::BackupVault",
+    "AWS::Cognito::UserPool",
+    "AWS::DynamoDB::GlobalTable",
+    "AWS::DynamoDB::Table",
+    "AWS::ECR::Repository",
+    "AWS::EFS::FileSystem",
+    "AWS::Elasticsearch::Domain",
+    "AWS::FSx::FileSystem",
+    "AWS::KMS::Key",
+    "AWS::Kinesis::Stream",
+    "AWS::Logs::LogGroup",
+    "AWS::OpenSearchService::Domain",
+    "AWS::QLDB::Ledger",
+    "AWS::S3::Bucket",
+    "AWS::SQS::Queue",
+    "AWS::SecretsManager::Secret",
+    "AWS::Timestream::Database",
+    "AWS::Timestream::Table",
+];
+
+// is_stateful is true for resource types that store data, so that migrations can plan
+// to retain them rather than letting them be replaced.
+pub fn is_stateful(resource_type: &str) -> bool {
+    SNAPSHOT_TYPES.contains(&resource_type) || STATEFUL_TYPES.contains(&resource_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stateful_types() {
+        assert!(is_stateful("AWS::RDS::DBInstance"));
+        assert!(is_stateful("AWS::S3::Bucket"));
+        assert!(!is_stateful("AWS::IAM::Role"));
+        assert!(!is_stateful("AWS::Lambda::Function"));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::ir::conditions::ConditionIr;
+use crate::ir::reference::Origin;
+use crate::ir::resources::ResourceIr;
+use crate::ir::CloudformationProgramIr;
+use serde_json::{json, Map, Value};
+
+// JsonSynthesizer emits the IR back out as a CloudFormation template. The template is
+// "normalized": AWS::NoValue is removed, and every intrinsic is in its long form.
+//
+// serde_json's Map keeps its keys sorted, so the same template always produces the same
+// output, no matter the order of the HashMaps in the IR. This keeps pretty output diff-stable.
+pub struct JsonSynthesizer {
+    // pretty indents the output. Otherwise, the output is minified.
+    pub pretty: bool,
+}
+
+impl JsonSynthesizer {
+    pub fn new(pretty: bool) -> JsonSynthesizer {
+        JsonSynthesizer { pretty }
+    }
+
+    pub fn output(&self, ir: &CloudformationProgramIr) -> String {
+        let template = to_template(ir);
+        let output = if self.pretty {
+            serde_json::to_string_pretty(&template)
+        } else {
+            serde_json::to_string(&template)
+        };
+
+        // Values built from the IR are always representable in json.
+        output.unwrap()
+    }
+}
+
+fn to_template(ir: &CloudformationProgramIr) -> Value {
+    let mut template = Map::new();
+
+    let mut parameters = Map::new();
+    for param in ir.parameters.iter() {
+        let mut parameter = param.constraints.clone();
+        parameter.insert(
+            "Type".into(),
+            Value::String(param.parameter_type.to_string()),
+        );
+        if let Some(default) = param.default.as_ref() {
+            parameter.insert("Default".into(), to_json_default(default));
+        }
+        if let Some(allowed_values) = &param.allowed_values {
+            parameter.insert("AllowedValues".into(), json!(allowed_values));
+        }
+        if param.no_echo {
+            parameter.insert("NoEcho".into(), json!(true));
+        }
+        if let Some(description) = &param.description {
+            parameter.insert("Description".into(), json!(description));
+        }
+        parameters.insert(param.name.to_string(), Value::Object(parameter));
+    }
+    if !parameters.is_empty() {
+        template.insert("Parameters".into(), Value::Object(parameters));
+    }
+
+    let mut mappings = Map::new();
+    for mapping in ir.mappings.iter() {
+        let mut outer = Map::new();
+        for (outer_key, inner_mapping) in mapping.map.iter() {
+            let mut inner = Map::new();
+            for (inner_key, inner_value) in inner_mapping.iter() {
+                if let Some(value) = to_json_ir(inner_value) {
+                    inner.insert(inner_key.to_string(), value);
+                }
+            }
+            outer.insert(outer_key.to_string(), Value::Object(inner));
+        }
+        mappings.insert(mapping.name.to_string(), Value::Object(outer));
+    }
+    if !mappings.is_empty() {
+        template.insert("Mappings".into(), Value::Object(mappings));
+    }
+
+    let mut conditions = Map::new();
+    for condition in ir.conditions.iter() {
+        conditions.insert(
+            condition.name.to_string(),
+            to_json_condition(&condition.value),
+        );
+    }
+    if !conditions.is_empty() {
+        template.insert("Conditions".into(), Value::Object(conditions));
+    }
+
+    let mut resources = Map::new();
+    for resource in ir.resources.iter() {
+        let mut resource_json = Map::new();
+        resource_json.insert("Type".into(), json!(resource.resource_type));
+        if let Some(condition) = &resource.condition {
+            resource_json.insert("Condition".into(), json!(condition));
+        }
+        // DependsOn is written the way it most often is: a string for a single dependency.
+        match resource.depends_on.as_slice() {
+            [] => {}
+            [dependency] => {
+                resource_json.insert("DependsOn".into(), json!(dependency));
+            }
+            dependencies => {
+                resource_json.insert("DependsOn".into(), json!(dependencies));
+            }
+        }
+        if let Some(metadata) = resource.metadata.as_ref().and_then(to_json_ir) {
+            resource_json.insert("Metadata".into(), metadata);
+        }
+        if let Some(policy) = resource.creation_policy.as_ref().and_then(to_json_ir) {
+            resource_json.insert("CreationPolicy".into(), policy);
+        }
+        if let Some(policy) = &resource.deletion_policy {
+            resource_json.insert("DeletionPolicy".into(), json!(policy));
+        }
+        if let Some(policy) = &resource.update_replace_policy {
+            resource_json.insert("UpdateReplacePolicy".into(), json!(policy));
+        }
+        let mut properties = Map::new();
+        for (name, prop) in resource.properties.iter() {
+            if let Some(value) = to_json_ir(prop) {
+                properties.insert(name.to_string(), value);
+            }
+        }
+        if !properties.is_empty() {
+            resource_json.insert("Properties".into(), Value::Object(properties));
+        }
+        resources.insert(resource.name.to_string(), Value::Object(resource_json));
+    }
+    template.insert("Resources".into(), Value::Object(resources));
+
+    let mut outputs = Map::new();
+    for output in ir.outputs.iter() {
+        let mut output_json = Map::new();
+        if let Some(condition) = &output.condition {
+            output_json.insert("Condition".into(), json!(condition));
+        }
+        if let Some(description) = &output.description {
+            output_json.insert("Description".into(), json!(description));
+        }
+        output_json.insert(
+            "Value".into(),
+            to_json_ir(&output.value).unwrap_or_else(|| json!({"Ref": "AWS::NoValue"})),
+        );
+        if let Some(name) = output.export_name.as_ref().and_then(to_json_ir) {
+            output_json.insert("Export".into(), json!({ "Name": name }));
+        }
+        outputs.insert(output.name.to_string(), Value::Object(output_json));
+    }
+    if !outputs.is_empty() {
+        template.insert("Outputs".into(), Value::Object(outputs));
+    }
+
+    Value::Object(template)
+}
+
+// to_json_default writes a parameter's default back out as declared. The default of a list
+// parameter is read as a list, but is declared as a comma separated string.
+fn to_json_default(default: &ResourceIr) -> Value {
+    match default {
+        ResourceIr::Array(_, items) => {
+            let items: Vec<&str> = items
+                .iter()
+                .filter_map(|x| match x {
+                    ResourceIr::String(x) => Option::Some(x.as_str()),
+                    _ => Option::None,
+                })
+                .collect();
+            json!(items.join(","))
+        }
+        x => to_json_or_no_value(x),
+    }
+}
+
+// to_json_ir returns None for values that are omitted from the template (AWS::NoValue).
+pub fn to_json_ir(resource_ir: &ResourceIr) -> Option<Value> {
+    let value = match resource_ir {
+        ResourceIr::NoValue => return Option::None,
+        ResourceIr::Null => Value::Null,
+        ResourceIr::Bool(b) => json!(b),
+        ResourceIr::Number(n) => json!(n),
+        ResourceIr::Double(n) => json!(n),
+        ResourceIr::String(s) => json!(s),
+        ResourceIr::Array(_, arr) => Value::Array(arr.iter().filter_map(to_json_ir).collect()),
+        ResourceIr::Object(_, o) => {
+            let mut obj = Map::new();
+            for (key, value) in o.iter() {
+                if let Some(value) = to_json_ir(value) {
+                    obj.insert(key.to_string(), value);
+                }
+            }
+            Value::Object(obj)
+        }
+        ResourceIr::If(bool_expr, true_expr, false_expr) => {
+            json!({"Fn::If": [bool_expr, to_json_or_no_value(true_expr), to_json_or_no_value(false_expr)]})
+        }
+        ResourceIr::Join(sep, join_obj) => {
+            let mut join = vec![json!(sep)];
+            join.extend(join_obj.iter().map(to_json_or_no_value));
+            json!({ "Fn::Join": join })
+        }
+        ResourceIr::Ref(x) => json!({"Ref": x.name}),
+        ResourceIr::GetAtt(name, attribute) => json!({"Fn::GetAtt": [name, attribute]}),
+        ResourceIr::Sub(arr) => {
+            // Refs and GetAtts are written inline, everything else is hoisted into the
+            // variable map with a generated name.
+            let mut sub = String::new();
+            let mut variables = Map::new();
+            for part in arr.iter() {
+                match part {
+                    // Literal text that looks like a variable has to be escaped again.
+                    ResourceIr::String(s) => sub.push_str(&s.replace("${", "${!")),
+                    ResourceIr::Ref(x) => sub.push_str(&format!("${{{}}}", x.name)),
+                    ResourceIr::GetAtt(name, attribute) => {
+                        sub.push_str(&format!("${{{}.{}}}", name, attribute))
+                    }
+                    other => {
+                        let variable = format!("Var{}", variables.len());
+                        sub.push_str(&format!("${{{}}}", variable));
+                        variables.insert(variable, to_json_or_no_value(other));
+                    }
+                }
+            }
+
+            if variables.is_empty() {
+                json!({ "Fn::Sub": sub })
+            } else {
+                json!({"Fn::Sub": [sub, variables]})
+            }
+        }
+        ResourceIr::Map(mapper, first, second) => json!({"Fn::FindInMap": [
+            to_json_or_no_value(mapper),
+            to_json_or_no_value(first),
+            to_json_or_no_value(second)
+        ]}),
+        ResourceIr::ToJsonString(x) => json!({ "Fn::ToJsonString": to_json_or_no_value(x) }),
+        ResourceIr::Split(delimiter, x) => {
+            json!({"Fn::Split": [delimiter, to_json_or_no_value(x)]})
+        }
+        ResourceIr::Base64(x) => json!({ "Fn::Base64": to_json_or_no_value(x) }),
+        ResourceIr::ImportValue(x) => json!({ "Fn::ImportValue": to_json_or_no_value(x) }),
+        ResourceIr::Select(index, list) => json!({"Fn::Select": [
+            to_json_or_no_value(index),
+            to_json_or_no_value(list)
+        ]}),
+        ResourceIr::GetAZs(region) => json!({ "Fn::GetAZs": to_json_or_no_value(region) }),
+    };
+
+    Option::Some(value)
+}
+
+// Inside an intrinsic, a value can't just be left out, so it has to stay AWS::NoValue.
+fn to_json_or_no_value(resource_ir: &ResourceIr) -> Value {
+    match to_json_ir(resource_ir) {
+        None => json!({"Ref": "AWS::NoValue"}),
+        Some(x) => x,
+    }
+}
+
+fn to_json_condition(condition: &ConditionIr) -> Value {
+    match condition {
+        ConditionIr::And(x) => {
+            json!({"Fn::And": x.iter().map(to_json_condition).collect::<Vec<Value>>()})
+        }
+        ConditionIr::Equals(a, b) => {
+            json!({"Fn::Equals": [to_json_condition(a), to_json_condition(b)]})
+        }
+        ConditionIr::Not(x) => json!({"Fn::Not": [to_json_condition(x)]}),
+        ConditionIr::Or(x) => {
+            json!({"Fn::Or": x.iter().map(to_json_condition).collect::<Vec<Value>>()})
+        }
+        ConditionIr::Map(name, l1, l2) => json!({"Fn::FindInMap": [
+            to_json_condition(name),
+            to_json_condition(l1),
+            to_json_condition(l2)
+        ]}),
+        ConditionIr::Str(x) => json!(x),
+        ConditionIr::Ref(x) => match x.origin {
+            Origin::Condition => json!({"Condition": x.name}),
+            _ => json!({"Ref": x.name}),
+        },
+    }
+}
@@ -1 +1,2 @@
+pub mod json_synthesizer;
 pub mod typescript_synthesizer;
@@ -1,108 +1,689 @@
 use crate::ir::conditions::ConditionIr;
+use crate::ir::constructor::ConstructorParameter;
+use crate::ir::import_values::has_imports;
 use crate::ir::mappings::MappingInstruction;
 use crate::ir::reference::Origin;
 use crate::ir::reference::PseudoParameter;
-use crate::ir::resources::ResourceIr;
+use crate::ir::reference::Reference;
+use crate::ir::resources::{
+    has_transforms, is_custom_resource, referenced_logical_ids, ResourceInstruction, ResourceIr,
+    Todo,
+};
 use crate::ir::CloudformationProgramIr;
-use crate::parser::lookup_table::MappingInnerValue;
 use crate::specification::Complexity;
-use std::collections::HashMap;
-use voca_rs::case::camel_case;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use voca_rs::case::{camel_case, snake_case};
+
+// L2Props maps a resource's properties onto the props of its L2 construct.
+type L2Props = &'static [(&'static str, &'static str)];
+
+// L2Construct is how a resource type is emitted as an L2 construct.
+struct L2Construct {
+    resource_type: &'static str,
+    construct: &'static str,
+    props: L2Props,
+    // removal_policy is true when the L2 takes a removalPolicy prop. It is always set from the
+    // DeletionPolicy, as some L2s retain the resource by default where CloudFormation deletes it.
+    // Without one, only resources that are deleted, the default of both, can be an L2.
+    removal_policy: bool,
+    // defaults are the props that keep the L2 from deploying differently than the template,
+    // where the L2's own default for a property left unset differs from CloudFormation's.
+    defaults: &'static [(&'static str, &'static str)],
+}
+
+// L2_CONSTRUCTS is the curated table of resource types prefer_l2 emits as L2 constructs, with
+// the L2 prop each property maps to. Properties missing from the table don't translate cleanly,
+// e.g. ones that an L2 takes as a cdk.Duration or a construct rather than a plain value.
+const L2_CONSTRUCTS: &[L2Construct] = &[
+    L2Construct {
+        resource_type: "AWS::S3::Bucket",
+        construct: "Bucket",
+        props: &[("BucketName", "bucketName")],
+        removal_policy: true,
+        defaults: &[],
+    },
+    L2Construct {
+        resource_type: "AWS::SNS::Topic",
+        construct: "Topic",
+        props: &[
+            ("TopicName", "topicName"),
+            ("DisplayName", "displayName"),
+            ("FifoTopic", "fifo"),
+        ],
+        removal_policy: false,
+        defaults: &[],
+    },
+    L2Construct {
+        resource_type: "AWS::SQS::Queue",
+        construct: "Queue",
+        props: &[("QueueName", "queueName"), ("FifoQueue", "fifo")],
+        removal_policy: true,
+        defaults: &[],
+    },
+    L2Construct {
+        resource_type: "AWS::Logs::LogGroup",
+        construct: "LogGroup",
+        props: &[("LogGroupName", "logGroupName")],
+        removal_policy: true,
+        // An L2 log group expires its logs after two years, where RetentionInDays never does.
+        defaults: &[("retention", "logs.RetentionDays.INFINITE")],
+    },
+];
+
+// SubStyle is how a Fn::Sub is emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubStyle {
+    // TemplateLiteral embeds each variable in a template literal, e.g. `${ props.stage }-queue`.
+    TemplateLiteral,
+    // FnSub keeps the Fn::Sub, e.g. cdk.Fn.sub("${Stage}-queue", {Stage: props.stage}).
+    FnSub,
+}
+
+// FormatOptions lays out the object and array literals of property values, to match the style
+// guide, or formatter, of the team taking over the code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    // multiline puts each entry on its own line. Otherwise entries share one line.
+    pub multiline: bool,
+    // trailing_comma ends the last entry with a comma too, as Prettier does.
+    pub trailing_comma: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            multiline: true,
+            trailing_comma: false,
+        }
+    }
+}
+
+impl FormatOptions {
+    fn join(&self, entries: &[String]) -> String {
+        let separator = match self.multiline {
+            true => ",\n",
+            false => ", ",
+        };
+        let mut joined = entries.join(separator);
+        if self.trailing_comma && !entries.is_empty() {
+            joined.push(',');
+        }
+        joined
+    }
+}
 
 pub struct TypescriptSynthesizer {
-    // TODO: Put options in here for different outputs in typescript
+    // stack_name names the generated stack class, and its props interface.
+    pub stack_name: String,
+    // nested generates a cdk.NestedStack, which is constructed within a parent stack.
+    pub nested: bool,
+    // allowed_value_unions types each prop with AllowedValues as a union of those values,
+    // rather than any string or number.
+    pub allowed_value_unions: bool,
+    // sub_style is how each Fn::Sub is emitted. Template literals are the most readable.
+    pub sub_style: SubStyle,
+    // format is how object and array literals are laid out.
+    pub format: FormatOptions,
+    // hoist_repeated_subs emits each Fn::Sub used more than once, e.g. an ARN shared by several
+    // policies, as a single const which every use refers to.
+    pub hoist_repeated_subs: bool,
+    // prefer_l2 emits resources of the types in L2_CONSTRUCTS as L2 constructs, e.g. s3.Bucket
+    // rather than s3.CfnBucket, whenever every property of the resource maps onto the L2.
+    pub prefer_l2: bool,
 }
 
 impl TypescriptSynthesizer {
+    pub fn new(stack_name: &str, nested: bool) -> TypescriptSynthesizer {
+        TypescriptSynthesizer {
+            stack_name: stack_name.to_string(),
+            nested,
+            allowed_value_unions: false,
+            sub_style: SubStyle::TemplateLiteral,
+            format: FormatOptions::default(),
+            hoist_repeated_subs: false,
+            prefer_l2: false,
+        }
+    }
+
     // TODO - remove parse_tree
     pub fn output(ir: CloudformationProgramIr) {
-        for import in ir.imports {
-            println!(
-                "import * as {} from '{}';",
-                import.name,
-                import.path.join("/")
-            )
-        }
+        print!(
+            "{}",
+            TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir)
+        );
+    }
+
+    // synthesize emits a whole typescript file: the imports, followed by the stack class.
+    pub fn synthesize(&self, ir: &CloudformationProgramIr) -> String {
+        let mut output = synthesize_imports(&[ir]);
+        output.push_str(&self.synthesize_stack(ir));
+        output
+    }
 
-        println!("export interface NoctStackProps extends cdk.StackProps {{");
-        for param in ir.constructor.inputs {
-            println!(
+    // synthesize_stack emits only the stack class, and its props interface.
+    //
+    // Each stack is its own class, so resource variables and construct ids are scoped to the
+    // stack's constructor: the same logical id in two stacks can never collide.
+    pub fn synthesize_stack(&self, ir: &CloudformationProgramIr) -> String {
+        let (base, scope_type) = match self.nested {
+            true => ("NestedStack", "cdk.Construct"),
+            false => ("Stack", "cdk.App"),
+        };
+
+        let mut output = String::new();
+        writeln!(
+            output,
+            "export interface {}Props extends cdk.{}Props {{",
+            self.stack_name, base
+        )
+        .unwrap();
+        for param in ir.constructor.inputs.iter() {
+            if let Some(description) = &param.description {
+                for line in description.lines() {
+                    writeln!(output, "\t// {}", line).unwrap();
+                }
+            }
+            writeln!(
+                output,
                 "\treadonly {}: {}",
                 camel_case(&param.name),
-                camel_case(&param.constructor_type)
+                self.prop_type(param)
             )
+            .unwrap();
         }
-        println!("}}");
-        println!("export class NoctStack extends cdk.Stack {{");
-        println!("\tconstructor(scope: cdk.App, id: string, props: NoctStackProps){{");
-        println!("\t\tsuper(scope, id, props);");
+        writeln!(output, "}}").unwrap();
+        writeln!(
+            output,
+            "export class {} extends cdk.{} {{",
+            self.stack_name, base
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "\tconstructor(scope: {}, id: string, props: {}Props){{",
+            scope_type, self.stack_name
+        )
+        .unwrap();
+        writeln!(output, "\t\tsuper(scope, id, props);").unwrap();
         for mapping in ir.mappings.iter() {
             let record_type = match mapping.find_first_type() {
-                MappingInnerValue::String(_) => "Record<string, Record<string, string>>",
-                MappingInnerValue::List(_) => "Record<string, Record<string, Array<string>>>",
+                Some(ResourceIr::Array(_, _)) => "Record<string, Record<string, Array<string>>>",
+                Some(ResourceIr::Number(_)) | Some(ResourceIr::Double(_)) => {
+                    "Record<string, Record<string, number>>"
+                }
+                _ => "Record<string, Record<string, string>>",
             };
-            println!(
+            writeln!(
+                output,
                 "const {}: {} = {}",
                 camel_case(&mapping.name),
                 record_type,
                 synthesize_mapping_instruction(mapping)
-            );
+            )
+            .unwrap();
         }
 
-        for cond in ir.conditions {
+        for cond in ir.conditions.iter() {
             let synthed = synthesize_condition_recursive(&cond.value);
-            println!("const {} = {};", camel_case(&cond.name), synthed)
+            writeln!(output, "const {} = {};", camel_case(&cond.name), synthed).unwrap();
+        }
+        let hoisted = match self.hoist_repeated_subs {
+            true => find_repeated_subs(ir),
+            false => Vec::new(),
+        };
+        for (value, name) in hoisted.iter() {
+            let synthed = to_string_ir_formatted(value, self.sub_style, &self.format).unwrap();
+            writeln!(output, "const {} = {};", name, synthed).unwrap();
         }
+        let nested_stacks: Vec<&str> = ir
+            .resources
+            .iter()
+            .filter(|x| x.resource_type == "AWS::CloudFormation::Stack")
+            .map(|x| x.name.as_str())
+            .collect();
+        let referenced = match self.prefer_l2 {
+            true => referenced_logical_ids(&ir.resources),
+            false => HashSet::new(),
+        };
+        // A resource may depend on one declared after it, so dependencies are only added once
+        // every resource is.
+        let mut dependencies = Vec::new();
         for reference in ir.resources.iter() {
-            let mut split_ref = reference.resource_type.split("::");
-            split_ref.next();
-            let service = split_ref.next().unwrap().to_ascii_lowercase();
-            let rtype = split_ref.next().unwrap();
-            println!(
-                "let {} = new {}.Cfn{}(this, '{}', {{",
+            let custom = is_custom_resource(&reference.resource_type);
+            for comment in reference.comments.iter() {
+                writeln!(output, "// {}", comment).unwrap();
+            }
+            let mut todos = reference.todos.clone();
+            if reference.properties.values().any(has_imports) {
+                todos.push(Todo {
+                    reason: String::from("Fn::ImportValue is only resolved at deploy time, consider passing the value in as a prop"),
+                    link: "https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/intrinsic-function-reference-importvalue.html",
+                });
+            }
+            if reference
+                .properties
+                .values()
+                .chain(reference.metadata.iter())
+                .any(has_transforms)
+            {
+                todos.push(Todo {
+                    reason: String::from("Fn::Transform is only applied by CloudFormation at deploy time, so the resource is emitted as it was before the transform"),
+                    link: "https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/intrinsic-function-reference-transform.html",
+                });
+            }
+            for todo in todos.iter() {
+                writeln!(output, "// TODO: {} ({})", todo.reason, todo.link).unwrap();
+            }
+            let l2 = match self.prefer_l2 && !referenced.contains(&reference.name) {
+                true => l2_construct(reference),
+                false => Option::None,
+            };
+            let (service, construct) = match (custom, l2) {
+                // Custom resources have no generated construct, their properties are passed
+                // through to the provider as they are.
+                (true, _) => (String::from("cdk"), String::from("CfnResource")),
+                (false, Some(l2)) => (
+                    service_name(&reference.resource_type),
+                    l2.construct.to_string(),
+                ),
+                (false, None) => {
+                    let rtype = reference.resource_type.rsplit("::").next().unwrap();
+                    (
+                        service_name(&reference.resource_type),
+                        format!("Cfn{}", rtype),
+                    )
+                }
+            };
+            writeln!(
+                output,
+                "let {} = new {}.{}(this, '{}', {{",
                 camel_case(&reference.name),
                 service,
-                rtype,
+                construct,
                 reference.name
-            );
+            )
+            .unwrap();
+            if custom {
+                writeln!(output, "\ttype: '{}',", reference.resource_type).unwrap();
+                writeln!(output, "\tproperties: {{").unwrap();
+            }
             for (name, prop) in reference.properties.iter() {
-                match to_string_ir(prop) {
+                let mut prop = prop.clone();
+                if !hoisted.is_empty() {
+                    replace_hoisted(&mut prop, &hoisted);
+                }
+                let prop_name = match l2 {
+                    _ if custom => match name.chars().all(char::is_alphanumeric) {
+                        true => name.to_string(),
+                        false => format!("\"{}\"", name),
+                    },
+                    Some(l2) => l2
+                        .props
+                        .iter()
+                        .find(|(x, _)| x == name)
+                        .map(|(_, x)| x.to_string())
+                        .unwrap(),
+                    None => camel_case(name),
+                };
+                match synthesize_value(&prop, self.sub_style, &self.format, &nested_stacks) {
                     None => {}
                     Some(x) => {
-                        println!("\t{}:{},", camel_case(name), x);
+                        let indent = if custom { "\t\t" } else { "\t" };
+                        writeln!(output, "{}{}:{},", indent, prop_name, x).unwrap();
                     }
                 }
             }
-            println!("}});");
+            if let Some(l2) = l2 {
+                if l2.removal_policy {
+                    writeln!(
+                        output,
+                        "\tremovalPolicy:cdk.RemovalPolicy.{},",
+                        l2_removal_policy(reference).unwrap()
+                    )
+                    .unwrap();
+                }
+                for (name, value) in l2.defaults.iter() {
+                    writeln!(output, "\t{}:{},", name, value).unwrap();
+                }
+            }
+            if custom {
+                writeln!(output, "\t}},").unwrap();
+            }
+            writeln!(output, "}});").unwrap();
+            // An L2 is a construct rather than a CfnResource, which adds dependencies on its node.
+            let dependent = match l2 {
+                Some(_) => format!("{}.node", camel_case(&reference.name)),
+                None => camel_case(&reference.name),
+            };
+            for dependency in reference.depends_on.iter() {
+                // A resource that was left out of the translation can't be depended on.
+                if ir.resources.iter().any(|x| &x.name == dependency) {
+                    dependencies.push(format!(
+                        "{}.addDependency({});",
+                        dependent,
+                        camel_case(dependency)
+                    ));
+                }
+            }
+            if let Some(metadata) = reference
+                .metadata
+                .as_ref()
+                .and_then(|x| synthesize_value(x, self.sub_style, &self.format, &nested_stacks))
+            {
+                writeln!(
+                    output,
+                    "{}.cfnOptions.metadata = {};",
+                    camel_case(&reference.name),
+                    metadata
+                )
+                .unwrap();
+            }
+            if let Some(policy) = reference
+                .creation_policy
+                .as_ref()
+                .and_then(|x| synthesize_value(x, self.sub_style, &self.format, &nested_stacks))
+            {
+                writeln!(
+                    output,
+                    "{}.cfnOptions.creationPolicy = {};",
+                    camel_case(&reference.name),
+                    policy
+                )
+                .unwrap();
+            }
+            // An L2's removalPolicy already sets both policies.
+            if let Some(policy) = reference.deletion_policy.as_ref().filter(|_| l2.is_none()) {
+                writeln!(
+                    output,
+                    "{}.cfnOptions.deletionPolicy = cdk.CfnDeletionPolicy.{};",
+                    camel_case(&reference.name),
+                    snake_case(policy).to_uppercase()
+                )
+                .unwrap();
+            }
+            if let Some(policy) = reference
+                .update_replace_policy
+                .as_ref()
+                .filter(|_| l2.is_none())
+            {
+                writeln!(
+                    output,
+                    "{}.cfnOptions.updateReplacePolicy = cdk.CfnDeletionPolicy.{};",
+                    camel_case(&reference.name),
+                    snake_case(policy).to_uppercase()
+                )
+                .unwrap();
+            }
         }
+        for dependency in dependencies.iter() {
+            writeln!(output, "{}", dependency).unwrap();
+        }
+
+        writeln!(output, "\t}}").unwrap();
+        writeln!(output, "}}").unwrap();
+        output
+    }
+
+    fn prop_type(&self, param: &ConstructorParameter) -> String {
+        let allowed_values = match &param.allowed_values {
+            Some(x) if self.allowed_value_unions && !x.is_empty() => x,
+            _ => return param.constructor_type.to_string(),
+        };
 
-        println!("\t}}");
-        println!("}}");
+        let literals: Vec<String> = match param.constructor_type.as_str() {
+            "number" => allowed_values.to_vec(),
+            "string" => allowed_values
+                .iter()
+                .map(|x| format!("\"{}\"", escape_string(x)))
+                .collect(),
+            // AllowedValues of a list parameter constrain each item, not the list itself.
+            _ => return param.constructor_type.to_string(),
+        };
+        literals.join(" | ")
+    }
+}
+
+// l2_construct returns the L2 construct for a resource, and how its properties map onto it,
+// when the resource can be emitted as one. Resources that others refer to stay L1, as an L2
+// doesn't have the same attributes, and so do resources with options only an L1 has.
+fn l2_construct(resource: &ResourceInstruction) -> Option<&'static L2Construct> {
+    let l2 = L2_CONSTRUCTS
+        .iter()
+        .find(|x| x.resource_type == resource.resource_type)?;
+    if resource.metadata.is_some() || resource.creation_policy.is_some() {
+        return Option::None;
+    }
+    match l2_removal_policy(resource) {
+        Some(policy) if l2.removal_policy || policy == "DESTROY" => {}
+        _ => return Option::None,
+    }
+    let maps = resource.properties.iter().all(|(name, value)| {
+        l2.props.iter().any(|(x, _)| x == name)
+            && !matches!(value, ResourceIr::Array(_, _) | ResourceIr::Object(_, _))
+    });
+    match maps {
+        true => Option::Some(l2),
+        false => Option::None,
+    }
+}
+
+// l2_removal_policy is the cdk.RemovalPolicy that deploys a resource with the same
+// DeletionPolicy and UpdateReplacePolicy as the template, both of which default to Delete. A
+// RemovalPolicy sets both policies the same, so there is none when they differ.
+fn l2_removal_policy(resource: &ResourceInstruction) -> Option<&'static str> {
+    let deletion = resource.deletion_policy.as_deref().unwrap_or("Delete");
+    let update_replace = resource
+        .update_replace_policy
+        .as_deref()
+        .unwrap_or("Delete");
+    if deletion != update_replace {
+        return Option::None;
+    }
+    match deletion {
+        "Delete" => Option::Some("DESTROY"),
+        "Retain" => Option::Some("RETAIN"),
+        "Snapshot" => Option::Some("SNAPSHOT"),
+        _ => Option::None,
+    }
+}
+
+// synthesize_nested_stacks emits one file holding a nested stack class per template, named
+// after the nested stack's logical id in its parent. Imports shared between the stacks are
+// only emitted once.
+pub fn synthesize_nested_stacks(stacks: &[(&str, &CloudformationProgramIr)]) -> String {
+    let irs: Vec<&CloudformationProgramIr> = stacks.iter().map(|(_, ir)| *ir).collect();
+    let mut output = synthesize_imports(&irs);
+    for (logical_id, ir) in stacks.iter() {
+        let synthesizer = TypescriptSynthesizer::new(&format!("{}Stack", logical_id), true);
+        output.push_str(&synthesizer.synthesize_stack(ir));
+    }
+    output
+}
+
+// service_name is the module a resource type's constructs are imported as, e.g. "s3" for
+// AWS::S3::Bucket.
+fn service_name(resource_type: &str) -> String {
+    resource_type
+        .split("::")
+        .nth(1)
+        .unwrap()
+        .to_ascii_lowercase()
+}
+
+fn synthesize_imports(irs: &[&CloudformationProgramIr]) -> String {
+    let mut seen = HashSet::new();
+    let mut output = String::new();
+    for import in irs.iter().flat_map(|ir| ir.imports.iter()) {
+        let line = format!(
+            "import * as {} from '{}';",
+            import.name,
+            import.path.join("/")
+        );
+        if seen.insert(line.clone()) {
+            writeln!(output, "{}", line).unwrap();
+        }
+    }
+    output
+}
+
+// find_repeated_subs returns each Fn::Sub that is used more than once across the resources'
+// properties, in the order they are first used, along with the name of the const it is
+// hoisted into.
+fn find_repeated_subs(ir: &CloudformationProgramIr) -> Vec<(ResourceIr, String)> {
+    let mut subs: Vec<(&ResourceIr, usize)> = Vec::new();
+    for resource in ir.resources.iter() {
+        for prop in resource.properties.values() {
+            count_subs(prop, &mut subs);
+        }
+    }
+
+    subs.into_iter()
+        .filter(|(_, count)| *count > 1)
+        .enumerate()
+        .map(|(i, (value, _))| (value.clone(), format!("sharedSub{}", i + 1)))
+        .collect()
+}
+
+// Hoisted consts are declared before any resource, so only a Fn::Sub of literals, parameters
+// and pseudo parameters can be hoisted.
+fn count_subs<'a>(value: &'a ResourceIr, subs: &mut Vec<(&'a ResourceIr, usize)>) {
+    match value {
+        ResourceIr::Sub(parts)
+            if !parts.iter().all(|x| match x {
+                ResourceIr::String(_) => true,
+                ResourceIr::Ref(x) => x.origin != Origin::LogicalId,
+                _ => false,
+            }) => {}
+        ResourceIr::Sub(_) => match subs.iter_mut().find(|(x, _)| *x == value) {
+            Some((_, count)) => *count += 1,
+            None => subs.push((value, 1)),
+        },
+        ResourceIr::Null
+        | ResourceIr::NoValue
+        | ResourceIr::Bool(_)
+        | ResourceIr::Number(_)
+        | ResourceIr::Double(_)
+        | ResourceIr::String(_)
+        | ResourceIr::Ref(_)
+        | ResourceIr::GetAtt(_, _) => {}
+        ResourceIr::Array(_, arr) | ResourceIr::Join(_, arr) => {
+            arr.iter().for_each(|x| count_subs(x, subs))
+        }
+        ResourceIr::Object(_, o) => o.values().for_each(|x| count_subs(x, subs)),
+        ResourceIr::If(_, true_expr, false_expr) => {
+            count_subs(true_expr, subs);
+            count_subs(false_expr, subs);
+        }
+        ResourceIr::Map(mapper, first, second) => {
+            count_subs(mapper, subs);
+            count_subs(first, subs);
+            count_subs(second, subs);
+        }
+        ResourceIr::Select(index, list) => {
+            count_subs(index, subs);
+            count_subs(list, subs);
+        }
+        ResourceIr::GetAZs(x)
+        | ResourceIr::ToJsonString(x)
+        | ResourceIr::ImportValue(x)
+        | ResourceIr::Split(_, x)
+        | ResourceIr::Base64(x) => count_subs(x, subs),
+    }
+}
+
+// replace_hoisted swaps each hoisted Fn::Sub within value for a reference to its const. The
+// const is a local, so it is referenced the way a resource's variable is.
+fn replace_hoisted(value: &mut ResourceIr, hoisted: &[(ResourceIr, String)]) {
+    if let Some((_, name)) = hoisted.iter().find(|(x, _)| x == value) {
+        *value = ResourceIr::Ref(Reference::new(name, Origin::LogicalId));
+        return;
+    }
+
+    match value {
+        ResourceIr::Null
+        | ResourceIr::NoValue
+        | ResourceIr::Bool(_)
+        | ResourceIr::Number(_)
+        | ResourceIr::Double(_)
+        | ResourceIr::String(_)
+        | ResourceIr::Ref(_)
+        | ResourceIr::GetAtt(_, _)
+        | ResourceIr::Sub(_) => {}
+        ResourceIr::Array(_, arr) | ResourceIr::Join(_, arr) => {
+            arr.iter_mut().for_each(|x| replace_hoisted(x, hoisted))
+        }
+        ResourceIr::Object(_, o) => o.values_mut().for_each(|x| replace_hoisted(x, hoisted)),
+        ResourceIr::If(_, true_expr, false_expr) => {
+            replace_hoisted(true_expr, hoisted);
+            replace_hoisted(false_expr, hoisted);
+        }
+        ResourceIr::Map(mapper, first, second) => {
+            replace_hoisted(mapper, hoisted);
+            replace_hoisted(first, hoisted);
+            replace_hoisted(second, hoisted);
+        }
+        ResourceIr::Select(index, list) => {
+            replace_hoisted(index, hoisted);
+            replace_hoisted(list, hoisted);
+        }
+        ResourceIr::GetAZs(x)
+        | ResourceIr::ToJsonString(x)
+        | ResourceIr::ImportValue(x)
+        | ResourceIr::Split(_, x)
+        | ResourceIr::Base64(x) => replace_hoisted(x, hoisted),
     }
 }
 
 pub fn to_string_ir(resource_value: &ResourceIr) -> Option<String> {
+    to_string_ir_with_style(resource_value, SubStyle::TemplateLiteral)
+}
+
+pub fn to_string_ir_with_style(resource_value: &ResourceIr, style: SubStyle) -> Option<String> {
+    to_string_ir_formatted(resource_value, style, &FormatOptions::default())
+}
+
+// to_string_ir_formatted emits a value with object and array literals laid out as asked.
+pub fn to_string_ir_formatted(
+    resource_value: &ResourceIr,
+    style: SubStyle,
+    format: &FormatOptions,
+) -> Option<String> {
+    synthesize_value(resource_value, style, format, &[])
+}
+
+// synthesize_value is to_string_ir_formatted for a value of a stack whose nested stacks, the
+// logical ids of its AWS::CloudFormation::Stack resources, are known.
+fn synthesize_value(
+    resource_value: &ResourceIr,
+    style: SubStyle,
+    format: &FormatOptions,
+    nested_stacks: &[&str],
+) -> Option<String> {
     match resource_value {
-        ResourceIr::Null => Option::None,
+        ResourceIr::NoValue => Option::None,
+        ResourceIr::Null => Option::Some(String::from("null")),
         ResourceIr::Bool(b) => Option::Some(b.to_string()),
         ResourceIr::Number(n) => Option::Some(n.to_string()),
-        ResourceIr::String(s) => Option::Some(format!("\"{}\"", s)),
+        // Debug formatting keeps the fraction of whole numbers, e.g. 1.0 rather than 1.
+        ResourceIr::Double(n) => Option::Some(format!("{:?}", n)),
+        ResourceIr::String(s) => Option::Some(format!("\"{}\"", escape_string(s))),
         ResourceIr::Array(_, arr) => {
             let mut v = Vec::new();
             for a in arr {
-                match to_string_ir(a) {
+                match synthesize_value(a, style, format, nested_stacks) {
                     None => {}
                     Some(s) => v.push(s),
                 }
             }
 
-            Option::Some(format!("[{}]", v.join(",\n")))
+            Option::Some(format!("[{}]", format.join(&v)))
         }
         ResourceIr::Object(complexity, o) => {
             // We are transforming to typescript-json which will not have quotes.
             let mut v = Vec::new();
             for (s, rv) in o {
-                match to_string_ir(rv) {
+                match synthesize_value(rv, style, format, nested_stacks) {
                     None => {}
                     Some(r) => {
                         // If a type is complex, all it's properties will be camel-case in cdk-ts.
@@ -120,7 +701,10 @@ pub fn to_string_ir(resource_value: &ResourceIr) -> Option<String> {
                 }
             }
 
-            Option::Some(format!("{{{}}}", v.join(",\n")))
+            Option::Some(format!("{{{}}}", format.join(&v)))
+        }
+        ResourceIr::Sub(arr) if style == SubStyle::FnSub => {
+            Option::Some(synthesize_fn_sub(arr, nested_stacks))
         }
         ResourceIr::Sub(arr) => {
             // Sub has two ways of being built: Either resolution via a bunch of objects
@@ -129,8 +713,14 @@ pub fn to_string_ir(resource_value: &ResourceIr) -> Option<String> {
             let mut r = Vec::new();
             for i in arr.iter() {
                 match i {
-                    ResourceIr::String(s) => r.push(s.to_string()),
-                    &_ => r.push(format!("${{ {} }}", to_string_ir(i).unwrap())),
+                    // Inline sources (e.g. a lambda's ZipFile) are emitted verbatim inside the
+                    // template literal, so anything typescript would interpret must be escaped.
+                    ResourceIr::String(s) => r.push(escape_template_literal(s)),
+                    &_ => {
+                        if let Some(x) = synthesize_value(i, style, format, nested_stacks) {
+                            r.push(format!("${{ {} }}", x));
+                        }
+                    }
                 };
             }
             Option::Some(format!("`{}`", r.join("")))
@@ -139,25 +729,34 @@ pub fn to_string_ir(resource_value: &ResourceIr) -> Option<String> {
             let a: &ResourceIr = mapper.as_ref();
             let mapper_str = match a {
                 ResourceIr::String(x) => camel_case(x),
-                &_ => to_string_ir(mapper).unwrap(),
+                &_ => synthesize_value(mapper, style, format, nested_stacks).unwrap(),
             };
-            let first_str = to_string_ir(first).unwrap();
-            let second_str = to_string_ir(second).unwrap();
+            let first_str = synthesize_value(first, style, format, nested_stacks).unwrap();
+            let second_str = synthesize_value(second, style, format, nested_stacks).unwrap();
 
             Option::Some(format!("{}[{}][{}]", mapper_str, first_str, second_str))
         }
+        // A nested stack has an Outputs.X attribute for each of its outputs. CfnStack has no attr
+        // for these, as they differ from stack to stack, so they are read through getAtt.
+        ResourceIr::GetAtt(name, attribute)
+            if attribute.starts_with("Outputs.") && nested_stacks.contains(&name.as_str()) =>
+        {
+            Option::Some(format!("{}.getAtt('{}')", camel_case(name), attribute))
+        }
         ResourceIr::GetAtt(name, attribute) => {
             Option::Some(format!("{}.attr{}", camel_case(name), attribute))
         }
         ResourceIr::If(bool_expr, true_expr, false_expr) => {
             let bool_expr = camel_case(bool_expr);
-            let true_expr = match to_string_ir(true_expr) {
-                None => String::from("{}"),
+            // AWS::NoValue in either branch means the property is left out when that branch is
+            // picked, which is what undefined does for a cdk property.
+            let true_expr = match synthesize_value(true_expr, style, format, nested_stacks) {
+                None => String::from("undefined"),
                 Some(x) => x,
             };
 
-            let false_expr = match to_string_ir(false_expr) {
-                None => String::from("{}"),
+            let false_expr = match synthesize_value(false_expr, style, format, nested_stacks) {
+                None => String::from("undefined"),
                 Some(x) => x,
             };
 
@@ -166,7 +765,7 @@ pub fn to_string_ir(resource_value: &ResourceIr) -> Option<String> {
         ResourceIr::Join(sep, join_obj) => {
             let mut strs = Vec::new();
             for rv in join_obj.iter() {
-                match to_string_ir(rv) {
+                match synthesize_value(rv, style, format, nested_stacks) {
                     None => {}
                     Some(x_str) => strs.push(x_str),
                 }
@@ -175,9 +774,110 @@ pub fn to_string_ir(resource_value: &ResourceIr) -> Option<String> {
             Option::Some(format!("{}.join(\"{}\")", strs.join(","), sep))
         }
         ResourceIr::Ref(x) => Option::Some(x.synthesize()),
+        ResourceIr::Select(index, list) => Option::Some(format!(
+            "cdk.Fn.select({}, {})",
+            synthesize_value(index, style, format, nested_stacks).unwrap(),
+            synthesize_value(list, style, format, nested_stacks).unwrap()
+        )),
+        ResourceIr::GetAZs(region) => match region.as_ref() {
+            // An empty region, or the stack's region, are both the default for getAzs.
+            ResourceIr::String(x) if x.is_empty() => Option::Some(String::from("cdk.Fn.getAzs()")),
+            ResourceIr::Ref(x) if x.origin == Origin::PseudoParameter(PseudoParameter::Region) => {
+                Option::Some(String::from("cdk.Fn.getAzs()"))
+            }
+            _ => Option::Some(format!(
+                "cdk.Fn.getAzs({})",
+                synthesize_value(region, style, format, nested_stacks).unwrap()
+            )),
+        },
+        ResourceIr::Base64(x) => Option::Some(format!(
+            "cdk.Fn.base64({})",
+            synthesize_value(x, style, format, nested_stacks).unwrap()
+        )),
+        ResourceIr::Split(delimiter, x) => Option::Some(format!(
+            "cdk.Fn.split(\"{}\", {})",
+            escape_string(delimiter),
+            synthesize_value(x, style, format, nested_stacks).unwrap()
+        )),
+        ResourceIr::ImportValue(x) => Option::Some(format!(
+            "cdk.Fn.importValue({})",
+            synthesize_value(x, style, format, nested_stacks).unwrap()
+        )),
+        ResourceIr::ToJsonString(x) => Option::Some(format!(
+            "this.toJsonString({})",
+            synthesize_value(x, style, format, nested_stacks).unwrap_or_else(|| String::from("{}"))
+        )),
     }
 }
 
+// synthesize_fn_sub keeps a Fn::Sub as a cdk.Fn.sub. Pseudo parameters are left in the string
+// for CloudFormation to resolve. Everything else is a typescript value, so it is passed in
+// through the variable map.
+fn synthesize_fn_sub(arr: &[ResourceIr], nested_stacks: &[&str]) -> String {
+    let mut sub = String::new();
+    let mut variables: Vec<(String, String)> = Vec::new();
+    for part in arr.iter() {
+        let name = match part {
+            // A literal ${ has to be written as ${! to not be read as a variable.
+            ResourceIr::String(s) => {
+                sub.push_str(&s.replace("${", "${!"));
+                continue;
+            }
+            ResourceIr::Ref(x) => match x.origin {
+                Origin::PseudoParameter(_) => {
+                    sub.push_str(&format!("${{{}}}", x.name));
+                    continue;
+                }
+                _ => x.name.to_string(),
+            },
+            ResourceIr::GetAtt(name, attribute) => {
+                format!("{}{}", name, attribute.replace('.', ""))
+            }
+            _ => format!("Var{}", variables.len()),
+        };
+        if !variables.iter().any(|(x, _)| x == &name) {
+            let value = synthesize_value(
+                part,
+                SubStyle::FnSub,
+                &FormatOptions::default(),
+                nested_stacks,
+            )
+            .unwrap();
+            variables.push((name.clone(), value));
+        }
+        sub.push_str(&format!("${{{}}}", name));
+    }
+
+    if variables.is_empty() {
+        return format!("cdk.Fn.sub(\"{}\")", escape_string(&sub));
+    }
+    let variables: Vec<String> = variables
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect();
+    format!(
+        "cdk.Fn.sub(\"{}\", {{{}}})",
+        escape_string(&sub),
+        variables.join(", ")
+    )
+}
+
+// escape_string makes a raw string safe to emit between double quotes.
+fn escape_string(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+// escape_template_literal makes a raw string safe to emit between backticks.
+// Newlines are kept as-is, as template literals can span multiple lines.
+fn escape_template_literal(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('`', "\\`")
+        .replace("${", "\\${")
+}
+
 fn synthesize_condition_recursive(val: &ConditionIr) -> String {
     match val {
         ConditionIr::And(x) => {
@@ -258,13 +958,19 @@ fn synthesize_mapping_instruction(mapping_instruction: &MappingInstruction) -> S
     mapping_parse_tree_ts
 }
 
-fn synthesize_inner_mapping(inner_mapping: &HashMap<String, MappingInnerValue>) -> String {
+fn synthesize_inner_mapping(inner_mapping: &HashMap<String, ResourceIr>) -> String {
+    let format = FormatOptions {
+        multiline: false,
+        trailing_comma: false,
+    };
     let mut inner_mapping_ts_str = String::from("{\n");
     let mut inner_mapping_entries = Vec::new();
     for (inner_mapping_key, inner_mapping_value) in inner_mapping {
         inner_mapping_entries.push(format!(
             "\t\t\"{}\": {}",
-            inner_mapping_key, inner_mapping_value
+            inner_mapping_key,
+            to_string_ir_formatted(inner_mapping_value, SubStyle::TemplateLiteral, &format)
+                .unwrap()
         ));
     }
     inner_mapping_ts_str.push_str(&inner_mapping_entries.join(",\n"));
@@ -0,0 +1,11 @@
+use crate::synthesizer::json_synthesizer::JsonSynthesizer;
+use crate::TransmuteError;
+
+// transmute translates the contents of a template, json or YAML, into normalized CloudFormation
+// json. It only takes and returns strings, and never reads the filesystem or starts threads,
+// so it can be called from a wasm32-unknown-unknown build.
+pub fn transmute(template: &str) -> Result<String, TransmuteError> {
+    let ir = crate::transmute(template)?;
+
+    Ok(JsonSynthesizer::new(false).output(&ir))
+}
@@ -1,6 +1,30 @@
+use noctilucent::ir::changes::{change_plan, topological_order, ChangeStep};
+use noctilucent::ir::conditions::ConditionIr;
+use noctilucent::ir::explanation::Explanation;
+use noctilucent::ir::import_values::ImportResolver;
+use noctilucent::ir::names::{find_physical_names, NameValue, PhysicalName};
+use noctilucent::ir::permissions::required_deploy_actions;
+use noctilucent::ir::reference::{Origin, PseudoParameter, Reference};
+use noctilucent::ir::resources::{
+    resource_dependencies, resources_of_type, translates_resources_collecting, ResourceIr,
+    ResourcesContext,
+};
+use noctilucent::ir::usage::intrinsic_usage;
+use noctilucent::ir::{CloudformationProgramIr, TranslationOptions};
+use noctilucent::parser::comments::extract_resource_comments;
 use noctilucent::parser::condition::{ConditionParseTree, ConditionValue};
+use noctilucent::parser::intrinsics::IntrinsicFunction;
+use noctilucent::parser::merge::merge;
 use noctilucent::parser::resource::{build_resources, ResourceParseTree, ResourceValue};
+use noctilucent::specification::{spec, Complexity, SimpleType, Specification};
+use noctilucent::synthesizer::json_synthesizer::JsonSynthesizer;
+use noctilucent::synthesizer::typescript_synthesizer::{
+    synthesize_nested_stacks, to_string_ir, to_string_ir_formatted, to_string_ir_with_style,
+    FormatOptions, SubStyle, TypescriptSynthesizer,
+};
+use noctilucent::{CloudformationParseTree, ParseOptions};
 use serde_json::Value;
+use std::collections::HashMap;
 
 macro_rules! map(
     { $($key:expr => $value:expr),+ } => {
@@ -32,12 +56,17 @@ fn test_parse_tree_basics() {
     let resource = ResourceParseTree {
         name: "LogicalResource".into(),
         condition: Option::None,
+        metadata: Option::None,
+        creation_policy: Option::None,
+        deletion_policy: Option::None,
+        update_replace_policy: Option::None,
+        depends_on: Vec::new(),
         resource_type: "AWS::IAM::Role".into(),
         properties: map! {
             "RoleName" => ResourceValue::String("bob".into()),
             "AssumeTime" => ResourceValue::Number(20),
             "Bool" => ResourceValue::Bool(true),
-            "NotExistent" => ResourceValue::Null,
+            "NotExistent" => ResourceValue::Ref("AWS::NoValue".to_string()),
             "Array" => ResourceValue::Array(vec![ResourceValue::String("hi".into()), ResourceValue::String("there".into())])
         },
     };
@@ -60,6 +89,11 @@ fn test_parse_tree_sub_str() {
     let resource = ResourceParseTree {
         name: "LogicalResource".into(),
         condition: Option::None,
+        metadata: Option::None,
+        creation_policy: Option::None,
+        deletion_policy: Option::None,
+        update_replace_policy: Option::None,
+        depends_on: Vec::new(),
         resource_type: "AWS::IAM::Role".into(),
         properties: map! {
             "RoleName" => ResourceValue::Sub(vec![ResourceValue::String("bobs-role-${AWS::Region}".into())])
@@ -91,6 +125,11 @@ fn test_parse_tree_sub_list() {
     let resource = ResourceParseTree {
         name: "LogicalResource".into(),
         condition: Option::None,
+        metadata: Option::None,
+        creation_policy: Option::None,
+        deletion_policy: Option::None,
+        update_replace_policy: Option::None,
+        depends_on: Vec::new(),
         resource_type: "AWS::IAM::Role".into(),
         properties: map! {
             "RoleName" => ResourceValue::Sub(vec![
@@ -109,3 +148,4569 @@ fn assert_resource_equal(val: Value, resource: ResourceParseTree) {
     let resources = build_resources(obj).unwrap();
     assert_eq!(resources.resources[0], resource)
 }
+
+#[test]
+fn test_lambda_zip_file_sub() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "TableName": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Handler": {
+                "Type": "AWS::Lambda::Function",
+                "Properties": {
+                    "Code": {
+                        "ZipFile": {
+                            "Fn::Sub": "def handler(event, ctx):\n    return `${TableName}`\n"
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let code = ir.resources[0].properties.get("Code").unwrap();
+    let zip_file = match code {
+        ResourceIr::Object(_, props) => props.get("ZipFile").unwrap(),
+        _ => panic!("Code should be an object"),
+    };
+    assert_eq!(
+        zip_file,
+        &ResourceIr::Sub(vec![
+            ResourceIr::String("def handler(event, ctx):\n    return `".into()),
+            ResourceIr::Ref(Reference::new("TableName", Origin::Parameter)),
+            ResourceIr::String("`\n".into()),
+        ])
+    );
+    assert_eq!(
+        to_string_ir(zip_file).unwrap(),
+        "`def handler(event, ctx):\n    return \\`${ props.tableName }\\`\n`"
+    );
+}
+
+fn translate(template: &Value) -> CloudformationProgramIr {
+    let parse_tree = CloudformationParseTree::build(template).unwrap();
+    CloudformationProgramIr::new_from_parse_tree(&parse_tree).unwrap()
+}
+
+#[test]
+fn test_list_parameter_ref_into_list_property() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "SecurityGroups": {"Type": "List<AWS::EC2::SecurityGroup::Id>"},
+            "ImageId": {"Type": "AWS::EC2::Image::Id"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Instance": {
+                "Type": "AWS::EC2::Instance",
+                "Properties": {
+                    "ImageId": {"Ref": "ImageId"},
+                    "SecurityGroupIds": {"Ref": "SecurityGroups"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let mut inputs: Vec<(String, String)> = ir
+        .constructor
+        .inputs
+        .iter()
+        .map(|input| (input.name.clone(), input.constructor_type.clone()))
+        .collect();
+    inputs.sort();
+    assert_eq!(
+        inputs,
+        vec![
+            ("imageId".to_string(), "string".to_string()),
+            ("securityGroups".to_string(), "string[]".to_string()),
+        ]
+    );
+
+    let security_groups = ir.resources[0].properties.get("SecurityGroupIds").unwrap();
+    assert_eq!(
+        security_groups,
+        &ResourceIr::Ref(Reference::new("SecurityGroups", Origin::Parameter))
+    );
+    assert_eq!(
+        to_string_ir(security_groups).unwrap(),
+        "props.securityGroups"
+    );
+}
+
+#[test]
+fn test_list_resource_ref_into_list_property() {
+    let template = serde_json::json!({
+        "Resources": {
+            "SecurityGroupLookup": {
+                "Type": "Custom::SecurityGroupLookup",
+                "Properties": {"ServiceToken": "arn:aws:lambda:us-east-1:123456789012:function:lookup"}
+            },
+            "SecurityGroup": {
+                "Type": "AWS::EC2::SecurityGroup",
+                "Properties": {"GroupDescription": "web"}
+            },
+            "Instance": {
+                "Type": "AWS::EC2::Instance",
+                "Properties": {
+                    "ImageId": "ami-0abcdef1234567890",
+                    "SecurityGroupIds": {"Ref": "SecurityGroupLookup"}
+                }
+            },
+            "Other": {
+                "Type": "AWS::EC2::Instance",
+                "Properties": {
+                    "ImageId": "ami-0abcdef1234567890",
+                    "SecurityGroupIds": {"Ref": "SecurityGroup"}
+                }
+            },
+            "RouteTable": {
+                "Type": "AWS::EC2::RouteTable",
+                "Properties": {"VpcId": "vpc-0abcdef1234567890"}
+            },
+            "Endpoint": {
+                "Type": "AWS::EC2::VPCEndpoint",
+                "Properties": {
+                    "ServiceName": "com.amazonaws.us-east-1.s3",
+                    "VpcId": "vpc-0abcdef1234567890",
+                    "RouteTableIds": {"Ref": "RouteTable"}
+                }
+            }
+        }
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let options = TranslationOptions {
+        list_ref_types: vec!["Custom::SecurityGroupLookup".into()],
+        ..TranslationOptions::default()
+    };
+    let ir =
+        CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options).unwrap();
+    let properties = |name: &str| {
+        &ir.resources
+            .iter()
+            .find(|r| r.name == name)
+            .unwrap()
+            .properties
+    };
+    // The specification doesn't say what a Ref to a custom resource returns, so a type that
+    // returns a list is listed as one.
+    let lookup = properties("Instance").get("SecurityGroupIds").unwrap();
+    match lookup {
+        ResourceIr::Ref(x) => assert!(x.is_list),
+        _ => panic!("expected a Ref, got {:?}", lookup),
+    }
+    assert_eq!(
+        to_string_ir(lookup).unwrap(),
+        "cdk.Token.asList(securityGroupLookup)"
+    );
+    // A Ref to a security group returns its id, a single value.
+    assert_eq!(
+        properties("Other").get("SecurityGroupIds").unwrap(),
+        &ResourceIr::Ref(Reference::new("SecurityGroup", Origin::LogicalId))
+    );
+    // So does a Ref to a type that isn't listed, or in ref_values.
+    assert_eq!(
+        properties("Endpoint").get("RouteTableIds").unwrap(),
+        &ResourceIr::Ref(Reference::new("RouteTable", Origin::LogicalId))
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""SecurityGroupIds":{"Ref":"SecurityGroupLookup"}"#));
+}
+
+#[test]
+fn test_metadata_transform_is_preserved() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Metadata": {
+                    "Fn::Transform": {
+                        "Name": "AddDefaultTags",
+                        "Parameters": {"Team": "storage"}
+                    }
+                },
+                "Properties": {
+                    "BucketName": "logs"
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let metadata = match ir.resources[0].metadata.as_ref().unwrap() {
+        ResourceIr::Object(_, metadata) => metadata,
+        _ => panic!("Metadata should be an object"),
+    };
+    match metadata.get("Fn::Transform").unwrap() {
+        ResourceIr::Object(_, transform) => assert_eq!(
+            transform.get("Name").unwrap(),
+            &ResourceIr::String("AddDefaultTags".into())
+        ),
+        _ => panic!("Fn::Transform should be an object"),
+    }
+    assert_eq!(ir.warnings.len(), 1);
+    assert!(ir.warnings[0].starts_with("Bucket has a Fn::Transform"));
+    assert!(ir.resources[0].todos.is_empty());
+}
+
+#[test]
+fn test_sub_nested_stack_output() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Network": {
+                "Type": "AWS::CloudFormation::Stack",
+                "Properties": {
+                    "TemplateURL": "https://example.com/network.json"
+                }
+            },
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "Properties": {
+                    "TopicName": {"Fn::Sub": "topic-${Network.Outputs.VpcId}"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let topic = ir.resources.iter().find(|r| r.name == "Topic").unwrap();
+    assert_eq!(
+        topic.properties.get("TopicName").unwrap(),
+        &ResourceIr::Sub(vec![
+            ResourceIr::String("topic-".into()),
+            ResourceIr::GetAtt("Network".into(), "Outputs.VpcId".into()),
+        ])
+    );
+}
+
+#[test]
+fn test_explain_sub_variable() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {}
+            },
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "Properties": {
+                    "TopicName": {"Fn::Sub": "topic-${Queue}"}
+                }
+            }
+        }
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let (_, explanations) = CloudformationProgramIr::explain_from_parse_tree(&parse_tree).unwrap();
+    assert_eq!(
+        explanations,
+        vec![Explanation {
+            resource: "Topic".into(),
+            message:
+                "resolved ${Queue} as LogicalId ref because it is not a parameter or pseudo parameter"
+                    .into(),
+        }]
+    );
+}
+
+#[test]
+fn test_explain_ref_return_value() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Jobs": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {}
+            },
+            "Worker": {
+                "Type": "AWS::Lambda::Function",
+                "Properties": {
+                    "Environment": {
+                        "Variables": {"JOBS_QUEUE": {"Ref": "Jobs"}}
+                    }
+                }
+            }
+        }
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let (_, explanations) = CloudformationProgramIr::explain_from_parse_tree(&parse_tree).unwrap();
+    assert!(explanations.contains(&Explanation {
+        resource: "Worker".into(),
+        message: "Ref Jobs returns the queue URL".into(),
+    }));
+}
+
+#[test]
+fn test_condition_find_in_map() {
+    let template = serde_json::json!({
+        "Mappings": {
+            "RegionMap": {
+                "us-east-1": {"Stage": "prod"}
+            }
+        },
+        "Conditions": {
+            "IsProd": {
+                "Fn::Equals": [
+                    {"Fn::FindInMap": ["RegionMap", {"Ref": "AWS::Region"}, "Stage"]},
+                    "prod"
+                ]
+            }
+        },
+        "Resources": {}
+    });
+
+    let ir = translate(&template);
+    assert_eq!(
+        ir.conditions[0].value,
+        ConditionIr::Equals(
+            Box::new(ConditionIr::Map(
+                Box::new(ConditionIr::Str("RegionMap".into())),
+                Box::new(ConditionIr::Ref(Reference::new(
+                    "AWS::Region",
+                    Origin::PseudoParameter(PseudoParameter::Region)
+                ))),
+                Box::new(ConditionIr::Str("Stage".into())),
+            )),
+            Box::new(ConditionIr::Str("prod".into())),
+        )
+    );
+}
+
+#[test]
+fn test_condition_find_in_map_missing_key() {
+    let template = serde_json::json!({
+        "Mappings": {
+            "RegionMap": {
+                "us-east-1": {"Stage": "prod"}
+            }
+        },
+        "Conditions": {
+            "IsProd": {
+                "Fn::Equals": [
+                    {"Fn::FindInMap": ["RegionMap", {"Ref": "AWS::Region"}, "Tier"]},
+                    "prod"
+                ]
+            }
+        },
+        "Resources": {}
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    assert!(CloudformationProgramIr::new_from_parse_tree(&parse_tree).is_err());
+}
+
+#[test]
+fn test_json_parameters_round_trip() {
+    let parameters = serde_json::json!({
+        "DbPassword": {
+            "Type": "String",
+            "NoEcho": true,
+            "MinLength": 8,
+            "AllowedPattern": "[a-zA-Z0-9]+",
+            "ConstraintDescription": "must be alphanumeric",
+            "Description": "The database admin password"
+        },
+        "Stage": {
+            "Type": "String",
+            "Default": "dev",
+            "AllowedValues": ["dev", "prod"]
+        },
+        "Subnets": {"Type": "CommaDelimitedList", "Default": "a,b"},
+        "Retention": {"Type": "Number", "Default": 7, "MaxValue": 30}
+    });
+    let template = serde_json::json!({
+        "Parameters": parameters,
+        "Resources": {"Queue": {"Type": "AWS::SQS::Queue"}}
+    });
+    let ir = translate(&template);
+
+    let output: Value = serde_json::from_str(&JsonSynthesizer::new(false).output(&ir)).unwrap();
+    assert_eq!(output["Parameters"], parameters);
+}
+
+#[test]
+fn test_json_output_pretty_and_compact() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String", "Default": "dev"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Condition": "IsProd",
+                "Properties": {
+                    "BucketName": {"Fn::Sub": "logs-${Stage}"},
+                    "AccessControl": {"Ref": "AWS::NoValue"}
+                }
+            }
+        }
+    });
+    let ir = translate(&template);
+
+    let compact = JsonSynthesizer::new(false).output(&ir);
+    assert_eq!(
+        compact,
+        concat!(
+            r#"{"Conditions":{"IsProd":{"Fn::Equals":[{"Ref":"Stage"},"prod"]}},"#,
+            r#""Parameters":{"Stage":{"Default":"dev","Type":"String"}},"#,
+            r#""Resources":{"Bucket":{"Condition":"IsProd","Properties":{"BucketName":{"Fn::Sub":"logs-${Stage}"}},"Type":"AWS::S3::Bucket"}}}"#
+        )
+    );
+
+    let pretty = JsonSynthesizer::new(true).output(&ir);
+    assert_eq!(
+        pretty,
+        r#"{
+  "Conditions": {
+    "IsProd": {
+      "Fn::Equals": [
+        {
+          "Ref": "Stage"
+        },
+        "prod"
+      ]
+    }
+  },
+  "Parameters": {
+    "Stage": {
+      "Default": "dev",
+      "Type": "String"
+    }
+  },
+  "Resources": {
+    "Bucket": {
+      "Condition": "IsProd",
+      "Properties": {
+        "BucketName": {
+          "Fn::Sub": "logs-${Stage}"
+        }
+      },
+      "Type": "AWS::S3::Bucket"
+    }
+  }
+}"#
+    );
+
+    // Both forms describe the same template.
+    let compact: Value = serde_json::from_str(&compact).unwrap();
+    let pretty: Value = serde_json::from_str(&pretty).unwrap();
+    assert_eq!(compact, pretty);
+}
+
+#[test]
+fn test_json_depends_on_round_trip() {
+    let template = serde_json::json!({
+        "Resources": {
+            "Bucket": {"Type": "AWS::S3::Bucket"},
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "DependsOn": "Bucket",
+                "Properties": {"DelaySeconds": 5}
+            },
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "DependsOn": ["Bucket", "Queue"]
+            }
+        }
+    });
+    let ir = translate(&template);
+
+    let output: Value = serde_json::from_str(&JsonSynthesizer::new(false).output(&ir)).unwrap();
+    assert_eq!(output["Resources"], template["Resources"]);
+}
+
+#[test]
+fn test_ssm_parameter_to_json_string() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Config": {
+                "Type": "AWS::SSM::Parameter",
+                "Properties": {
+                    "Type": "String",
+                    "Value": {
+                        "Fn::ToJsonString": {
+                            "queue": {"Fn::GetAtt": ["Queue", "Arn"]}
+                        }
+                    }
+                }
+            },
+            "Zones": {
+                "Type": "AWS::SSM::Parameter",
+                "Properties": {
+                    "Type": "StringList",
+                    "Value": ["us-east-1a", "us-east-1b"]
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let config = ir.resources.iter().find(|r| r.name == "Config").unwrap();
+    let value = config.properties.get("Value").unwrap();
+    assert_eq!(
+        to_string_ir(value).unwrap(),
+        "this.toJsonString({queue: queue.attrArn})"
+    );
+
+    let zones = ir.resources.iter().find(|r| r.name == "Zones").unwrap();
+    assert_eq!(
+        to_string_ir(zones.properties.get("Value").unwrap()).unwrap(),
+        "[\"us-east-1a\",\n\"us-east-1b\"].join(\",\")"
+    );
+}
+
+#[test]
+fn test_find_physical_names() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {
+                    "BucketName": "company-logs"
+                }
+            },
+            "Table": {
+                "Type": "AWS::DynamoDB::Table",
+                "Properties": {
+                    "TableName": {"Fn::Sub": "${AWS::StackName}-table"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let mut names = find_physical_names(&ir.resources);
+    names.sort_by(|a, b| a.resource.cmp(&b.resource));
+    assert_eq!(
+        names,
+        vec![
+            PhysicalName {
+                resource: "Bucket".into(),
+                property: "BucketName".into(),
+                value: NameValue::Literal("company-logs".into()),
+            },
+            PhysicalName {
+                resource: "Table".into(),
+                property: "TableName".into(),
+                value: NameValue::Intrinsic,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_sam_globals_merge() {
+    let template = serde_json::json!({
+        "Transform": "AWS::Serverless-2016-10-31",
+        "Globals": {
+            "Function": {
+                "Runtime": "python3.9",
+                "Timeout": 30
+            }
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Handler": {
+                "Type": "AWS::Serverless::Function",
+                "Properties": {
+                    "Handler": "index.handler",
+                    "Timeout": 60
+                }
+            }
+        }
+    });
+
+    let options = ParseOptions {
+        merge_sam_globals: true,
+        ..ParseOptions::default()
+    };
+    let parse_tree = CloudformationParseTree::build_with_options(&template, &options).unwrap();
+    let properties = &parse_tree.resources.resources[0].properties;
+    assert_eq!(
+        properties.get("Runtime").unwrap(),
+        &ResourceValue::String("python3.9".into())
+    );
+    // The resource's own value wins over the global one.
+    assert_eq!(
+        properties.get("Timeout").unwrap(),
+        &ResourceValue::Number(60)
+    );
+    assert_eq!(parse_tree.warnings.len(), 1);
+
+    // Without the option, the Globals section is left alone.
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    assert!(!parse_tree.resources.resources[0]
+        .properties
+        .contains_key("Runtime"));
+    assert_eq!(
+        parse_tree.warnings,
+        vec!["Globals were not merged into resources, so the resources they apply to are missing them"]
+    );
+}
+
+#[test]
+fn test_depends_on_conditional_resource() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Condition": "IsProd",
+                "Properties": {}
+            },
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "DependsOn": "Queue",
+                "Properties": {}
+            },
+            "ProdTopic": {
+                "Type": "AWS::SNS::Topic",
+                "Condition": "IsProd",
+                "DependsOn": ["Queue"],
+                "Properties": {}
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    assert_eq!(
+        ir.warnings,
+        vec!["Topic depends on Queue, which only exists when condition IsProd is true".to_string()]
+    );
+}
+
+#[test]
+fn test_yaml_resource_comment_preserved() {
+    let yaml = "\
+Resources:
+  # Stores the access logs.
+  LogBucket:
+    Type: AWS::S3::Bucket
+";
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "LogBucket": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {}
+            }
+        }
+    });
+
+    let mut ir = translate(&template);
+    ir.attach_comments(&extract_resource_comments(yaml));
+    assert_eq!(
+        ir.resources[0].comments,
+        vec!["Stores the access logs.".to_string()]
+    );
+}
+
+#[test]
+fn test_map_of_complex_property() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Search": {
+                "Type": "AWS::Elasticsearch::Domain",
+                "Properties": {
+                    "LogPublishingOptions": {
+                        "SEARCH_SLOW_LOGS": {
+                            "CloudWatchLogsLogGroupArn": "arn:aws:logs:us-east-1:123456789012:log-group:slow",
+                            "Enabled": true
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let options = ir.resources[0]
+        .properties
+        .get("LogPublishingOptions")
+        .unwrap();
+    let slow_logs = match options {
+        ResourceIr::Object(Complexity::Simple(_), o) => o.get("SEARCH_SLOW_LOGS").unwrap(),
+        _ => panic!("LogPublishingOptions should be a map"),
+    };
+    match slow_logs {
+        ResourceIr::Object(Complexity::Complex(item_type), o) => {
+            assert_eq!(item_type, "LogPublishingOption");
+            assert_eq!(o.get("Enabled").unwrap(), &ResourceIr::Bool(true));
+        }
+        _ => panic!("map values should be a LogPublishingOption"),
+    }
+}
+
+#[test]
+fn test_sub_variable_suggestion() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {}
+            },
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "Properties": {
+                    "TopicName": {"Fn::Sub": "${Bcuket}-events"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    assert!(ir.warnings.contains(
+        &"Topic: Fn::Sub variable ${Bcuket} could not be resolved, did you mean ${Bucket}?"
+            .to_string()
+    ));
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let options = TranslationOptions {
+        check_references: true,
+        ..TranslationOptions::default()
+    };
+    match CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options) {
+        Ok(_) => panic!("Bcuket should not resolve"),
+        Err(err) => assert!(err.to_string().contains("did you mean ${Bucket}?")),
+    }
+}
+
+#[test]
+fn test_sub_variable_map_no_value() {
+    let template = serde_json::json!({
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {
+                    "BucketName": {"Fn::Sub": ["logs-${Suffix}", {"Suffix": {"Ref": "AWS::NoValue"}}]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let bucket_name = ir.resources[0].properties.get("BucketName").unwrap();
+    assert_eq!(to_string_ir(bucket_name).unwrap(), "`logs-`");
+}
+
+#[test]
+fn test_select_get_azs_snapshot() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Vpc": {
+                "Type": "AWS::EC2::VPC",
+                "Properties": {
+                    "CidrBlock": "10.0.0.0/16"
+                }
+            },
+            "Subnet": {
+                "Type": "AWS::EC2::Subnet",
+                "Properties": {
+                    "VpcId": {"Ref": "Vpc"},
+                    "CidrBlock": "10.0.0.0/24",
+                    "AvailabilityZone": {"Fn::Select": ["0", {"Fn::GetAZs": ""}]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let subnet = ir.resources.iter().find(|r| r.name == "Subnet").unwrap();
+    let az = subnet.properties.get("AvailabilityZone").unwrap();
+    assert_eq!(
+        to_string_ir(az).unwrap(),
+        "cdk.Fn.select(0, cdk.Fn.getAzs())"
+    );
+}
+
+#[test]
+fn test_select_with_ref_index() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "ZoneIndex": {"Type": "Number"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Subnet": {
+                "Type": "AWS::EC2::Subnet",
+                "Properties": {
+                    "VpcId": "vpc-0a1b2c3d",
+                    "CidrBlock": "10.0.0.0/24",
+                    "AvailabilityZone": {"Fn::Select": [{"Ref": "ZoneIndex"}, {"Fn::GetAZs": ""}]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let az = ir.resources[0].properties.get("AvailabilityZone").unwrap();
+    match az {
+        ResourceIr::Select(index, list) => {
+            assert_eq!(
+                index.as_ref(),
+                &ResourceIr::Ref(Reference::new("ZoneIndex", Origin::Parameter))
+            );
+            assert!(matches!(list.as_ref(), ResourceIr::GetAZs(_)));
+        }
+        x => panic!("expected a Select, found {:?}", x),
+    }
+    assert_eq!(
+        to_string_ir(az).unwrap(),
+        "cdk.Fn.select(props.zoneIndex, cdk.Fn.getAzs())"
+    );
+}
+
+#[test]
+fn test_empty_string_properties() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": "",
+                    "RedrivePolicy": {"deadLetterTargetArn": "", "maxReceiveCount": 5}
+                }
+            }
+        }
+    });
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+
+    let ir = CloudformationProgramIr::new_from_parse_tree(&parse_tree).unwrap();
+    let properties = &ir.resources[0].properties;
+    assert_eq!(
+        properties.get("QueueName").unwrap(),
+        &ResourceIr::String(String::new())
+    );
+
+    let options = TranslationOptions {
+        drop_empty_strings: true,
+        ..TranslationOptions::default()
+    };
+    let ir =
+        CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options).unwrap();
+    let properties = &ir.resources[0].properties;
+    assert!(!properties.contains_key("QueueName"));
+    match properties.get("RedrivePolicy").unwrap() {
+        ResourceIr::Object(_, o) => {
+            assert!(!o.contains_key("deadLetterTargetArn"));
+            assert_eq!(o.get("maxReceiveCount").unwrap(), &ResourceIr::Number(5));
+        }
+        _ => panic!("RedrivePolicy should be an object"),
+    }
+}
+
+#[test]
+fn test_template_with_bom() {
+    let contents = "\u{feff}\n\n  {\"Conditions\": {}, \"Mappings\": {}, \"Resources\": {\"Queue\": {\"Type\": \"AWS::SQS::Queue\", \"Properties\": {}}}}";
+    let parse_tree =
+        CloudformationParseTree::build_from_str(contents, &ParseOptions::default()).unwrap();
+    assert_eq!(parse_tree.resources.resources[0].name, "Queue");
+
+    let contents = "\u{feff}Resources:\n  Queue:\n    Type: AWS::SQS::Queue\n";
+    let parse_tree =
+        CloudformationParseTree::build_from_yaml_str(contents, &ParseOptions::default()).unwrap();
+    assert_eq!(parse_tree.resources.resources[0].name, "Queue");
+}
+
+#[test]
+fn test_if_between_lists() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {}
+            },
+            "Policy": {
+                "Type": "AWS::IAM::ManagedPolicy",
+                "Properties": {
+                    "PolicyDocument": {"Version": "2012-10-17", "Statement": []},
+                    "Groups": {"Fn::If": ["IsProd", ["admins", "operators", "auditors"], ["developers"]]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let policy = ir.resources.iter().find(|r| r.name == "Policy").unwrap();
+    let groups = policy.properties.get("Groups").unwrap();
+    match groups {
+        ResourceIr::If(condition, true_expr, false_expr) => {
+            assert_eq!(condition, "IsProd");
+            assert!(matches!(true_expr.as_ref(), ResourceIr::Array(_, x) if x.len() == 3));
+            assert!(matches!(false_expr.as_ref(), ResourceIr::Array(_, x) if x.len() == 1));
+        }
+        _ => panic!("Groups should be a whole list chosen by Fn::If"),
+    }
+    assert_eq!(
+        to_string_ir(groups).unwrap(),
+        "(isProd)?[\"admins\",\n\"operators\",\n\"auditors\"]:[\"developers\"]"
+    );
+}
+
+#[test]
+fn test_empty_parameters_section() {
+    for parameters in [Value::Null, serde_json::json!({})] {
+        let template = serde_json::json!({
+            "Parameters": parameters,
+            "Conditions": {},
+            "Mappings": {},
+            "Resources": {
+                "Queue": {
+                    "Type": "AWS::SQS::Queue",
+                    "Properties": {
+                        "QueueName": {"Fn::Sub": "${AWS::StackName}-queue"}
+                    }
+                }
+            }
+        });
+
+        let parse_tree = CloudformationParseTree::build(&template).unwrap();
+        assert!(parse_tree.parameters.params.is_empty());
+        let ir = CloudformationProgramIr::new_from_parse_tree(&parse_tree).unwrap();
+        assert!(ir.constructor.inputs.is_empty());
+    }
+
+    let template = serde_json::json!({"Parameters": "Stage", "Resources": {}});
+    assert!(CloudformationParseTree::build(&template).is_err());
+}
+
+#[test]
+fn test_nested_stacks_reuse_logical_ids() {
+    let bucket_template = |name: &str| {
+        serde_json::json!({
+            "Conditions": {},
+            "Mappings": {},
+            "Resources": {
+                "Bucket": {
+                    "Type": "AWS::S3::Bucket",
+                    "Properties": {"BucketName": name}
+                }
+            }
+        })
+    };
+    let logs = translate(&bucket_template("logs"));
+    let assets = translate(&bucket_template("assets"));
+
+    let output = synthesize_nested_stacks(&[("Logs", &logs), ("Assets", &assets)]);
+    assert_eq!(output.matches("import * as s3").count(), 1);
+    for (stack, name) in [("LogsStack", "logs"), ("AssetsStack", "assets")] {
+        let class = output
+            .split("export interface ")
+            .find(|x| x.starts_with(stack))
+            .unwrap();
+        assert!(class.contains(&format!("export class {} extends cdk.NestedStack", stack)));
+        assert!(class.contains("let bucket = new s3.CfnBucket(this, 'Bucket', {"));
+        assert!(class.contains(&format!("bucketName:\"{}\"", name)));
+    }
+}
+
+#[test]
+fn test_event_pattern_json_with_sub() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "BucketName": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "UploadRule": {
+                "Type": "AWS::Events::Rule",
+                "Properties": {
+                    "EventPattern": {
+                        "source": ["aws.s3"],
+                        "detail-type": ["Object Created"],
+                        "detail": {
+                            "bucket": {
+                                "name": [{"Fn::Sub": "${BucketName}-${AWS::Region}"}]
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let pattern = ir.resources[0].properties.get("EventPattern").unwrap();
+    let detail = match pattern {
+        ResourceIr::Object(Complexity::Simple(_), x) => {
+            assert!(x.contains_key("detail-type"));
+            x.get("detail").unwrap()
+        }
+        _ => panic!("EventPattern should be passed through as Json"),
+    };
+    let name = match detail {
+        ResourceIr::Object(_, x) => match x.get("bucket").unwrap() {
+            ResourceIr::Object(_, x) => x.get("name").unwrap(),
+            _ => panic!("bucket should be an object"),
+        },
+        _ => panic!("detail should be an object"),
+    };
+    assert_eq!(
+        name,
+        &ResourceIr::Array(
+            Complexity::Simple(SimpleType::Json),
+            vec![ResourceIr::Sub(vec![
+                ResourceIr::Ref(Reference::new("BucketName", Origin::Parameter)),
+                ResourceIr::String("-".to_string()),
+                ResourceIr::Ref(Reference::new(
+                    "AWS::Region",
+                    Origin::PseudoParameter(PseudoParameter::Region)
+                )),
+            ])]
+        )
+    );
+    assert_eq!(
+        to_string_ir(name).unwrap(),
+        "[`${ props.bucketName }-${ this.region }`]"
+    );
+    let synthesized = to_string_ir(pattern).unwrap();
+    assert!(synthesized.contains("\"detail-type\": [\"Object Created\"]"));
+}
+
+#[test]
+fn test_cross_condition_references() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]},
+            "IsDev": {"Fn::Equals": [{"Ref": "Stage"}, "dev"]},
+            "IsProdAlarmed": {"Fn::And": [{"Condition": "IsProd"}, {"Fn::Equals": [{"Ref": "AWS::Region"}, "us-east-1"]}]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "ProdTopic": {
+                "Type": "AWS::SNS::Topic",
+                "Condition": "IsProd",
+                "Properties": {"TopicName": "prod"}
+            },
+            "DevQueue": {
+                "Type": "AWS::SQS::Queue",
+                "Condition": "IsDev",
+                "Properties": {
+                    "QueueName": {"Fn::GetAtt": ["ProdTopic", "TopicName"]}
+                }
+            },
+            "ProdQueue": {
+                "Type": "AWS::SQS::Queue",
+                "Condition": "IsProd",
+                "Properties": {
+                    "QueueName": {"Fn::Sub": "${DevQueue}-prod"}
+                }
+            },
+            "ProdSubscription": {
+                "Type": "AWS::SNS::Subscription",
+                "Condition": "IsProdAlarmed",
+                "Properties": {
+                    "TopicArn": {"Ref": "ProdTopic"},
+                    "Protocol": "sqs",
+                    "Endpoint": {"Fn::GetAtt": ["ProdQueue", "Arn"]}
+                }
+            },
+            "Subscription": {
+                "Type": "AWS::SNS::Subscription",
+                "Properties": {
+                    "TopicArn": {"Fn::If": ["IsProd", {"Ref": "ProdTopic"}, {"Ref": "AWS::NoValue"}]},
+                    "Protocol": "sqs",
+                    "Endpoint": {"Fn::If": ["IsDev", {"Ref": "ProdQueue"}, "none"]}
+                }
+            }
+        }
+    });
+
+    let mut warnings = translate(&template).warnings;
+    warnings.sort();
+    assert_eq!(
+        warnings,
+        vec![
+            "DevQueue references ProdTopic, which only exists when condition IsProd is true",
+            "ProdQueue references DevQueue, which only exists when condition IsDev is true",
+            "Subscription only sets required property TopicArn depending on condition IsProd",
+            "Subscription references ProdQueue, which only exists when condition IsProd is true",
+        ]
+    );
+}
+
+#[test]
+fn test_join_with_nested_if() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::Join": ["-", [
+                        "orders",
+                        {"Ref": "Stage"},
+                        {"Fn::If": ["IsProd", "primary", "scratch"]}
+                    ]]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let queue_name = ir.resources[0].properties.get("QueueName").unwrap();
+    let elements = match queue_name {
+        ResourceIr::Join(sep, x) => {
+            assert_eq!(sep, "-");
+            match x.as_slice() {
+                [ResourceIr::Array(_, elements)] => elements,
+                _ => panic!("Join should hold a single list"),
+            }
+        }
+        _ => panic!("QueueName should be a Join"),
+    };
+    assert_eq!(
+        elements[2],
+        ResourceIr::If(
+            "IsProd".to_string(),
+            Box::new(ResourceIr::String("primary".to_string())),
+            Box::new(ResourceIr::String("scratch".to_string()))
+        )
+    );
+    assert_eq!(
+        to_string_ir(queue_name).unwrap(),
+        "[\"orders\",\nprops.stage,\n(isProd)?\"primary\":\"scratch\"].join(\"-\")"
+    );
+}
+
+#[test]
+fn test_allowed_values() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Acl": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Logs": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {"AccessControl": "LogDeliveryWrite"}
+            },
+            "Assets": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {"AccessControl": "PublicReadWrites"}
+            },
+            "Uploads": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {"AccessControl": {"Ref": "Acl"}}
+            }
+        }
+    });
+
+    let options = TranslationOptions {
+        allowed_values: map! {
+            "AWS::S3::Bucket.AccessControl" => vec![
+                "Private".to_string(),
+                "PublicRead".to_string(),
+                "PublicReadWrite".to_string(),
+                "LogDeliveryWrite".to_string()
+            ]
+        },
+        ..TranslationOptions::default()
+    };
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let ir =
+        CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options).unwrap();
+    assert_eq!(
+        ir.warnings,
+        vec!["Assets sets AccessControl to PublicReadWrites, which is not one of: Private, PublicRead, PublicReadWrite, LogDeliveryWrite"]
+    );
+}
+
+#[test]
+fn test_creation_policy_signal_count_from_parameter() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "InstanceCount": {"Type": "Number"},
+            "SignalTimeout": {"Type": "String", "Default": "PT15M"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Fleet": {
+                "Type": "AWS::AutoScaling::AutoScalingGroup",
+                "CreationPolicy": {
+                    "ResourceSignal": {
+                        "Count": {"Ref": "InstanceCount"},
+                        "Timeout": {"Ref": "SignalTimeout"}
+                    }
+                },
+                "Properties": {
+                    "MinSize": "1",
+                    "MaxSize": "4"
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let policy = ir.resources[0].creation_policy.as_ref().unwrap();
+    let signal = match policy {
+        ResourceIr::Object(_, x) => x.get("ResourceSignal").unwrap(),
+        _ => panic!("CreationPolicy should be an object"),
+    };
+    match signal {
+        ResourceIr::Object(_, x) => {
+            assert_eq!(
+                x.get("Count").unwrap(),
+                &ResourceIr::Ref(Reference::new("InstanceCount", Origin::Parameter))
+            );
+            assert_eq!(
+                x.get("Timeout").unwrap(),
+                &ResourceIr::Ref(Reference::new("SignalTimeout", Origin::Parameter))
+            );
+        }
+        _ => panic!("ResourceSignal should be an object"),
+    }
+    let synthesized = to_string_ir(policy).unwrap();
+    assert!(synthesized.starts_with("{resourceSignal: {"));
+    assert!(synthesized.contains("count: props.instanceCount"));
+    assert!(synthesized.contains("timeout: props.signalTimeout"));
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""CreationPolicy":{"ResourceSignal":{"Count":{"Ref":"InstanceCount"},"Timeout":{"Ref":"SignalTimeout"}}}"#));
+}
+
+#[test]
+fn test_allowed_value_union_props() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String", "AllowedValues": ["dev", "prod"]}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Ref": "Stage"}
+                }
+            }
+        }
+    });
+    let ir = translate(&template);
+
+    let mut synthesizer = TypescriptSynthesizer::new("QueueStack", false);
+    assert!(synthesizer.synthesize_stack(&ir).starts_with(
+        "export interface QueueStackProps extends cdk.StackProps {\n\treadonly stage: string\n}\n"
+    ));
+
+    synthesizer.allowed_value_unions = true;
+    assert_eq!(
+        synthesizer.synthesize_stack(&ir),
+        "export interface QueueStackProps extends cdk.StackProps {\n\
+         \treadonly stage: \"dev\" | \"prod\"\n\
+         }\n\
+         export class QueueStack extends cdk.Stack {\n\
+         \tconstructor(scope: cdk.App, id: string, props: QueueStackProps){\n\
+         \t\tsuper(scope, id, props);\n\
+         let queue = new sqs.CfnQueue(this, 'Queue', {\n\
+         \tqueueName:props.stage,\n\
+         });\n\
+         \t}\n\
+         }\n"
+    );
+}
+
+#[test]
+fn test_sub_number_in_variable_map() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::Sub": ["shard-${Count}", {"Count": 5}]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let queue_name = ir.resources[0].properties.get("QueueName").unwrap();
+    assert_eq!(
+        queue_name,
+        &ResourceIr::Sub(vec![
+            ResourceIr::String("shard-".to_string()),
+            ResourceIr::Number(5)
+        ])
+    );
+    assert_eq!(to_string_ir(queue_name).unwrap(), "`shard-${ 5 }`");
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""QueueName":{"Fn::Sub":["shard-${Var0}",{"Var0":5}]}"#));
+}
+
+#[test]
+fn test_intrinsic_usage() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "Properties": {
+                    "TopicName": {"Fn::Sub": "${AWS::StackName}-${Stage}-topic"}
+                }
+            },
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::Sub": ["${Prefix}-queue", {"Prefix": {"Ref": "Stage"}}]},
+                    "DelaySeconds": {"Fn::If": ["IsProd", 0, 5]}
+                }
+            },
+            "Subscription": {
+                "Type": "AWS::SNS::Subscription",
+                "Properties": {
+                    "TopicArn": {"Ref": "Topic"},
+                    "Protocol": "sqs",
+                    "Endpoint": {"Fn::GetAtt": ["Queue", "Arn"]}
+                }
+            }
+        }
+    });
+
+    let usage = intrinsic_usage(&translate(&template));
+    let expected = HashMap::from([
+        (IntrinsicFunction::Equals, 1),
+        (IntrinsicFunction::Ref, 2),
+        (IntrinsicFunction::Sub, 2),
+        (IntrinsicFunction::If, 1),
+        (IntrinsicFunction::GetAtt, 1),
+    ]);
+    assert_eq!(usage, expected);
+}
+
+#[test]
+fn test_required_property_under_if() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Alerts": {
+                "Type": "AWS::SNS::Topic",
+                "Properties": {"TopicName": "alerts"}
+            },
+            "PagerSubscription": {
+                "Type": "AWS::SNS::Subscription",
+                "Properties": {
+                    "TopicArn": {"Ref": "Alerts"},
+                    "Protocol": {"Fn::If": ["IsProd", "https", {"Ref": "AWS::NoValue"}]},
+                    "Endpoint": "https://pager.example.com"
+                }
+            },
+            "EmailSubscription": {
+                "Type": "AWS::SNS::Subscription",
+                "Properties": {
+                    "TopicArn": {"Ref": "Alerts"},
+                    "Protocol": {"Fn::If": ["IsProd", "email", "email-json"]},
+                    "Endpoint": "ops@example.com"
+                }
+            },
+            "QueueSubscription": {
+                "Type": "AWS::SNS::Subscription",
+                "Properties": {
+                    "Protocol": "sqs"
+                }
+            }
+        }
+    });
+
+    let mut warnings = translate(&template).warnings;
+    warnings.sort();
+    assert_eq!(
+        warnings,
+        vec![
+            "PagerSubscription only sets required property Protocol depending on condition IsProd",
+            "QueueSubscription is missing required property TopicArn",
+        ]
+    );
+}
+
+struct KnownExports;
+
+impl ImportResolver for KnownExports {
+    fn resolve(&self, name: &str) -> Option<ResourceIr> {
+        match name {
+            "network-VpcId" => Some(ResourceIr::String("vpc-0a1b2c3d".to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_resolve_import_values() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "WebSecurityGroup": {
+                "Type": "AWS::EC2::SecurityGroup",
+                "Properties": {
+                    "GroupDescription": "web",
+                    "VpcId": {"Fn::ImportValue": "network-VpcId"},
+                    "SecurityGroupIngress": [{
+                        "IpProtocol": "tcp",
+                        "FromPort": 443,
+                        "ToPort": 443,
+                        "SourceSecurityGroupId": {"Fn::ImportValue": {"Fn::Sub": "${AWS::StackName}-LbGroup"}}
+                    }]
+                },
+                "Metadata": {"Vpc": {"Fn::ImportValue": "network-VpcId"}}
+            }
+        },
+        "Outputs": {
+            "VpcId": {
+                "Value": {"Fn::ImportValue": "network-VpcId"},
+                "Export": {"Name": {"Fn::Sub": ["${Vpc}-web", {"Vpc": {"Fn::ImportValue": "network-VpcId"}}]}}
+            }
+        }
+    });
+
+    let mut ir = translate(&template);
+    ir.resolve_imports(&KnownExports);
+    let vpc = ResourceIr::String("vpc-0a1b2c3d".to_string());
+    assert_eq!(ir.outputs[0].value, vpc);
+    assert_eq!(
+        ir.outputs[0].export_name,
+        Some(ResourceIr::Sub(vec![
+            vpc.clone(),
+            ResourceIr::String("-web".into())
+        ]))
+    );
+    match ir.resources[0].metadata.as_ref().unwrap() {
+        ResourceIr::Object(_, x) => assert_eq!(x.get("Vpc").unwrap(), &vpc),
+        _ => panic!("Metadata should be an object"),
+    }
+    let properties = &ir.resources[0].properties;
+    assert_eq!(
+        properties.get("VpcId").unwrap(),
+        &ResourceIr::String("vpc-0a1b2c3d".to_string())
+    );
+    let ingress = match properties.get("SecurityGroupIngress").unwrap() {
+        ResourceIr::Array(_, x) => match &x[0] {
+            ResourceIr::Object(_, x) => x,
+            _ => panic!("ingress rules should be objects"),
+        },
+        _ => panic!("SecurityGroupIngress should be a list"),
+    };
+    let group = ingress.get("SourceSecurityGroupId").unwrap();
+    assert!(
+        matches!(group, ResourceIr::ImportValue(x) if matches!(x.as_ref(), ResourceIr::Sub(_)))
+    );
+    assert_eq!(
+        to_string_ir(group).unwrap(),
+        "cdk.Fn.importValue(`${ this.stackName }-LbGroup`)"
+    );
+}
+
+#[test]
+fn test_auto_scaling_group_zone_lists() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "SubnetIds": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Fleet": {
+                "Type": "AWS::AutoScaling::AutoScalingGroup",
+                "Properties": {
+                    "MinSize": "1",
+                    "MaxSize": "4",
+                    "AvailabilityZones": {"Fn::GetAZs": ""},
+                    "VPCZoneIdentifier": {"Fn::Split": [",", {"Ref": "SubnetIds"}]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let properties = &ir.resources[0].properties;
+    let zones = properties.get("AvailabilityZones").unwrap();
+    assert_eq!(
+        zones,
+        &ResourceIr::GetAZs(Box::new(ResourceIr::String(String::new())))
+    );
+    assert_eq!(to_string_ir(zones).unwrap(), "cdk.Fn.getAzs()");
+
+    let subnets = properties.get("VPCZoneIdentifier").unwrap();
+    assert_eq!(
+        subnets,
+        &ResourceIr::Split(
+            ",".to_string(),
+            Box::new(ResourceIr::Ref(Reference::new(
+                "SubnetIds",
+                Origin::Parameter
+            )))
+        )
+    );
+    assert_eq!(
+        to_string_ir(subnets).unwrap(),
+        "cdk.Fn.split(\",\", props.subnetIds)"
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""VPCZoneIdentifier":{"Fn::Split":[",",{"Ref":"SubnetIds"}]}"#));
+}
+
+#[test]
+fn test_sub_styles() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "Properties": {"TopicName": "alerts"}
+            },
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::Sub": "${Stage}-${Topic.TopicName}-${AWS::Region}"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let queue = ir.resources.iter().find(|x| x.name == "Queue").unwrap();
+    let queue_name = queue.properties.get("QueueName").unwrap();
+    assert_eq!(
+        to_string_ir_with_style(queue_name, SubStyle::TemplateLiteral).unwrap(),
+        "`${ props.stage }-${ topic.attrTopicName }-${ this.region }`"
+    );
+    assert_eq!(
+        to_string_ir_with_style(queue_name, SubStyle::FnSub).unwrap(),
+        "cdk.Fn.sub(\"${Stage}-${TopicTopicName}-${AWS::Region}\", {Stage: props.stage, TopicTopicName: topic.attrTopicName})"
+    );
+
+    let mut synthesizer = TypescriptSynthesizer::new("QueueStack", false);
+    synthesizer.sub_style = SubStyle::FnSub;
+    assert!(synthesizer
+        .synthesize_stack(&ir)
+        .contains("\tqueueName:cdk.Fn.sub(\"${Stage}-"));
+}
+
+#[test]
+fn test_function_role_policy_ordering() {
+    let function = |depends_on: Vec<&str>| {
+        serde_json::json!({
+            "Type": "AWS::Lambda::Function",
+            "DependsOn": depends_on,
+            "Properties": {
+                "Handler": "index.handler",
+                "Runtime": "python3.9",
+                "Role": {"Fn::GetAtt": ["WorkerRole", "Arn"]},
+                "Code": {"ZipFile": "def handler(event, ctx): pass"}
+            }
+        })
+    };
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "WorkerRole": {
+                "Type": "AWS::IAM::Role",
+                "Properties": {
+                    "AssumeRolePolicyDocument": {
+                        "Statement": [{
+                            "Effect": "Allow",
+                            "Principal": {"Service": "lambda.amazonaws.com"},
+                            "Action": "sts:AssumeRole"
+                        }]
+                    }
+                }
+            },
+            "WorkerPolicy": {
+                "Type": "AWS::IAM::Policy",
+                "Properties": {
+                    "PolicyName": "worker",
+                    "Roles": [{"Ref": "WorkerRole"}],
+                    "PolicyDocument": {
+                        "Statement": [{"Effect": "Allow", "Action": "sqs:*", "Resource": "*"}]
+                    }
+                }
+            },
+            "Worker": function(vec![]),
+            "OrderedWorker": function(vec!["WorkerPolicy"])
+        }
+    });
+
+    assert_eq!(
+        translate(&template).warnings,
+        vec!["Worker uses role WorkerRole, which WorkerPolicy is attached to; consider DependsOn: WorkerPolicy so the policy is attached first"]
+    );
+}
+
+#[test]
+fn test_base64_literal() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Instance": {
+                "Type": "AWS::EC2::Instance",
+                "Properties": {
+                    "ImageId": "ami-0abcdef1234567890",
+                    "UserData": {"Fn::Base64": "#!/bin/bash\nyum update -y"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let user_data = ir.resources[0].properties.get("UserData").unwrap();
+    assert_eq!(
+        user_data,
+        &ResourceIr::Base64(Box::new(ResourceIr::String(
+            "#!/bin/bash\nyum update -y".to_string()
+        )))
+    );
+    assert_eq!(
+        to_string_ir(user_data).unwrap(),
+        "cdk.Fn.base64(\"#!/bin/bash\\nyum update -y\")"
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r##""UserData":{"Fn::Base64":"#!/bin/bash\nyum update -y"}"##));
+}
+
+#[test]
+fn test_intrinsic_with_extra_keys() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Ref": "Stage", "Extra": 1}
+                }
+            }
+        }
+    });
+    let err = CloudformationParseTree::build(&template).unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("Ref must be the only key in its object, found Extra, Ref")
+    );
+
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"], "Extra": 1}
+        },
+        "Mappings": {},
+        "Resources": {}
+    });
+    assert!(CloudformationParseTree::build(&template).is_err());
+
+    // Condition is also an ordinary key, e.g. within a policy statement.
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Policy": {
+                "Type": "AWS::IAM::ManagedPolicy",
+                "Properties": {
+                    "PolicyDocument": {
+                        "Statement": [{
+                            "Effect": "Allow",
+                            "Action": "s3:GetObject",
+                            "Resource": "*",
+                            "Condition": {"Bool": {"aws:SecureTransport": "true"}}
+                        }]
+                    }
+                }
+            }
+        }
+    });
+    assert!(CloudformationParseTree::build(&template).is_ok());
+}
+
+#[test]
+fn test_if_with_no_value_in_either_branch() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "DelaySeconds": {"Fn::If": ["IsProd", {"Ref": "AWS::NoValue"}, 30]},
+                    "QueueName": {"Fn::If": ["IsProd", "orders", {"Ref": "AWS::NoValue"}]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let properties = &ir.resources[0].properties;
+
+    let delay = properties.get("DelaySeconds").unwrap();
+    assert_eq!(
+        delay,
+        &ResourceIr::If(
+            "IsProd".to_string(),
+            Box::new(ResourceIr::NoValue),
+            Box::new(ResourceIr::Number(30))
+        )
+    );
+    assert_eq!(to_string_ir(delay).unwrap(), "(isProd)?undefined:30");
+
+    let queue_name = properties.get("QueueName").unwrap();
+    assert_eq!(
+        queue_name,
+        &ResourceIr::If(
+            "IsProd".to_string(),
+            Box::new(ResourceIr::String("orders".to_string())),
+            Box::new(ResourceIr::NoValue)
+        )
+    );
+    assert_eq!(
+        to_string_ir(queue_name).unwrap(),
+        "(isProd)?\"orders\":undefined"
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""DelaySeconds":{"Fn::If":["IsProd",{"Ref":"AWS::NoValue"},30]}"#));
+    assert!(output.contains(r#""QueueName":{"Fn::If":["IsProd","orders",{"Ref":"AWS::NoValue"}]}"#));
+}
+
+#[test]
+fn test_required_deploy_actions() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Uploads": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {"BucketName": "uploads"}
+            },
+            "Jobs": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {"QueueName": "jobs"}
+            }
+        }
+    });
+
+    let actions = required_deploy_actions(&translate(&template));
+    assert!(actions.contains("s3:CreateBucket"));
+    assert!(actions.contains("s3:DeleteBucket"));
+    assert!(actions.contains("sqs:CreateQueue"));
+    assert!(actions.contains("sqs:DeleteQueue"));
+    assert!(!actions.iter().any(|x| x.starts_with("lambda:")));
+}
+
+#[test]
+fn test_yaml_block_scalars() {
+    let template = "\
+Conditions: {}
+Mappings: {}
+Resources:
+  Instance:
+    Type: AWS::EC2::Instance
+    Properties:
+      ImageId: ami-0abcdef1234567890
+      UserData: !Base64 |
+        #!/bin/bash
+        yum update -y
+        yum install -y httpd
+  Key:
+    Type: AWS::KMS::Key
+    Properties:
+      Description: >
+        Encrypts the uploads
+        bucket.
+
+        Rotated yearly.
+      KeyPolicy:
+        Version: '2012-10-17'
+";
+    let parse_tree =
+        CloudformationParseTree::build_from_yaml_str(template, &ParseOptions::default()).unwrap();
+    let ir = CloudformationProgramIr::new_from_parse_tree(&parse_tree).unwrap();
+
+    let instance = ir.resources.iter().find(|x| x.name == "Instance").unwrap();
+    assert_eq!(
+        instance.properties.get("UserData").unwrap(),
+        &ResourceIr::Base64(Box::new(ResourceIr::String(
+            "#!/bin/bash\nyum update -y\nyum install -y httpd\n".to_string()
+        )))
+    );
+
+    let key = ir.resources.iter().find(|x| x.name == "Key").unwrap();
+    assert_eq!(
+        key.properties.get("Description").unwrap(),
+        &ResourceIr::String("Encrypts the uploads bucket.\nRotated yearly.\n".to_string())
+    );
+}
+
+#[test]
+fn test_type_denylist() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "HandlerRole": {
+                "Type": "AWS::IAM::Role",
+                "Properties": {
+                    "MaxSessionDurationSeconds": 3600,
+                    "AssumeRolePolicyDocument": {
+                        "Statement": [{
+                            "Effect": "Allow",
+                            "Principal": {"Service": "lambda.amazonaws.com"},
+                            "Action": "sts:AssumeRole"
+                        }]
+                    }
+                }
+            },
+            "Handler": {
+                "Type": "AWS::Lambda::Function",
+                "Properties": {
+                    "Code": {"ZipFile": "exports.handler = async () => {};"},
+                    "Handler": "index.handler",
+                    "Runtime": "nodejs18.x",
+                    "Role": {"Fn::GetAtt": ["HandlerRole", "Arn"]}
+                }
+            },
+            "Jobs": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {"QueueName": "jobs"}
+            }
+        }
+    });
+
+    // HandlerRole has a property roles don't have, but skipped resources aren't translated, so
+    // it doesn't stop the others from being translated.
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    assert!(CloudformationProgramIr::new_from_parse_tree(&parse_tree).is_err());
+    let options = TranslationOptions {
+        type_denylist: vec!["AWS::IAM::*".to_string()],
+        ..TranslationOptions::default()
+    };
+    let ir =
+        CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options).unwrap();
+
+    let mut names: Vec<&str> = ir.resources.iter().map(|x| x.name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["Handler", "Jobs"]);
+    assert!(ir.warnings.contains(
+        &"HandlerRole was skipped, as resources of type AWS::IAM::Role are not translated"
+            .to_string()
+    ));
+    assert!(ir
+        .warnings
+        .contains(&"Handler references HandlerRole, which was skipped".to_string()));
+
+    let options = TranslationOptions {
+        type_allowlist: vec!["AWS::SQS::Queue".to_string()],
+        ..TranslationOptions::default()
+    };
+    let ir =
+        CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options).unwrap();
+    let names: Vec<&str> = ir.resources.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(names, vec!["Jobs"]);
+}
+
+#[test]
+fn test_nested_stack_output_snapshot() {
+    let template = serde_json::json!({
+        "Resources": {
+            "Network": {
+                "Type": "AWS::CloudFormation::Stack",
+                "Properties": {
+                    "TemplateURL": "https://example.com/network.json"
+                }
+            },
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "Properties": {
+                    "TopicName": {"Fn::GetAtt": ["Network", "Outputs.TopicName"]},
+                    "DisplayName": {"Fn::Sub": "topic-${Network.Outputs.VpcId}"}
+                }
+            },
+            "Lookup": {
+                "Type": "Custom::Lookup",
+                "Properties": {"ServiceToken": "arn:aws:lambda:us-east-1:123456789012:function:lookup"}
+            },
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::GetAtt": ["Lookup", "Outputs.QueueName"]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains(
+        "let network = new cloudformation.CfnStack(this, 'Network', {\n\ttemplateUrl:\"https://example.com/network.json\",\n});"
+    ));
+    assert!(output.contains("\ttopicName:network.getAtt('Outputs.TopicName'),\n"));
+    assert!(output.contains("\tdisplayName:`topic-${ network.getAtt('Outputs.VpcId') }`,\n"));
+    // Only a nested stack's outputs are read through getAtt.
+    assert!(!output.contains("lookup.getAtt"));
+}
+
+#[test]
+fn test_yaml_non_decimal_numbers() {
+    let template = "\
+Conditions: {}
+Mappings: {}
+Resources:
+  Queue:
+    Type: AWS::SQS::Queue
+    Properties:
+      DelaySeconds: 0x1F
+      MessageRetentionPeriod: 0o17
+      MaximumMessageSize: 0262144
+      QueueName: 0755
+";
+    let parse_tree =
+        CloudformationParseTree::build_from_yaml_str(template, &ParseOptions::default()).unwrap();
+    let ir = CloudformationProgramIr::new_from_parse_tree(&parse_tree).unwrap();
+    let properties = &ir.resources[0].properties;
+
+    assert_eq!(
+        properties.get("DelaySeconds").unwrap(),
+        &ResourceIr::Number(31)
+    );
+    assert_eq!(
+        properties.get("MessageRetentionPeriod").unwrap(),
+        &ResourceIr::Number(15)
+    );
+    // Leading zeros are ambiguous, so they depend on the property's type.
+    assert_eq!(
+        properties.get("MaximumMessageSize").unwrap(),
+        &ResourceIr::Number(262144)
+    );
+    assert_eq!(
+        properties.get("QueueName").unwrap(),
+        &ResourceIr::String("0755".to_string())
+    );
+}
+
+#[test]
+fn test_join_with_intrinsic_separator() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::Join": [{"Fn::Sub": "${AWS::Region}"}, ["jobs", "queue"]]}
+                }
+            }
+        }
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let err = CloudformationProgramIr::new_from_parse_tree(&parse_tree)
+        .err()
+        .unwrap();
+    assert!(format!("{:?}", err)
+        .contains("Fn::Join separator must be a literal string, found Fn::Sub in Queue"));
+}
+
+#[test]
+fn test_todo_for_unknown_type() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Widget": {
+                "Type": "AWS::Widgets::Widget",
+                "Properties": {"Size": 3}
+            },
+            "Jobs": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::ImportValue": "shared-QueueName"}
+                }
+            },
+            "Logs": {
+                "Type": "AWS::S3::Bucket",
+                "Metadata": {
+                    "Fn::Transform": {"Name": "AddDefaultTags"}
+                }
+            },
+            "Handler": {
+                "Type": "AWS::Serverless::Function",
+                "Properties": {"Handler": "index.handler"}
+            }
+        }
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let options = TranslationOptions {
+        translate_unknown_types: true,
+        ..TranslationOptions::default()
+    };
+    let ir =
+        CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options).unwrap();
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains(
+        "// TODO: AWS::Widgets::Widget is not in the specification, so its properties were translated as plain json (https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/cfn-resource-specification.html)\nlet widget = new widgets.CfnWidget(this, 'Widget', {"
+    ));
+    assert!(output.contains("// TODO: Fn::ImportValue is only resolved at deploy time"));
+    assert!(output.contains(
+        "// TODO: Fn::Transform is only applied by CloudFormation at deploy time, so the resource is emitted as it was before the transform (https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/intrinsic-function-reference-transform.html)\nlet logs = new s3.CfnBucket(this, 'Logs', {"
+    ));
+    assert!(output.contains(
+        "// TODO: AWS::Serverless::Function is expanded by the AWS::Serverless-2016-10-31 transform, which isn't applied, so its properties were translated as plain json (https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/transform-aws-serverless.html)\nlet handler = new serverless.CfnFunction(this, 'Handler', {"
+    ));
+}
+
+#[test]
+fn test_policy_with_intrinsic_principals() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "TrustedAccount": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Keys": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {"BucketName": "keys"}
+            },
+            "KeysPolicy": {
+                "Type": "AWS::S3::BucketPolicy",
+                "Properties": {
+                    "Bucket": {"Ref": "Keys"},
+                    "PolicyDocument": {
+                        "Statement": [{
+                            "Effect": "Allow",
+                            "Principal": {
+                                "AWS": [
+                                    {"Fn::Sub": "arn:${AWS::Partition}:iam::${AWS::AccountId}:root"},
+                                    {"Fn::Sub": "arn:${AWS::Partition}:iam::${TrustedAccount}:root"}
+                                ]
+                            },
+                            "Action": "s3:GetObject",
+                            "Resource": {"Fn::Sub": "${Keys.Arn}/*"}
+                        }]
+                    }
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let policy = ir
+        .resources
+        .iter()
+        .find(|r| r.name == "KeysPolicy")
+        .unwrap();
+    let document = to_string_ir(policy.properties.get("PolicyDocument").unwrap()).unwrap();
+    assert!(document.contains("Principal: {AWS: [`arn:${ this.partition }:iam::${ this.account }:root`,\n`arn:${ this.partition }:iam::${ props.trustedAccount }:root`]}"));
+    assert!(document.contains("Resource: `${ keys.attrArn }/*`"));
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(
+        r#""Principal":{"AWS":[{"Fn::Sub":"arn:${AWS::Partition}:iam::${AWS::AccountId}:root"},{"Fn::Sub":"arn:${AWS::Partition}:iam::${TrustedAccount}:root"}]}"#
+    ));
+}
+
+#[test]
+fn test_split_round_trip() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Hosts": {"Type": "CommaDelimitedList"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Fleet": {
+                "Type": "AWS::AutoScaling::AutoScalingGroup",
+                "Properties": {
+                    "MinSize": "1",
+                    "MaxSize": "4",
+                    "LoadBalancerNames": {"Fn::Split": [",", {"Ref": "Hosts"}]},
+                    "VPCZoneIdentifier": {"Fn::Split": [",", {"Fn::Sub": "${AWS::Region}a,${AWS::Region}b"}]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let names = ir.resources[0].properties.get("LoadBalancerNames").unwrap();
+    assert_eq!(
+        names,
+        &ResourceIr::Split(
+            ",".to_string(),
+            Box::new(ResourceIr::Ref(Reference::new("Hosts", Origin::Parameter)))
+        )
+    );
+    let zones = ir.resources[0].properties.get("VPCZoneIdentifier").unwrap();
+    assert_eq!(
+        to_string_ir(zones).unwrap(),
+        "cdk.Fn.split(\",\", `${ this.region }a,${ this.region }b`)"
+    );
+
+    // The normalized json reads back into the same IR.
+    let output = JsonSynthesizer::new(false).output(&ir);
+    let mut output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    output["Conditions"] = serde_json::json!({});
+    output["Mappings"] = serde_json::json!({});
+    let round_trip = translate(&output);
+    assert_eq!(
+        round_trip.resources[0].properties,
+        ir.resources[0].properties
+    );
+
+    let template = serde_json::json!({
+        "Parameters": {
+            "Hosts": {"Type": "CommaDelimitedList"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Fleet": {
+                "Type": "AWS::AutoScaling::AutoScalingGroup",
+                "Properties": {
+                    "MinSize": "1",
+                    "MaxSize": "4",
+                    "LoadBalancerNames": {"Fn::Split": [{"Ref": "AWS::Region"}, {"Ref": "Hosts"}]}
+                }
+            }
+        }
+    });
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    assert!(CloudformationProgramIr::new_from_parse_tree(&parse_tree).is_err());
+}
+
+#[test]
+fn test_hoist_repeated_subs() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "ReadPolicy": {
+                "Type": "AWS::IAM::ManagedPolicy",
+                "Properties": {
+                    "PolicyDocument": {
+                        "Statement": [{
+                            "Effect": "Allow",
+                            "Action": "s3:GetObject",
+                            "Resource": {"Fn::Sub": "arn:${AWS::Partition}:s3:::assets-${AWS::AccountId}/*"}
+                        }]
+                    }
+                }
+            },
+            "WritePolicy": {
+                "Type": "AWS::IAM::ManagedPolicy",
+                "Properties": {
+                    "PolicyDocument": {
+                        "Statement": [{
+                            "Effect": "Allow",
+                            "Action": "s3:PutObject",
+                            "Resource": {"Fn::Sub": "arn:${AWS::Partition}:s3:::assets-${AWS::AccountId}/*"}
+                        }]
+                    }
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let mut synthesizer = TypescriptSynthesizer::new("NoctStack", false);
+    let output = synthesizer.synthesize(&ir);
+    assert!(!output.contains("sharedSub"));
+
+    synthesizer.hoist_repeated_subs = true;
+    let output = synthesizer.synthesize(&ir);
+    assert_eq!(
+        output
+            .matches("`arn:${ this.partition }:s3:::assets-${ this.account }/*`")
+            .count(),
+        1
+    );
+    assert!(output
+        .contains("const sharedSub1 = `arn:${ this.partition }:s3:::assets-${ this.account }/*`;"));
+    assert_eq!(output.matches("Resource: sharedSub1").count(), 2);
+}
+
+#[test]
+fn test_base64_user_data_sub() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Instance": {
+                "Type": "AWS::EC2::Instance",
+                "Properties": {
+                    "ImageId": "ami-0abcdef1234567890",
+                    "UserData": {"Fn::Base64": {"Fn::Sub": "#!/bin/bash\necho ${Stage} > /etc/stage"}}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let user_data = ir.resources[0].properties.get("UserData").unwrap();
+    assert_eq!(
+        user_data,
+        &ResourceIr::Base64(Box::new(ResourceIr::Sub(vec![
+            ResourceIr::String("#!/bin/bash\necho ".to_string()),
+            ResourceIr::Ref(Reference::new("Stage", Origin::Parameter)),
+            ResourceIr::String(" > /etc/stage".to_string()),
+        ])))
+    );
+    assert_eq!(
+        to_string_ir(user_data).unwrap(),
+        "cdk.Fn.base64(`#!/bin/bash\necho ${ props.stage } > /etc/stage`)"
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(
+        r##""UserData":{"Fn::Base64":{"Fn::Sub":"#!/bin/bash\necho ${Stage} > /etc/stage"}}"##
+    ));
+}
+
+#[test]
+fn test_sub_unknown_pseudo_parameter() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::Sub": "jobs-${AWS::Foo}"}
+                }
+            }
+        }
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    match CloudformationProgramIr::new_from_parse_tree(&parse_tree) {
+        Ok(_) => panic!("AWS::Foo is not a pseudo parameter"),
+        Err(err) => assert_eq!(
+            err.to_string(),
+            "Resources.Queue.Properties.QueueName.Fn::Sub: Fn::Sub variable ${AWS::Foo} could not be resolved, AWS::Foo is not a pseudo parameter in Queue"
+        ),
+    }
+}
+
+#[test]
+fn test_get_azs_regions() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Fleet": {
+                "Type": "AWS::AutoScaling::AutoScalingGroup",
+                "Properties": {
+                    "MinSize": "1",
+                    "MaxSize": "4",
+                    "AvailabilityZones": {"Fn::GetAZs": {"Ref": "AWS::Region"}}
+                }
+            },
+            "Replica": {
+                "Type": "AWS::AutoScaling::AutoScalingGroup",
+                "Properties": {
+                    "MinSize": "1",
+                    "MaxSize": "4",
+                    "AvailabilityZones": {"Fn::GetAZs": "us-west-2"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let zones = |name: &str| {
+        ir.resources
+            .iter()
+            .find(|r| r.name == name)
+            .unwrap()
+            .properties
+            .get("AvailabilityZones")
+            .unwrap()
+            .clone()
+    };
+
+    let fleet = zones("Fleet");
+    assert_eq!(
+        fleet,
+        ResourceIr::GetAZs(Box::new(ResourceIr::Ref(Reference::new(
+            "AWS::Region",
+            Origin::PseudoParameter(PseudoParameter::Region)
+        ))))
+    );
+    assert_eq!(to_string_ir(&fleet).unwrap(), "cdk.Fn.getAzs()");
+
+    let replica = zones("Replica");
+    assert_eq!(
+        replica,
+        ResourceIr::GetAZs(Box::new(ResourceIr::String("us-west-2".to_string())))
+    );
+    assert_eq!(
+        to_string_ir(&replica).unwrap(),
+        "cdk.Fn.getAzs(\"us-west-2\")"
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#"{"Fn::GetAZs":{"Ref":"AWS::Region"}}"#));
+    assert!(output.contains(r#"{"Fn::GetAZs":"us-west-2"}"#));
+}
+
+#[test]
+fn test_parameter_and_output_descriptions() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String", "Description": "The stage to deploy to"},
+            "Retries": {"Type": "Number"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Jobs": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {"QueueName": "jobs"}
+            }
+        },
+        "Outputs": {
+            "JobsUrl": {
+                "Description": "Where workers read jobs from",
+                "Value": {"Ref": "Jobs"}
+            }
+        }
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let params = &parse_tree.parameters.params;
+    assert_eq!(
+        params.get("Stage").unwrap().description.as_deref(),
+        Some("The stage to deploy to")
+    );
+    assert_eq!(params.get("Retries").unwrap().description, None);
+    assert_eq!(
+        parse_tree.outputs.outputs[0].description.as_deref(),
+        Some("Where workers read jobs from")
+    );
+
+    let ir = CloudformationProgramIr::new_from_parse_tree(&parse_tree).unwrap();
+    let stage = ir
+        .constructor
+        .inputs
+        .iter()
+        .find(|x| x.logical_name == "Stage")
+        .unwrap();
+    assert_eq!(stage.description.as_deref(), Some("The stage to deploy to"));
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("\t// The stage to deploy to\n\treadonly stage: string"));
+}
+
+#[test]
+fn test_import_value_names() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Env": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "AppSecurityGroup": {
+                "Type": "AWS::EC2::SecurityGroup",
+                "Properties": {
+                    "GroupDescription": {"Fn::ImportValue": "shared-GroupDescription"},
+                    "VpcId": {"Fn::ImportValue": {"Fn::Sub": "${Env}-VpcId"}}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let properties = &ir.resources[0].properties;
+    let description = properties.get("GroupDescription").unwrap();
+    assert_eq!(
+        description,
+        &ResourceIr::ImportValue(Box::new(ResourceIr::String(
+            "shared-GroupDescription".to_string()
+        )))
+    );
+    assert_eq!(
+        to_string_ir(description).unwrap(),
+        "cdk.Fn.importValue(\"shared-GroupDescription\")"
+    );
+
+    // The export belongs to another stack, so the Sub's variables are only this stack's.
+    let vpc = properties.get("VpcId").unwrap();
+    assert_eq!(
+        vpc,
+        &ResourceIr::ImportValue(Box::new(ResourceIr::Sub(vec![
+            ResourceIr::Ref(Reference::new("Env", Origin::Parameter)),
+            ResourceIr::String("-VpcId".to_string()),
+        ])))
+    );
+    assert_eq!(
+        to_string_ir(vpc).unwrap(),
+        "cdk.Fn.importValue(`${ props.env }-VpcId`)"
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""VpcId":{"Fn::ImportValue":{"Fn::Sub":"${Env}-VpcId"}}"#));
+}
+
+#[test]
+fn test_tags_across_resource_types() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Uploads": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {
+                    "Tags": [{"Key": "team", "Value": "storage"}]
+                }
+            },
+            "Jobs": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "Tags": [{"Key": "team", "Value": {"Ref": "AWS::StackName"}}]
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    for (name, value) in [("Uploads", "\"storage\""), ("Jobs", "this.stackName")] {
+        let resource = ir.resources.iter().find(|r| r.name == name).unwrap();
+        let tags = resource.properties.get("Tags").unwrap();
+        match tags {
+            ResourceIr::Array(Complexity::Complex(x), arr) => {
+                assert_eq!(x, "Tag");
+                assert!(
+                    matches!(&arr[0], ResourceIr::Object(Complexity::Complex(x), _) if x == "Tag")
+                );
+            }
+            x => panic!("expected a list of tags, found {:?}", x),
+        }
+        let tags = to_string_ir(tags).unwrap();
+        assert!(tags.contains("key: \"team\""));
+        assert!(tags.contains(&format!("value: {}", value)));
+    }
+}
+
+#[test]
+fn test_complex_property_without_type() {
+    // The specification names the property's type, but leaves the type itself out.
+    let specification = Specification::from_json(
+        r#"{
+            "PropertyTypes": {},
+            "ResourceTypes": {
+                "AWS::Widgets::Widget": {
+                    "Properties": {
+                        "Settings": {"Required": false, "Type": "Settings"}
+                    }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Widget": {
+                "Type": "AWS::Widgets::Widget",
+                "Properties": {
+                    "Settings": {"Size": 3, "Owner": {"Ref": "AWS::AccountId"}}
+                }
+            }
+        }
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let ir = CloudformationProgramIr::new_from_parse_tree_with_specification(
+        &parse_tree,
+        &TranslationOptions::default(),
+        &specification,
+    )
+    .unwrap();
+    let settings = ir.resources[0].properties.get("Settings").unwrap();
+    let settings = to_string_ir(settings).unwrap();
+    assert!(settings.contains("size: 3"));
+    assert!(settings.contains("owner: this.account"));
+}
+
+#[test]
+fn test_nested_and_of_equals() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProdInOregon": {"Fn::And": [
+                {"Fn::Equals": [{"Ref": "Stage"}, "prod"]},
+                {"Fn::Equals": [{"Ref": "AWS::Region"}, "us-west-2"]}
+            ]}
+        },
+        "Mappings": {},
+        "Resources": {}
+    });
+
+    let ir = translate(&template);
+    assert_eq!(ir.conditions.len(), 1);
+    assert_eq!(ir.conditions[0].name, "IsProdInOregon");
+    assert_eq!(
+        ir.conditions[0].value,
+        ConditionIr::And(vec![
+            ConditionIr::Equals(
+                Box::new(ConditionIr::Ref(Reference::new("Stage", Origin::Parameter))),
+                Box::new(ConditionIr::Str("prod".to_string()))
+            ),
+            ConditionIr::Equals(
+                Box::new(ConditionIr::Ref(Reference::new(
+                    "AWS::Region",
+                    Origin::PseudoParameter(PseudoParameter::Region)
+                ))),
+                Box::new(ConditionIr::Str("us-west-2".to_string()))
+            ),
+        ])
+    );
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains(
+        "const isProdInOregon = (props.stage == \"prod\" && this.region == \"us-west-2\");"
+    ));
+}
+
+#[test]
+fn test_sub_ref_and_get_att_variables() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {"BucketName": "assets"}
+            },
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "Properties": {
+                    "DisplayName": {"Fn::Sub": "${Bucket} at ${Bucket.Arn}"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let topic = ir.resources.iter().find(|r| r.name == "Topic").unwrap();
+    let display_name = topic.properties.get("DisplayName").unwrap();
+    assert_eq!(
+        display_name,
+        &ResourceIr::Sub(vec![
+            ResourceIr::Ref(Reference::new("Bucket", Origin::LogicalId)),
+            ResourceIr::String(" at ".to_string()),
+            ResourceIr::GetAtt("Bucket".to_string(), "Arn".to_string()),
+        ])
+    );
+    assert_eq!(
+        to_string_ir(display_name).unwrap(),
+        "`${ bucket } at ${ bucket.attrArn }`"
+    );
+}
+
+#[test]
+fn test_console_exported_template() {
+    let template = serde_json::json!({
+        "AWSTemplateFormatVersion": "2010-09-09",
+        "Metadata": {
+            "AWS::CloudFormation::Designer": {
+                "3f1c0b7e-1c3a-4f5e-9f0a-2d3e4f5a6b7c": {
+                    "size": {"width": 60, "height": 60},
+                    "position": {"x": 90, "y": 120},
+                    "z": 1,
+                    "embeds": []
+                }
+            }
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Uploads": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {"BucketName": "uploads"},
+                "Metadata": {
+                    "AWS::CloudFormation::Designer": {
+                        "id": "3f1c0b7e-1c3a-4f5e-9f0a-2d3e4f5a6b7c"
+                    }
+                }
+            },
+            "Jobs": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {"QueueName": "jobs"},
+                "Metadata": {
+                    "AWS::CloudFormation::Designer": {
+                        "id": "8a9b0c1d-2e3f-4a5b-8c7d-6e5f4a3b2c1d"
+                    },
+                    "Owner": "platform"
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let uploads = ir.resources.iter().find(|r| r.name == "Uploads").unwrap();
+    assert!(uploads.metadata.is_none());
+    let jobs = ir.resources.iter().find(|r| r.name == "Jobs").unwrap();
+    match jobs.metadata.as_ref().unwrap() {
+        ResourceIr::Object(_, x) => {
+            assert!(!x.contains_key("AWS::CloudFormation::Designer"));
+            assert!(x.contains_key("Owner"));
+        }
+        x => panic!("expected the metadata to be an object, found {:?}", x),
+    }
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(!output.contains("Designer"));
+
+    let options = ParseOptions {
+        keep_designer_metadata: true,
+        ..ParseOptions::default()
+    };
+    let parse_tree = CloudformationParseTree::build_with_options(&template, &options).unwrap();
+    let ir = CloudformationProgramIr::new_from_parse_tree(&parse_tree).unwrap();
+    let uploads = ir.resources.iter().find(|r| r.name == "Uploads").unwrap();
+    assert!(
+        matches!(uploads.metadata.as_ref(), Some(ResourceIr::Object(_, x)) if x.contains_key("AWS::CloudFormation::Designer"))
+    );
+}
+
+#[test]
+fn test_sub_escaped_literal() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Instance": {
+                "Type": "AWS::EC2::Instance",
+                "Properties": {
+                    "ImageId": "ami-0abcdef1234567890",
+                    "UserData": {"Fn::Base64": {"Fn::Sub": "echo ${!HOME} in ${AWS::Region}"}}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let user_data = ir.resources[0].properties.get("UserData").unwrap();
+    assert_eq!(
+        user_data,
+        &ResourceIr::Base64(Box::new(ResourceIr::Sub(vec![
+            ResourceIr::String("echo ".to_string()),
+            ResourceIr::String("${HOME}".to_string()),
+            ResourceIr::String(" in ".to_string()),
+            ResourceIr::Ref(Reference::new(
+                "AWS::Region",
+                Origin::PseudoParameter(PseudoParameter::Region)
+            )),
+        ])))
+    );
+    assert_eq!(
+        to_string_ir(user_data).unwrap(),
+        "cdk.Fn.base64(`echo \\${HOME} in ${ this.region }`)"
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#"{"Fn::Sub":"echo ${!HOME} in ${AWS::Region}"}"#));
+}
+
+fn stack(resource: &str, output: &str, export: &str) -> CloudformationParseTree {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            resource: {"Type": "AWS::SQS::Queue", "Properties": {}}
+        },
+        "Outputs": {
+            output: {"Value": {"Ref": resource}, "Export": {"Name": export}}
+        }
+    });
+    CloudformationParseTree::build(&template).unwrap()
+}
+
+#[test]
+fn test_merge_templates() {
+    let merged = merge(vec![
+        stack("QueueA", "QueueAUrl", "a-url"),
+        stack("QueueB", "QueueBUrl", "b-url"),
+    ])
+    .unwrap();
+    let mut names: Vec<&str> = merged
+        .resources
+        .resources
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["QueueA", "QueueB"]);
+    assert_eq!(merged.outputs.outputs.len(), 2);
+
+    let err = merge(vec![
+        stack("Queue", "QueueAUrl", "a-url"),
+        stack("Queue", "QueueBUrl", "b-url"),
+    ])
+    .unwrap_err();
+    assert!(format!("{:?}", err).contains("Logical id Queue is defined by more than one template"));
+
+    let err = merge(vec![
+        stack("QueueA", "QueueAUrl", "url"),
+        stack("QueueB", "QueueBUrl", "url"),
+    ])
+    .unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("Outputs QueueAUrl and QueueBUrl have the same export name")
+    );
+}
+
+#[test]
+fn test_sub_pseudo_parameters() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::Sub": "${AWS::StackName}-${AWS::Region}${AWS::NoValue}"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    assert_eq!(
+        ir.resources[0].properties.get("QueueName").unwrap(),
+        &ResourceIr::Sub(vec![
+            ResourceIr::Ref(Reference::new(
+                "AWS::StackName",
+                Origin::PseudoParameter(PseudoParameter::StackName)
+            )),
+            ResourceIr::String("-".to_string()),
+            ResourceIr::Ref(Reference::new(
+                "AWS::Region",
+                Origin::PseudoParameter(PseudoParameter::Region)
+            )),
+            ResourceIr::String("".to_string()),
+        ])
+    );
+
+    // Every pseudo parameter resolves the same way inside a Fn::Sub as it does in a Ref.
+    for (name, pseudo) in [
+        ("AWS::Partition", PseudoParameter::Partition),
+        ("AWS::StackId", PseudoParameter::StackId),
+        ("AWS::URLSuffix", PseudoParameter::URLSuffix),
+        ("AWS::AccountId", PseudoParameter::AccountId),
+        ("AWS::NotificationARNs", PseudoParameter::NotificationArns),
+    ] {
+        let template = serde_json::json!({
+            "Conditions": {},
+            "Mappings": {},
+            "Resources": {
+                "Queue": {
+                    "Type": "AWS::SQS::Queue",
+                    "Properties": {"QueueName": {"Fn::Sub": format!("${{{}}}", name)}}
+                }
+            }
+        });
+        let ir = translate(&template);
+        assert_eq!(
+            ir.resources[0].properties.get("QueueName").unwrap(),
+            &ResourceIr::Sub(vec![ResourceIr::Ref(Reference::new(
+                name,
+                Origin::PseudoParameter(pseudo)
+            ))])
+        );
+    }
+}
+
+#[test]
+fn test_select_index_out_of_range() {
+    let select = |index: Value, list: Value| {
+        let template = serde_json::json!({
+            "Parameters": {
+                "Subnets": {"Type": "CommaDelimitedList"}
+            },
+            "Conditions": {},
+            "Mappings": {},
+            "Resources": {
+                "Queue": {
+                    "Type": "AWS::SQS::Queue",
+                    "Properties": {"QueueName": {"Fn::Select": [index, list]}}
+                }
+            }
+        });
+        let parse_tree = CloudformationParseTree::build(&template).unwrap();
+        CloudformationProgramIr::new_from_parse_tree(&parse_tree).map(|_| ())
+    };
+
+    let err = select(serde_json::json!(5), serde_json::json!(["a", "b"])).unwrap_err();
+    assert!(format!("{:?}", err).contains("Fn::Select index 5 is out of range in Queue"));
+    assert!(select(serde_json::json!("-1"), serde_json::json!(["a", "b"])).is_err());
+    assert!(select(serde_json::json!("1"), serde_json::json!(["a", "b"])).is_ok());
+    // The length of a parameter isn't known until deploy.
+    assert!(select(serde_json::json!(5), serde_json::json!({"Ref": "Subnets"})).is_ok());
+}
+
+#[test]
+fn test_no_value_omits_property() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::If": ["IsProd", "orders", {"Ref": "AWS::NoValue"}]},
+                    "DelaySeconds": {"Ref": "AWS::NoValue"}
+                }
+            }
+        }
+    });
+    let ir = translate(&template);
+    let props = &ir.resources[0].properties;
+    assert_eq!(
+        props.get("QueueName").unwrap(),
+        &ResourceIr::If(
+            "IsProd".to_string(),
+            Box::new(ResourceIr::String("orders".to_string())),
+            Box::new(ResourceIr::NoValue)
+        )
+    );
+    assert!(!props.contains_key("DelaySeconds"));
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains(r#"queueName:(isProd)?"orders":undefined,"#));
+    assert!(!output.contains("delaySeconds"));
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""QueueName":{"Fn::If":["IsProd","orders",{"Ref":"AWS::NoValue"}]}"#));
+    assert!(!output.contains("DelaySeconds"));
+
+    // A literal null is kept as it is, rather than read as AWS::NoValue.
+    let template = serde_json::json!({
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "RedrivePolicy": {
+                        "deadLetterTargetArn": null,
+                        "maxReceiveCount": {"Ref": "AWS::NoValue"}
+                    }
+                }
+            }
+        }
+    });
+    let ir = translate(&template);
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""RedrivePolicy":{"deadLetterTargetArn":null}"#));
+}
+
+#[test]
+fn test_prefer_l2_constructs() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {"BucketName": "logs"}
+            },
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {"DelaySeconds": 30}
+            },
+            "Archive": {
+                "Type": "AWS::S3::Bucket",
+                "DeletionPolicy": "Retain",
+                "UpdateReplacePolicy": "Retain"
+            },
+            "AppLogs": {"Type": "AWS::Logs::LogGroup"},
+            "Topic": {"Type": "AWS::SNS::Topic", "DeletionPolicy": "Retain"}
+        }
+    });
+    let ir = translate(&template);
+    let mut synthesizer = TypescriptSynthesizer::new("NoctStack", false);
+    synthesizer.prefer_l2 = true;
+    let output = synthesizer.synthesize(&ir);
+    // An L2 bucket is retained by default, where CloudFormation deletes it.
+    assert!(output.contains(concat!(
+        "let bucket = new s3.Bucket(this, 'Bucket', {\n",
+        "\tbucketName:\"logs\",\n",
+        "\tremovalPolicy:cdk.RemovalPolicy.DESTROY,\n",
+        "});\n"
+    )));
+    assert!(output.contains(concat!(
+        "let archive = new s3.Bucket(this, 'Archive', {\n",
+        "\tremovalPolicy:cdk.RemovalPolicy.RETAIN,\n",
+        "});\n"
+    )));
+    assert!(!output.contains("archive.cfnOptions"));
+    assert!(output.contains(concat!(
+        "let appLogs = new logs.LogGroup(this, 'AppLogs', {\n",
+        "\tremovalPolicy:cdk.RemovalPolicy.DESTROY,\n",
+        "\tretention:logs.RetentionDays.INFINITE,\n",
+        "});\n"
+    )));
+    // DelaySeconds is a cdk.Duration on the L2, so the queue stays an L1.
+    assert!(output.contains("let queue = new sqs.CfnQueue(this, 'Queue', {"));
+    // Nor does a Topic, which has no removalPolicy to keep it from being deleted.
+    assert!(output.contains("let topic = new sns.CfnTopic(this, 'Topic', {"));
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("let bucket = new s3.CfnBucket(this, 'Bucket', {"));
+}
+
+#[test]
+fn test_unknown_property_error() {
+    let translate_err = |properties: Value| {
+        let template = serde_json::json!({
+            "Conditions": {},
+            "Mappings": {},
+            "Resources": {
+                "Logs": {
+                    "Type": "AWS::S3::Bucket",
+                    "Properties": properties
+                }
+            }
+        });
+        let parse_tree = CloudformationParseTree::build(&template).unwrap();
+        match CloudformationProgramIr::new_from_parse_tree(&parse_tree) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => err.to_string(),
+        }
+    };
+
+    assert_eq!(
+        translate_err(serde_json::json!({"Baz": "x"})),
+        r#"Resources.Logs.Properties.Baz: unknown property "Baz" on AWS::S3::Bucket in Logs"#
+    );
+    assert_eq!(
+        translate_err(serde_json::json!({"VersioningConfiguration": {"Baz": "x"}})),
+        r#"Resources.Logs.Properties.VersioningConfiguration.Baz: unknown property "Baz" on AWS::S3::Bucket.VersioningConfiguration in Logs"#
+    );
+}
+
+#[test]
+fn test_unknown_resource_type_error() {
+    let template = serde_json::json!({
+        "Resources": {
+            "Widget": {
+                "Type": "AWS::Foo::Bar",
+                "Properties": {"Size": 3}
+            }
+        }
+    });
+
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    match CloudformationProgramIr::new_from_parse_tree(&parse_tree) {
+        Ok(_) => panic!("AWS::Foo::Bar is not a resource type"),
+        Err(err) => assert_eq!(
+            err.to_string(),
+            r#"Resources.Widget: unknown resource type "AWS::Foo::Bar" in Widget"#
+        ),
+    }
+}
+
+#[test]
+fn test_sub_unescaped_shell_variable() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Instance": {
+                "Type": "AWS::EC2::Instance",
+                "Properties": {
+                    "ImageId": "ami-0abcdef1234567890",
+                    "UserData": {"Fn::Base64": {"Fn::Sub": "#!/bin/bash\ncd ${HOME}\ncat <<EOF > region\n${AWS::Region}\nEOF\n"}}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    assert_eq!(
+        ir.warnings,
+        vec!["Instance: Fn::Sub variable ${HOME} could not be resolved, if it is a shell variable it has to be escaped as ${!HOME}"]
+    );
+}
+
+#[test]
+fn test_depends_on_forms() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {"Type": "AWS::SQS::Queue", "Properties": {}},
+            "Bucket": {"Type": "AWS::S3::Bucket", "Properties": {}},
+            "Single": {
+                "Type": "AWS::SNS::Topic",
+                "DependsOn": "Queue",
+                "Properties": {}
+            },
+            "List": {
+                "Type": "AWS::SNS::Topic",
+                "DependsOn": ["Queue", "Bucket"],
+                "Properties": {}
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let depends_on = |name: &str| {
+        ir.resources
+            .iter()
+            .find(|r| r.name == name)
+            .unwrap()
+            .depends_on
+            .clone()
+    };
+    assert_eq!(depends_on("Single"), vec!["Queue".to_string()]);
+    assert_eq!(
+        depends_on("List"),
+        vec!["Queue".to_string(), "Bucket".to_string()]
+    );
+    assert!(depends_on("Queue").is_empty());
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("single.addDependency(queue);\n"));
+    assert!(output.contains("list.addDependency(queue);\nlist.addDependency(bucket);\n"));
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn test_wasm_transmute() {
+    let template = "\
+Resources:
+  Queue:
+    Type: AWS::SQS::Queue
+    Properties:
+      QueueName: !Sub ${AWS::StackName}-queue
+";
+    let output = noctilucent::wasm::transmute(template).unwrap();
+    assert!(output.contains(r#""QueueName":{"Fn::Sub":"${AWS::StackName}-queue"}"#));
+
+    let err = noctilucent::wasm::transmute("{").unwrap_err();
+    assert!(err.to_string().starts_with("Template could not be read"));
+}
+
+#[test]
+fn test_deletion_policies() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "DeletionPolicy": "Retain",
+                "UpdateReplacePolicy": "RetainExceptOnCreate"
+            },
+            "Database": {
+                "Type": "AWS::RDS::DBInstance",
+                "DeletionPolicy": "Snapshot",
+                "Properties": {"DBInstanceClass": "db.t3.micro"}
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let bucket = ir.resources.iter().find(|r| r.name == "Bucket").unwrap();
+    assert_eq!(bucket.deletion_policy, Some("Retain".to_string()));
+    assert_eq!(
+        bucket.update_replace_policy,
+        Some("RetainExceptOnCreate".to_string())
+    );
+    assert!(bucket.properties.is_empty());
+    let database = ir.resources.iter().find(|r| r.name == "Database").unwrap();
+    assert_eq!(database.deletion_policy, Some("Snapshot".to_string()));
+    assert_eq!(database.update_replace_policy, None);
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("bucket.cfnOptions.deletionPolicy = cdk.CfnDeletionPolicy.RETAIN;"));
+    assert!(output.contains(
+        "bucket.cfnOptions.updateReplacePolicy = cdk.CfnDeletionPolicy.RETAIN_EXCEPT_ON_CREATE;"
+    ));
+    assert!(output.contains("database.cfnOptions.deletionPolicy = cdk.CfnDeletionPolicy.SNAPSHOT;"));
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""DeletionPolicy":"Retain","Type":"AWS::S3::Bucket","UpdateReplacePolicy":"RetainExceptOnCreate""#));
+}
+
+#[test]
+fn test_if_branches_of_different_types() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "RedrivePolicy": {"Fn::If": [
+                        "IsProd",
+                        "{\"maxReceiveCount\": 5}",
+                        {"maxReceiveCount": 3}
+                    ]}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let policy = ir.resources[0].properties.get("RedrivePolicy").unwrap();
+    match policy {
+        ResourceIr::If(condition, true_expr, false_expr) => {
+            assert_eq!(condition, "IsProd");
+            assert_eq!(
+                true_expr.as_ref(),
+                &ResourceIr::String("{\"maxReceiveCount\": 5}".to_string())
+            );
+            match false_expr.as_ref() {
+                ResourceIr::Object(_, o) => {
+                    assert_eq!(o.get("maxReceiveCount"), Some(&ResourceIr::Number(3)))
+                }
+                x => panic!("expected an object, found {:?}", x),
+            }
+        }
+        x => panic!("expected an Fn::If, found {:?}", x),
+    }
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(
+        r#""RedrivePolicy":{"Fn::If":["IsProd","{\"maxReceiveCount\": 5}",{"maxReceiveCount":3}]}"#
+    ));
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(
+        output.contains(r#"redrivePolicy:(isProd)?"{\"maxReceiveCount\": 5}":{"#),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn test_metadata_with_sub() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Instance": {
+                "Type": "AWS::EC2::Instance",
+                "Metadata": {
+                    "AWS::CloudFormation::Init": {
+                        "config": {
+                            "files": {
+                                "/etc/region": {"content": {"Fn::Sub": "${AWS::Region}"}}
+                            }
+                        }
+                    }
+                },
+                "Properties": {"ImageId": "ami-0abcdef1234567890"}
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let metadata = match ir.resources[0].metadata.as_ref().unwrap() {
+        ResourceIr::Object(Complexity::Simple(SimpleType::Json), x) => x,
+        x => panic!("expected the metadata to be a json object, found {:?}", x),
+    };
+    let content = match metadata.get("AWS::CloudFormation::Init").unwrap() {
+        ResourceIr::Object(_, init) => match init.get("config").unwrap() {
+            ResourceIr::Object(_, config) => match config.get("files").unwrap() {
+                ResourceIr::Object(_, files) => match files.get("/etc/region").unwrap() {
+                    ResourceIr::Object(_, file) => file.get("content").unwrap(),
+                    x => panic!("unexpected {:?}", x),
+                },
+                x => panic!("unexpected {:?}", x),
+            },
+            x => panic!("unexpected {:?}", x),
+        },
+        x => panic!("unexpected {:?}", x),
+    };
+    assert_eq!(
+        content,
+        &ResourceIr::Sub(vec![ResourceIr::Ref(Reference::new(
+            "AWS::Region",
+            Origin::PseudoParameter(PseudoParameter::Region)
+        ))])
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""Metadata":{"AWS::CloudFormation::Init":{"config":{"files":{"/etc/region":{"content":{"Fn::Sub":"${AWS::Region}"}}}}}}"#));
+}
+
+#[test]
+fn test_format_options() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "Tags": [{"Key": "team", "Value": "storage"}]
+                }
+            }
+        }
+    });
+    let ir = translate(&template);
+    let tags = ir.resources[0].properties.get("Tags").unwrap();
+    let format = |multiline: bool, trailing_comma: bool| {
+        let format = FormatOptions {
+            multiline,
+            trailing_comma,
+        };
+        to_string_ir_formatted(tags, SubStyle::TemplateLiteral, &format).unwrap()
+    };
+
+    // Object keys are in no particular order, so either may come first.
+    let expected = |template: &str| {
+        vec![
+            template
+                .replace("{0}", "key: \"team\"")
+                .replace("{1}", "value: \"storage\""),
+            template
+                .replace("{0}", "value: \"storage\"")
+                .replace("{1}", "key: \"team\""),
+        ]
+    };
+    assert!(expected("[{{0},\n{1}}]").contains(&format(true, false)));
+    assert!(expected("[{{0},\n{1},},]").contains(&format(true, true)));
+    assert!(expected("[{{0}, {1}}]").contains(&format(false, false)));
+    assert!(expected("[{{0}, {1},},]").contains(&format(false, true)));
+    assert_eq!(format(true, false), to_string_ir(tags).unwrap());
+
+    let mut synthesizer = TypescriptSynthesizer::new("NoctStack", false);
+    synthesizer.format = FormatOptions {
+        multiline: false,
+        trailing_comma: true,
+    };
+    let output = synthesizer.synthesize(&ir);
+    assert!(expected("\ttags:[{{0}, {1},},],\n")
+        .iter()
+        .any(|x| output.contains(x)));
+}
+
+#[test]
+fn test_security_group_ingress_rules() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Port": {"Type": "Number", "Default": "8080"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Group": {
+                "Type": "AWS::EC2::SecurityGroup",
+                "Properties": {
+                    "GroupDescription": "web",
+                    "SecurityGroupIngress": [
+                        {
+                            "IpProtocol": "tcp",
+                            "FromPort": 443,
+                            "ToPort": 443,
+                            "CidrIp": "0.0.0.0/0"
+                        },
+                        {
+                            "IpProtocol": "tcp",
+                            "FromPort": {"Ref": "Port"},
+                            "ToPort": {"Ref": "Port"},
+                            "CidrIp": "10.0.0.0/8",
+                            "Description": {"Fn::Sub": "app in ${AWS::Region}"}
+                        }
+                    ]
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let rules = match ir.resources[0]
+        .properties
+        .get("SecurityGroupIngress")
+        .unwrap()
+    {
+        ResourceIr::Array(_, rules) => rules,
+        x => panic!("expected an array, found {:?}", x),
+    };
+    assert_eq!(rules.len(), 2);
+    let rule = |i: usize| match &rules[i] {
+        ResourceIr::Object(Complexity::Complex(x), rule) => {
+            assert_eq!(x, "Ingress");
+            rule
+        }
+        x => panic!("expected a complex object, found {:?}", x),
+    };
+    assert_eq!(rule(0).get("FromPort"), Some(&ResourceIr::Number(443)));
+    assert_eq!(
+        rule(1).get("FromPort"),
+        Some(&ResourceIr::Ref(Reference::new("Port", Origin::Parameter)))
+    );
+    assert_eq!(
+        rule(1).get("Description"),
+        Some(&ResourceIr::Sub(vec![
+            ResourceIr::String("app in ".to_string()),
+            ResourceIr::Ref(Reference::new(
+                "AWS::Region",
+                Origin::PseudoParameter(PseudoParameter::Region)
+            )),
+        ]))
+    );
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("fromPort: props.port"));
+    assert!(output.contains("description: `app in ${ this.region }`"));
+}
+
+#[test]
+fn test_translate_outputs() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {"Type": "AWS::SQS::Queue", "Properties": {}}
+        },
+        "Outputs": {
+            "QueueArn": {
+                "Description": "the queue",
+                "Value": {"Fn::GetAtt": ["Queue", "Arn"]},
+                "Export": {"Name": {"Fn::Sub": "${AWS::StackName}-QueueArn"}}
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    assert_eq!(ir.outputs.len(), 1);
+    let output = &ir.outputs[0];
+    assert_eq!(output.name, "QueueArn");
+    assert_eq!(output.condition, None);
+    assert_eq!(output.description, Some("the queue".to_string()));
+    assert_eq!(
+        output.value,
+        ResourceIr::GetAtt("Queue".to_string(), "Arn".to_string())
+    );
+    assert_eq!(
+        output.export_name,
+        Some(ResourceIr::Sub(vec![
+            ResourceIr::Ref(Reference::new(
+                "AWS::StackName",
+                Origin::PseudoParameter(PseudoParameter::StackName)
+            )),
+            ResourceIr::String("-QueueArn".to_string()),
+        ]))
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""Outputs":{"QueueArn":{"Description":"the queue","Export":{"Name":{"Fn::Sub":"${AWS::StackName}-QueueArn"}},"Value":{"Fn::GetAtt":["Queue","Arn"]}}}"#));
+}
+
+#[test]
+fn test_translate_parameters() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {
+                "Type": "String",
+                "Default": "dev",
+                "AllowedValues": ["dev", "prod"],
+                "Description": "where it is deployed"
+            },
+            "Retention": {"Type": "Number", "Default": 14},
+            "Subnets": {"Type": "CommaDelimitedList", "Default": "a, b"},
+            "Password": {"Type": "String", "NoEcho": "true"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {"Type": "AWS::SQS::Queue", "Properties": {}}
+        }
+    });
+
+    let ir = translate(&template);
+    let names: Vec<&str> = ir.parameters.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(names, vec!["Password", "Retention", "Stage", "Subnets"]);
+
+    let password = &ir.parameters[0];
+    assert!(password.no_echo);
+    assert_eq!(password.default, None);
+
+    let retention = &ir.parameters[1];
+    assert_eq!(retention.parameter_type, "Number");
+    assert_eq!(retention.default, Some(ResourceIr::Number(14)));
+    assert!(!retention.no_echo);
+
+    let stage = &ir.parameters[2];
+    assert_eq!(stage.parameter_type, "String");
+    assert_eq!(stage.default, Some(ResourceIr::String("dev".to_string())));
+    assert_eq!(
+        stage.allowed_values,
+        Some(vec!["dev".to_string(), "prod".to_string()])
+    );
+    assert_eq!(stage.description, Some("where it is deployed".to_string()));
+
+    let subnets = &ir.parameters[3];
+    assert!(subnets.is_list());
+    assert_eq!(
+        subnets.default,
+        Some(ResourceIr::Array(
+            Complexity::Simple(SimpleType::String),
+            vec![
+                ResourceIr::String("a".to_string()),
+                ResourceIr::String("b".to_string())
+            ]
+        ))
+    );
+}
+
+#[test]
+fn test_resources_of_type() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Logs": {"Type": "AWS::S3::Bucket", "Properties": {}},
+            "Queue": {"Type": "AWS::SQS::Queue", "Properties": {}},
+            "Assets": {"Type": "AWS::S3::Bucket", "Properties": {}}
+        }
+    });
+
+    let ir = translate(&template);
+    let mut buckets: Vec<&str> = resources_of_type(&ir.resources, "AWS::S3::Bucket")
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect();
+    buckets.sort_unstable();
+    assert_eq!(buckets, vec!["Assets", "Logs"]);
+    assert_eq!(resources_of_type(&ir.resources, "AWS::SQS::Queue").len(), 1);
+    assert!(resources_of_type(&ir.resources, "AWS::SNS::Topic").is_empty());
+}
+
+#[test]
+fn test_fix_property_casing() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {
+                    "bucketName": "logs",
+                    "VersioningConfiguration": {"status": "Enabled"}
+                }
+            }
+        }
+    });
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    assert!(CloudformationProgramIr::new_from_parse_tree(&parse_tree).is_err());
+
+    let options = TranslationOptions {
+        fix_property_casing: true,
+        ..TranslationOptions::default()
+    };
+    let ir = match CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options)
+    {
+        Ok(ir) => ir,
+        Err(e) => panic!("{:?}", e),
+    };
+    let props = &ir.resources[0].properties;
+    assert_eq!(
+        props.get("BucketName"),
+        Some(&ResourceIr::String("logs".to_string()))
+    );
+    assert!(!props.contains_key("bucketName"));
+    match props.get("VersioningConfiguration") {
+        Some(ResourceIr::Object(_, x)) => assert!(x.contains_key("Status")),
+        x => panic!("expected an object, found {:?}", x),
+    }
+    let mut warnings = ir.warnings.clone();
+    warnings.sort();
+    assert_eq!(
+        warnings,
+        vec![
+            "bucketName in Bucket was corrected to BucketName".to_string(),
+            "status in Bucket was corrected to Status".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_translate_mappings() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {
+            "RegionMap": {
+                "us-east-1": {"AMI": "ami-0ff8a91507f77f867", "Zones": ["us-east-1a", "us-east-1b"]},
+                "eu-west-1": {"AMI": "ami-047bb4163c506cd98", "Zones": ["eu-west-1a", "eu-west-1b"]}
+            },
+            "Sizes": {"dev": {"Memory": 128, "Cpu": 0.25}},
+            "Empty": {"dev": {}}
+        },
+        "Resources": {
+            "Queue": {"Type": "AWS::SQS::Queue", "Properties": {}}
+        }
+    });
+
+    let ir = translate(&template);
+    assert_eq!(ir.mappings.len(), 3);
+    let mapping = |name: &str| ir.mappings.iter().find(|m| m.name == name).unwrap();
+    let sizes = mapping("Sizes").map.get("dev").unwrap();
+    assert_eq!(sizes.get("Memory"), Some(&ResourceIr::Number(128)));
+    assert_eq!(sizes.get("Cpu"), Some(&ResourceIr::Double(0.25)));
+    assert!(mapping("Empty").find_first_type().is_none());
+
+    let mapping = mapping("RegionMap");
+    assert_eq!(mapping.map.len(), 2);
+    let us_east_1 = mapping.map.get("us-east-1").unwrap();
+    assert_eq!(
+        us_east_1.get("AMI"),
+        Some(&ResourceIr::String("ami-0ff8a91507f77f867".to_string()))
+    );
+    assert_eq!(
+        us_east_1.get("Zones"),
+        Some(&ResourceIr::Array(
+            Complexity::Simple(SimpleType::String),
+            vec![
+                ResourceIr::String("us-east-1a".to_string()),
+                ResourceIr::String("us-east-1b".to_string())
+            ]
+        ))
+    );
+    let eu_west_1 = mapping.map.get("eu-west-1").unwrap();
+    assert_eq!(
+        eu_west_1.get("AMI"),
+        Some(&ResourceIr::String("ami-047bb4163c506cd98".to_string()))
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(
+        r#""eu-west-1":{"AMI":"ami-047bb4163c506cd98","Zones":["eu-west-1a","eu-west-1b"]}"#
+    ));
+    assert!(output.contains(r#""Sizes":{"dev":{"Cpu":0.25,"Memory":128}}"#));
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("\t\t\"Zones\": [\"eu-west-1a\", \"eu-west-1b\"]"));
+    assert!(output.contains("const sizes: Record<string, Record<string, number>> = {"));
+    assert!(output.contains("\t\t\"Memory\": 128"));
+}
+
+#[test]
+fn test_topological_order() {
+    let chain = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Alarm": {
+                "Type": "AWS::CloudWatch::Alarm",
+                "Properties": {"AlarmActions": [{"Ref": "Topic"}]}
+            },
+            "Queue": {"Type": "AWS::SQS::Queue"},
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "DependsOn": "Queue",
+                "Properties": {"DisplayName": {"Fn::GetAtt": ["Queue", "QueueName"]}}
+            }
+        }
+    });
+    let ir = translate(&chain);
+    let order: Vec<&str> = topological_order(&ir.resources)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    assert_eq!(order, vec!["Queue", "Topic", "Alarm"]);
+
+    let cycle = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Alarm": {
+                "Type": "AWS::CloudWatch::Alarm",
+                "Properties": {"AlarmActions": [{"Ref": "Topic"}]}
+            },
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {"QueueName": {"Fn::GetAtt": ["Topic", "TopicName"]}}
+            },
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "DependsOn": "Queue"
+            }
+        }
+    });
+    let ir = translate(&cycle);
+    match topological_order(&ir.resources) {
+        Ok(_) => panic!("expected the cycle between Queue and Topic to be rejected"),
+        Err(e) => assert_eq!(
+            e.to_string(),
+            "Resources Queue, Topic depend on each other in a cycle"
+        ),
+    }
+}
+
+#[test]
+fn test_change_plan() {
+    let old = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Function": {
+                "Type": "AWS::Lambda::Function",
+                "Properties": {
+                    "Code": {"ZipFile": "exports.handler = () => {}"},
+                    "Handler": "index.handler",
+                    "Runtime": "nodejs14.x",
+                    "Role": {"Fn::GetAtt": ["OldRole", "Arn"]}
+                }
+            },
+            "OldRole": {
+                "Type": "AWS::IAM::Role",
+                "Properties": {"AssumeRolePolicyDocument": {"Version": "2012-10-17"}}
+            }
+        }
+    });
+    let new = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Function": {
+                "Type": "AWS::Lambda::Function",
+                "Properties": {
+                    "Code": {"ZipFile": "exports.handler = () => {}"},
+                    "Handler": "index.handler",
+                    "Runtime": "nodejs14.x",
+                    "Role": {"Fn::GetAtt": ["Role", "Arn"]}
+                }
+            },
+            "Role": {
+                "Type": "AWS::IAM::Role",
+                "Properties": {"AssumeRolePolicyDocument": {"Version": "2012-10-17"}}
+            }
+        }
+    });
+
+    let plan = change_plan(&translate(&old), &translate(&new));
+    assert_eq!(
+        plan,
+        vec![
+            ChangeStep::Create("Role".to_string()),
+            ChangeStep::Update("Function".to_string()),
+            ChangeStep::Delete("OldRole".to_string()),
+        ]
+    );
+    assert!(change_plan(&translate(&new), &translate(&new)).is_empty());
+}
+
+#[test]
+fn test_custom_resources() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Provider": {
+                "Type": "AWS::Lambda::Function",
+                "Properties": {"Handler": "index.handler"}
+            },
+            "Seed": {
+                "Type": "Custom::Foo",
+                "Properties": {
+                    "ServiceToken": {"Fn::GetAtt": ["Provider", "Arn"]},
+                    "Settings": {"table_name": "orders", "Rows": [1, 2]}
+                }
+            },
+            "Legacy": {
+                "Type": "AWS::CloudFormation::CustomResource",
+                "Properties": {
+                    "ServiceToken": {"Fn::GetAtt": ["Provider", "Arn"]},
+                    "Mode": "fast"
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let seed = ir.resources.iter().find(|r| r.name == "Seed").unwrap();
+    assert!(seed.todos.is_empty());
+    assert!(seed.properties.contains_key("ServiceToken"));
+    assert!(seed.properties.contains_key("Settings"));
+    let legacy = ir.resources.iter().find(|r| r.name == "Legacy").unwrap();
+    assert!(legacy.properties.contains_key("Mode"));
+    assert!(!ir.imports.iter().any(|x| x.name == "foo"));
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains(concat!(
+        "let seed = new cdk.CfnResource(this, 'Seed', {\n",
+        "\ttype: 'Custom::Foo',\n",
+        "\tproperties: {\n",
+    )));
+    assert!(output.contains("\t\tServiceToken:provider.attrArn,\n"));
+    assert!(output.contains(r#""table_name": "orders""#));
+    assert!(output.contains("let legacy = new cdk.CfnResource(this, 'Legacy', {"));
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""ServiceToken":{"Fn::GetAtt":["Provider","Arn"]}"#));
+    assert!(output.contains(r#""table_name":"orders""#));
+}
+
+#[test]
+fn test_find_in_map_subnet_list() {
+    let template = |second_key: &str| {
+        serde_json::json!({
+            "Parameters": {
+                "Stage": {"Type": "String"}
+            },
+            "Conditions": {},
+            "Mappings": {
+                "Network": {
+                    "prod": {"SubnetIds": ["subnet-1a", "subnet-1b"]},
+                    "dev": {"SubnetIds": ["subnet-2a"]}
+                }
+            },
+            "Resources": {
+                "SubnetGroup": {
+                    "Type": "AWS::RDS::DBSubnetGroup",
+                    "Properties": {
+                        "DBSubnetGroupDescription": "database subnets",
+                        "SubnetIds": {"Fn::FindInMap": ["Network", {"Ref": "Stage"}, second_key]}
+                    }
+                }
+            }
+        })
+    };
+
+    let ir = translate(&template("SubnetIds"));
+    let group = ir
+        .resources
+        .iter()
+        .find(|r| r.name == "SubnetGroup")
+        .unwrap();
+    match group.properties.get("SubnetIds") {
+        Some(ResourceIr::Map(mapper, _, second)) => {
+            assert_eq!(mapper.as_ref(), &ResourceIr::String("Network".to_string()));
+            assert_eq!(
+                second.as_ref(),
+                &ResourceIr::String("SubnetIds".to_string())
+            );
+        }
+        x => panic!("expected a Map, found {:?}", x),
+    }
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(
+        output.contains(r#""SubnetIds":{"Fn::FindInMap":["Network",{"Ref":"Stage"},"SubnetIds"]}"#)
+    );
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("\tsubnetIds:network[props.stage][\"SubnetIds\"],\n"));
+
+    let parse_tree = CloudformationParseTree::build(&template("Subnets")).unwrap();
+    match CloudformationProgramIr::new_from_parse_tree(&parse_tree) {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => assert!(format!("{:?}", err)
+            .contains("Fn::FindInMap refers to missing key Subnets in mapping Network")),
+    }
+}
+
+#[test]
+fn test_floating_point_numbers() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Policy": {
+                "Type": "AWS::AutoScaling::ScalingPolicy",
+                "Properties": {
+                    "AutoScalingGroupName": "workers",
+                    "PolicyType": "TargetTrackingScaling",
+                    "TargetTrackingConfiguration": {
+                        "PredefinedMetricSpecification": {
+                            "PredefinedMetricType": "ASGAverageCPUUtilization"
+                        },
+                        "TargetValue": 0.5
+                    }
+                }
+            },
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {"DelaySeconds": 3}
+            },
+            "Seed": {
+                "Type": "Custom::Seed",
+                "Properties": {
+                    "ServiceToken": "arn:aws:lambda:us-east-1:123456789012:function:seed",
+                    "Rows": 9999999999999_i64,
+                    "Ratio": 1.0,
+                    "Limit": 18446744073709551615_u64
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let policy = ir.resources.iter().find(|r| r.name == "Policy").unwrap();
+    match policy.properties.get("TargetTrackingConfiguration") {
+        Some(ResourceIr::Object(_, o)) => {
+            assert_eq!(o.get("TargetValue"), Some(&ResourceIr::Double(0.5)))
+        }
+        x => panic!("expected an Object, found {:?}", x),
+    }
+    let queue = ir.resources.iter().find(|r| r.name == "Queue").unwrap();
+    assert_eq!(
+        queue.properties.get("DelaySeconds"),
+        Some(&ResourceIr::Number(3))
+    );
+    let seed = ir.resources.iter().find(|r| r.name == "Seed").unwrap();
+    assert_eq!(
+        seed.properties.get("Rows"),
+        Some(&ResourceIr::Number(9999999999999))
+    );
+    assert_eq!(seed.properties.get("Ratio"), Some(&ResourceIr::Double(1.0)));
+    assert_eq!(
+        seed.properties.get("Limit"),
+        Some(&ResourceIr::String("18446744073709551615".into()))
+    );
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("targetValue: 0.5"));
+    assert!(output.contains("\tdelaySeconds:3,\n"));
+    assert!(output.contains("\t\tRows:9999999999999,\n"));
+    assert!(output.contains("\t\tRatio:1.0,\n"));
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""TargetValue":0.5"#));
+    assert!(output.contains(r#""DelaySeconds":3"#));
+    assert!(output.contains(r#""Rows":9999999999999"#));
+    assert!(output.contains(r#""Ratio":1.0"#));
+    assert!(output.contains(r#""Limit":"18446744073709551615""#));
+}
+
+#[test]
+fn test_nested_stack_exports() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {"Type": "AWS::SQS::Queue", "Properties": {}}
+        },
+        "Outputs": {
+            "QueueArn": {
+                "Value": {"Fn::GetAtt": ["Queue", "Arn"]},
+                "Export": {"Name": "orders-queue-arn"}
+            },
+            "QueueUrl": {
+                "Value": {"Ref": "Queue"}
+            }
+        }
+    });
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    let warning = "Output QueueArn has an Export, which nested stacks should not use, the parent stack can read the output instead";
+
+    let ir = CloudformationProgramIr::new_from_parse_tree(&parse_tree).unwrap();
+    assert!(!ir.warnings.iter().any(|x| x.starts_with("Output")));
+
+    let options = TranslationOptions {
+        is_nested: true,
+        ..Default::default()
+    };
+    let ir =
+        CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options).unwrap();
+    let exports: Vec<&String> = ir
+        .warnings
+        .iter()
+        .filter(|x| x.starts_with("Output"))
+        .collect();
+    assert_eq!(exports, vec![warning]);
+}
+
+#[test]
+fn test_coerce_primitive_strings() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Versioned": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "FifoQueue": "true",
+                    "ContentBasedDeduplication": {"Ref": "Versioned"},
+                    "DelaySeconds": "8080",
+                    "MaximumMessageSize": "large",
+                    "QueueName": "8080"
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let properties = &ir.resources[0].properties;
+    assert_eq!(properties.get("FifoQueue"), Some(&ResourceIr::Bool(true)));
+    assert!(matches!(
+        properties.get("ContentBasedDeduplication"),
+        Some(ResourceIr::Ref(_))
+    ));
+    assert_eq!(
+        properties.get("DelaySeconds"),
+        Some(&ResourceIr::Number(8080))
+    );
+    // Strings that aren't the declared type are left for CloudFormation to reject.
+    assert_eq!(
+        properties.get("MaximumMessageSize"),
+        Some(&ResourceIr::String("large".to_string()))
+    );
+    assert_eq!(
+        properties.get("QueueName"),
+        Some(&ResourceIr::String("8080".to_string()))
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""DelaySeconds":8080"#));
+    assert!(output.contains(r#""FifoQueue":true"#));
+}
+
+#[test]
+fn test_double_properties() {
+    let template = |target_value: Value| {
+        serde_json::json!({
+            "Conditions": {},
+            "Mappings": {},
+            "Resources": {
+                "Policy": {
+                    "Type": "AWS::AutoScaling::ScalingPolicy",
+                    "Properties": {
+                        "AutoScalingGroupName": "workers",
+                        "PolicyType": "TargetTrackingScaling",
+                        "TargetTrackingConfiguration": {
+                            "PredefinedMetricSpecification": {
+                                "PredefinedMetricType": "ASGAverageCPUUtilization"
+                            },
+                            "TargetValue": target_value
+                        }
+                    }
+                }
+            }
+        })
+    };
+    let target_value = |ir: &CloudformationProgramIr| match ir.resources[0]
+        .properties
+        .get("TargetTrackingConfiguration")
+    {
+        Some(ResourceIr::Object(_, o)) => o.get("TargetValue").cloned(),
+        x => panic!("expected an Object, found {:?}", x),
+    };
+    let warning = r#""0.75" in Policy is a string, but was read as the number 0.75 as its property is a Double"#;
+
+    let ir = translate(&template(serde_json::json!(0.75)));
+    assert_eq!(target_value(&ir), Some(ResourceIr::Double(0.75)));
+    assert!(!ir.warnings.iter().any(|x| x == warning));
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""TargetValue":0.75"#));
+
+    let ir = translate(&template(serde_json::json!("0.75")));
+    assert_eq!(target_value(&ir), Some(ResourceIr::Double(0.75)));
+    assert!(ir.warnings.iter().any(|x| x == warning));
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("targetValue: 0.75"));
+
+    // Whole numbers are read as integers, which a Double accepts.
+    let ir = translate(&template(serde_json::json!("50")));
+    assert_eq!(target_value(&ir), Some(ResourceIr::Number(50)));
+}
+
+#[test]
+fn test_transmute() {
+    let template = "\
+Parameters:
+  Stage:
+    Type: String
+    Default: dev
+  RetentionDays:
+    Type: Number
+Conditions:
+  IsProd: !Equals [!Ref Stage, prod]
+Mappings:
+  Sizes:
+    dev:
+      Memory: 128
+Resources:
+  Queue:
+    Type: AWS::SQS::Queue
+    Properties:
+      QueueName: !Sub ${Stage}-queue
+  Logs:
+    Type: AWS::Logs::LogGroup
+    Condition: IsProd
+    Properties:
+      RetentionInDays: !Ref RetentionDays
+Outputs:
+  QueueUrl:
+    Value: !Ref Queue
+";
+    let ir = noctilucent::transmute(template).unwrap();
+    assert_eq!(ir.parameters.len(), 2);
+    assert_eq!(ir.conditions.len(), 1);
+    assert_eq!(ir.mappings.len(), 1);
+    assert_eq!(ir.resources.len(), 2);
+    assert_eq!(ir.outputs.len(), 1);
+
+    let json = serde_json::to_string(&serde_json::json!({
+        "Resources": {"Queue": {"Type": "AWS::SQS::Queue", "Properties": {"DelaySeconds": 5}}}
+    }))
+    .unwrap();
+    let ir = noctilucent::transmute(&json).unwrap();
+    assert_eq!(ir.resources.len(), 1);
+    assert!(ir.parameters.is_empty());
+    assert!(ir.conditions.is_empty());
+    assert!(ir.mappings.is_empty());
+    assert!(ir.outputs.is_empty());
+
+    let ir = noctilucent::transmute("Resources:\n  B:\n    Type: AWS::S3::Bucket\n").unwrap();
+    assert_eq!(ir.resources.len(), 1);
+
+    for (template, error) in [
+        ("Conditions: {}", "Template has no Resources"),
+        ("Resources: []", "Resources must be an object"),
+    ] {
+        match noctilucent::transmute(template) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => assert_eq!(err.to_string(), error),
+        }
+    }
+
+    match noctilucent::transmute("Resources: [") {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => assert!(err.to_string().starts_with("Template could not be read")),
+    }
+}
+
+#[test]
+fn test_inline_single_use_parameters() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "QueueName": {"Type": "String", "Default": "orders"},
+            "Delay": {"Type": "Number", "Default": 5},
+            "Stage": {"Type": "String", "Default": "dev"},
+            "Secret": {"Type": "String", "Default": "hunter2", "NoEcho": true},
+            "Retention": {"Type": "Number"},
+            "Shared": {"Type": "String", "Default": "shared"}
+        },
+        "Conditions": {
+            "IsProd": {"Fn::Equals": [{"Ref": "Stage"}, "prod"]}
+        },
+        "Mappings": {},
+        "Resources": {
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "QueueName": {"Fn::Sub": "${QueueName}-${Stage}"},
+                    "DelaySeconds": {"Ref": "Delay"},
+                    "MessageRetentionPeriod": {"Ref": "Retention"},
+                    "Tags": [
+                        {"Key": "secret", "Value": {"Ref": "Secret"}},
+                        {"Key": "shared", "Value": {"Ref": "Shared"}},
+                        {"Key": "also-shared", "Value": {"Ref": "Shared"}}
+                    ]
+                }
+            }
+        }
+    });
+
+    let mut ir = translate(&template);
+    ir.inline_single_use_parameters();
+    let names: Vec<&str> = ir.parameters.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(names, vec!["Retention", "Secret", "Shared", "Stage"]);
+    let mut inputs: Vec<&str> = ir
+        .constructor
+        .inputs
+        .iter()
+        .map(|x| x.logical_name.as_str())
+        .collect();
+    inputs.sort_unstable();
+    assert_eq!(inputs, vec!["Retention", "Secret", "Shared", "Stage"]);
+    assert!(ir.warnings.contains(
+        &"Parameter Delay was only used once, so it was replaced with its default".to_string()
+    ));
+
+    let properties = &ir.resources[0].properties;
+    assert_eq!(properties.get("DelaySeconds"), Some(&ResourceIr::Number(5)));
+    assert_eq!(
+        properties.get("QueueName"),
+        Some(&ResourceIr::Sub(vec![
+            ResourceIr::String("orders".to_string()),
+            ResourceIr::String("-".to_string()),
+            ResourceIr::Ref(Reference::new("Stage", Origin::Parameter)),
+        ]))
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""QueueName":{"Fn::Sub":"orders-${Stage}"}"#));
+    assert!(output.contains(r#""DelaySeconds":5"#));
+}
+
+#[test]
+fn test_task_definition_container_environment() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Task": {
+                "Type": "AWS::ECS::TaskDefinition",
+                "Properties": {
+                    "ContainerDefinitions": [{
+                        "Name": "web",
+                        "Image": "nginx",
+                        "Environment": [
+                            {"Name": "STAGE", "Value": {"Ref": "Stage"}},
+                            {"Name": "REGION", "Value": {"Fn::Sub": "${AWS::Region}"}}
+                        ],
+                        "PortMappings": [{"ContainerPort": "80", "Protocol": "tcp"}]
+                    }]
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let containers = match ir.resources[0].properties.get("ContainerDefinitions") {
+        Some(ResourceIr::Array(_, x)) => x,
+        x => panic!("expected an Array, found {:?}", x),
+    };
+    let container = match &containers[0] {
+        ResourceIr::Object(Complexity::Complex(_), o) => o,
+        x => panic!("expected a complex Object, found {:?}", x),
+    };
+    match container.get("Environment") {
+        Some(ResourceIr::Array(_, env)) => match &env[0] {
+            ResourceIr::Object(Complexity::Complex(_), o) => assert_eq!(
+                o.get("Value"),
+                Some(&ResourceIr::Ref(Reference::new("Stage", Origin::Parameter)))
+            ),
+            x => panic!("expected a complex Object, found {:?}", x),
+        },
+        x => panic!("expected an Array, found {:?}", x),
+    }
+    match container.get("PortMappings") {
+        Some(ResourceIr::Array(_, ports)) => match &ports[0] {
+            ResourceIr::Object(_, o) => {
+                assert_eq!(o.get("ContainerPort"), Some(&ResourceIr::Number(80)))
+            }
+            x => panic!("expected an Object, found {:?}", x),
+        },
+        x => panic!("expected an Array, found {:?}", x),
+    }
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("value: props.stage"));
+    assert!(output.contains("containerPort: 80"));
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""Environment":[{"Name":"STAGE","Value":{"Ref":"Stage"}},{"Name":"REGION","Value":{"Fn::Sub":"${AWS::Region}"}}]"#));
+    assert!(output.contains(r#""PortMappings":[{"ContainerPort":80,"Protocol":"tcp"}]"#));
+}
+
+#[test]
+fn test_collect_translation_errors() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {
+                "Type": "AWS::S3::Bucket",
+                "Properties": {"BucketNam": "logs"}
+            },
+            "Queue": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "DelaySeconds": 5,
+                    "QueueName": {"Fn::Select": ["3", ["a", "b"]]}
+                }
+            }
+        }
+    });
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+
+    let errors =
+        match translates_resources_collecting(&parse_tree, spec(), &ResourcesContext::default()) {
+            Ok(_) => panic!("expected errors"),
+            Err(errors) => errors,
+        };
+    let errors: Vec<String> = errors.iter().map(|x| x.to_string()).collect();
+    assert_eq!(
+        errors,
+        vec![
+            r#"Resources.Bucket.Properties.BucketNam: unknown property "BucketNam" on AWS::S3::Bucket in Bucket"#,
+            "Resources.Queue.Properties.QueueName.Fn::Select: Fn::Select index 3 is out of range in Queue",
+        ]
+    );
+
+    // Without collecting, translation still stops at the first error.
+    match CloudformationProgramIr::new_from_parse_tree(&parse_tree) {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => assert!(err
+            .to_string()
+            .starts_with(r#"Resources.Bucket.Properties.BucketNam: unknown property "BucketNam""#)),
+    }
+}
+
+#[test]
+fn test_undeclared_language_extensions() {
+    let template = |transform: Value| {
+        serde_json::json!({
+            "Transform": transform,
+            "Parameters": {
+                "Subnets": {"Type": "CommaDelimitedList"}
+            },
+            "Conditions": {},
+            "Mappings": {},
+            "Resources": {
+                "Queue": {
+                    "Type": "AWS::SQS::Queue",
+                    "Properties": {
+                        "DelaySeconds": {"Fn::Length": {"Ref": "Subnets"}}
+                    }
+                }
+            }
+        })
+    };
+    let warning = "Fn::Length is only available with the AWS::LanguageExtensions transform, which the template doesn't declare";
+
+    let parse_tree = CloudformationParseTree::build(&template(Value::Null)).unwrap();
+    assert_eq!(parse_tree.warnings, vec![warning]);
+
+    let parse_tree =
+        CloudformationParseTree::build(&template(serde_json::json!("AWS::LanguageExtensions")))
+            .unwrap();
+    assert!(parse_tree.warnings.is_empty());
+    let parse_tree = CloudformationParseTree::build(&template(serde_json::json!([
+        "AWS::LanguageExtensions",
+        "AWS::Serverless-2016-10-31"
+    ])))
+    .unwrap();
+    assert!(parse_tree.warnings.is_empty());
+}
+
+#[test]
+fn test_error_paths() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Queue": {"Type": "AWS::SQS::Queue", "Properties": {}},
+            "Topic": {
+                "Type": "AWS::SNS::Topic",
+                "Properties": {
+                    "Subscription": [{
+                        "Protocol": "sqs",
+                        "Endpoint": {"Fn::GetAtt": [{"Ref": "Queue"}, "Arn"]}
+                    }]
+                }
+            }
+        }
+    });
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+
+    let err = match CloudformationProgramIr::new_from_parse_tree(&parse_tree) {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => err,
+    };
+    assert_eq!(
+        err.path(),
+        [
+            "Resources",
+            "Topic",
+            "Properties",
+            "Subscription",
+            "0",
+            "Endpoint",
+            "Fn::GetAtt"
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "Resources.Topic.Properties.Subscription.0.Endpoint.Fn::GetAtt: Get attribute first element must be a string"
+    );
+
+    // Errors found while reading the template have no path.
+    let err = CloudformationParseTree::build_from_str("{", &ParseOptions::default()).unwrap_err();
+    assert!(err.path().is_empty());
+    assert!(err.to_string().starts_with("Template could not be read"));
+}
+
+#[test]
+fn test_yaml_duplicate_logical_id() {
+    let template = "
+Parameters:
+  Queue:
+    Type: String
+Resources:
+  Queue:
+    Type: AWS::SQS::Queue
+";
+    let err = CloudformationParseTree::build_from_yaml_str(template, &ParseOptions::default())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Logical id Queue is declared more than once"
+    );
+}
+
+#[test]
+fn test_tag_value_from_join() {
+    let template = serde_json::json!({
+        "Parameters": {
+            "Stage": {"Type": "String"}
+        },
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Jobs": {
+                "Type": "AWS::SQS::Queue",
+                "Properties": {
+                    "Tags": [{
+                        "Key": "owner",
+                        "Value": {"Fn::Join": ["-", [{"Ref": "Stage"}, "jobs", {"Ref": "AWS::Region"}]]}
+                    }]
+                }
+            },
+            "Template": {
+                "Type": "AWS::EC2::LaunchTemplate",
+                "Properties": {
+                    "LaunchTemplateData": {
+                        "TagSpecifications": [{
+                            "ResourceType": "instance",
+                            "Tags": [{
+                                "Key": "queue",
+                                "Value": {"Fn::Join": ["/", [{"Fn::GetAtt": ["Jobs", "QueueName"]}, "worker"]]}
+                            }]
+                        }]
+                    }
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let jobs = ir.resources.iter().find(|r| r.name == "Jobs").unwrap();
+    let tag = match jobs.properties.get("Tags") {
+        Some(ResourceIr::Array(Complexity::Complex(_), arr)) => match &arr[0] {
+            ResourceIr::Object(Complexity::Complex(x), o) if x == "Tag" => o,
+            x => panic!("expected a Tag, found {:?}", x),
+        },
+        x => panic!("expected a list of tags, found {:?}", x),
+    };
+    assert_eq!(
+        tag.get("Value"),
+        Some(&ResourceIr::Join(
+            "-".to_string(),
+            vec![ResourceIr::Array(
+                Complexity::Simple(SimpleType::String),
+                vec![
+                    ResourceIr::Ref(Reference::new("Stage", Origin::Parameter)),
+                    ResourceIr::String("jobs".to_string()),
+                    ResourceIr::Ref(Reference::new(
+                        "AWS::Region",
+                        Origin::PseudoParameter(PseudoParameter::Region)
+                    )),
+                ]
+            )]
+        ))
+    );
+
+    let output = TypescriptSynthesizer::new("NoctStack", false).synthesize(&ir);
+    assert!(output.contains("value: [props.stage,\n\"jobs\",\nthis.region].join(\"-\")"));
+    assert!(output.contains("value: [jobs.attrQueueName,\n\"worker\"].join(\"/\")"));
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(
+        r#""Tags":[{"Key":"owner","Value":{"Fn::Join":["-",[{"Ref":"Stage"},"jobs",{"Ref":"AWS::Region"}]]}}]"#
+    ));
+    assert!(output.contains(
+        r#""Tags":[{"Key":"queue","Value":{"Fn::Join":["/",[{"Fn::GetAtt":["Jobs","QueueName"]},"worker"]]}}]"#
+    ));
+}
+
+#[test]
+fn test_sub_ref_and_get_att_of_one_resource() {
+    let template = serde_json::json!({
+        "Conditions": {},
+        "Mappings": {},
+        "Resources": {
+            "Bucket": {"Type": "AWS::S3::Bucket", "Properties": {}},
+            "Config": {
+                "Type": "AWS::SSM::Parameter",
+                "Properties": {
+                    "Type": "String",
+                    "Value": {"Fn::Sub": "${Bucket} ${Bucket.Arn}"}
+                }
+            }
+        }
+    });
+
+    let ir = translate(&template);
+    let config = ir.resources.iter().find(|r| r.name == "Config").unwrap();
+    assert_eq!(
+        config.properties.get("Value"),
+        Some(&ResourceIr::Sub(vec![
+            ResourceIr::Ref(Reference::new("Bucket", Origin::LogicalId)),
+            ResourceIr::String(" ".to_string()),
+            ResourceIr::GetAtt("Bucket".to_string(), "Arn".to_string()),
+        ]))
+    );
+    assert_eq!(
+        resource_dependencies(config)
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["Bucket".to_string()]
+    );
+
+    let output = JsonSynthesizer::new(false).output(&ir);
+    assert!(output.contains(r#""Value":{"Fn::Sub":"${Bucket} ${Bucket.Arn}"}"#));
+}
+
+#[test]
+fn test_dangling_references() {
+    let template = |alarm_action: &str| {
+        serde_json::json!({
+            "Conditions": {},
+            "Mappings": {},
+            "Resources": {
+                // Alarm is read before Topic, which it refers to.
+                "Alarm": {
+                    "Type": "AWS::CloudWatch::Alarm",
+                    "Properties": {
+                        "AlarmActions": [{"Ref": alarm_action}],
+                        "ComparisonOperator": "GreaterThanThreshold",
+                        "EvaluationPeriods": 1,
+                        "Threshold": 1
+                    }
+                },
+                "Topic": {"Type": "AWS::SNS::Topic", "Properties": {}}
+            },
+            "Outputs": {
+                "Missing": {"Value": {"Fn::GetAtt": ["Queue", "Arn"]}}
+            }
+        })
+    };
+    let options = TranslationOptions {
+        check_references: true,
+        ..Default::default()
+    };
+
+    // Without the check, a misspelled reference is translated like any other.
+    let parse_tree = CloudformationParseTree::build(&template("Topc")).unwrap();
+    assert!(CloudformationProgramIr::new_from_parse_tree(&parse_tree).is_ok());
+
+    match CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options) {
+        Ok(_) => panic!("expected an error"),
+        Err(err) => assert_eq!(
+            err.to_string(),
+            "Alarm refers to Topc, which is not a resource in the template, did you mean Topic?; Missing refers to Queue, which is not a resource in the template"
+        ),
+    }
+
+    let mut template = template("Topic");
+    template["Outputs"]["Missing"]["Value"] =
+        serde_json::json!({"Fn::GetAtt": ["Topic", "TopicName"]});
+    let parse_tree = CloudformationParseTree::build(&template).unwrap();
+    assert!(
+        CloudformationProgramIr::new_from_parse_tree_with_options(&parse_tree, &options).is_ok()
+    );
+}